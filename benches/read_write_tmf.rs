@@ -4,7 +4,7 @@ fn read_tmf(c: &mut Criterion) {
     use tmf::TMFMesh;
     let mut file = std::fs::File::open("target/test_res/susan.tmf").unwrap();
     let mut out = Vec::new();
-    file.read_to_end(&mut out);
+    file.read_to_end(&mut out).unwrap();
     c.bench_function("read susan.tmf", |b| {
         b.iter(|| {
             let r_mesh = TMFMesh::read_tmf_one(&mut (&out as &[u8])).unwrap();
@@ -17,7 +17,7 @@ fn read_unified_tmf(c: &mut Criterion) {
     use tmf::TMFMesh;
     let mut file = std::fs::File::open("target/test_res/susan_unified.tmf").unwrap();
     let mut out = Vec::new();
-    file.read_to_end(&mut out);
+    file.read_to_end(&mut out).unwrap();
     c.bench_function("read unified susan.tmf", |b| {
         b.iter(|| {
             let r_mesh = TMFMesh::read_tmf_one(&mut (&out as &[u8])).unwrap();
@@ -33,7 +33,7 @@ fn read_nefreti_tmf(c: &mut Criterion) {
         Err(_) => return,
     };
     let mut out = Vec::new();
-    file.read_to_end(&mut out);
+    file.read_to_end(&mut out).unwrap();
     c.bench_function("read nefretiti.tmf", |b| {
         b.iter(|| {
             let r_mesh = TMFMesh::read_tmf_one(&mut (&out as &[u8])).unwrap();
@@ -47,9 +47,9 @@ fn write_tmf(c: &mut Criterion) {
     use tmf::TMFPrecisionInfo;
     let mut file = std::fs::File::open("testing/susan.tmf").unwrap();
     let mut out = Vec::new();
-    file.read_to_end(&mut out);
+    file.read_to_end(&mut out).unwrap();
     let r_mesh = TMFMesh::read_tmf_one(&mut (&out as &[u8])).unwrap().0;
-    let mut prec = TMFPrecisionInfo::default();
+    let prec = TMFPrecisionInfo::default();
     c.bench_function("write susan.tmf", |b| {
         b.iter(|| {
             out.clear();
@@ -1,4 +1,4 @@
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{criterion_group, criterion_main, Criterion};
 use tmf::unaligned_rw::{UnalignedRWMode, UnalignedReader, UnalignedWriter};
 fn unaligned_read(c: &mut Criterion) {
     c.bench_function("unaligned_read", |b| {
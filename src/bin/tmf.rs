@@ -0,0 +1,365 @@
+//! Command-line tool for converting, inspecting, verifying and comparing TMF files.
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use std::process::ExitCode;
+use tmf::{
+    ColorPrecisionMode, FloatType, NormalPrecisionMode, TMFMesh, TMFPrecisionInfo,
+    TangentPrecisionMode, TriangleEncodingMode, UvPrecisionMode, VertexPrecisionMode,
+};
+const USAGE: &str = "Usage:
+    tmf convert <INPUT> <OUTPUT> [PRECISION FLAGS]  Converts between .obj and .tmf files, based on file extensions.
+    tmf info <FILE.tmf>                             Prints meshes and segments inside a .tmf file.
+    tmf verify <FILE>                               Checks integrity of all meshes in a .obj or .tmf file.
+    tmf diff <A> <B> [--tolerance <DIST>]           Compares meshes in two .obj or .tmf files.
+
+Precision flags:
+    --vertex-precision <FRACTION>     Maximal vertex deviation, as a portion of the shortest edge.
+    --normal-precision <DEGREES>      Maximal normal deviation, in degrees.
+    --uv-resolution <PIXELS>          Texture resolution used to calculate UV precision.
+    --uv-pixel-deviation <PIXELS>     Maximal UV deviation, in pixels of the texture.
+    --tangent-precision <DEGREES>     Maximal tangent deviation, in degrees.
+    --color-bits <BITS>               Bits used to save each vertex color channel.
+    --triangle-encoding <MODE>        Encodings allowed for triangles: standard, predictive or connectivity.";
+/// Error reported to the user, before exiting with a failure exit code.
+type CliResult<T> = Result<T, String>;
+/// Command line arguments, split into positional arguments and `--flag value` pairs.
+struct Args {
+    positional: Vec<String>,
+    flags: Vec<(String, String)>,
+    /// Set if `--help` or `-h` was passed, which takes no value.
+    help: bool,
+}
+impl Args {
+    fn parse<I: Iterator<Item = String>>(mut args: I) -> CliResult<Self> {
+        let mut positional = Vec::new();
+        let mut flags = Vec::new();
+        let mut help = false;
+        while let Some(arg) = args.next() {
+            if arg == "--help" || arg == "-h" {
+                help = true;
+                continue;
+            }
+            match arg.strip_prefix("--") {
+                Some(flag) => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("Flag --{flag} requires a value"))?;
+                    flags.push((flag.to_owned(), value));
+                }
+                None => positional.push(arg),
+            }
+        }
+        Ok(Self {
+            positional,
+            flags,
+            help,
+        })
+    }
+    fn positional(&self, index: usize, name: &str) -> CliResult<&str> {
+        self.positional
+            .get(index)
+            .map(String::as_str)
+            .ok_or_else(|| format!("Missing argument <{name}>"))
+    }
+    fn flag<T: std::str::FromStr>(&self, name: &str) -> CliResult<Option<T>> {
+        match self.flags.iter().rev().find(|(flag, _)| flag == name) {
+            Some((_, value)) => value
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("Invalid value '{value}' of flag --{name}")),
+            None => Ok(None),
+        }
+    }
+    fn check_flags(&self, allowed: &[&str]) -> CliResult<()> {
+        match self
+            .flags
+            .iter()
+            .find(|(flag, _)| !allowed.contains(&flag.as_str()))
+        {
+            Some((flag, _)) => Err(format!("Unknown flag --{flag}")),
+            None => Ok(()),
+        }
+    }
+    fn precision_info(&self) -> CliResult<TMFPrecisionInfo> {
+        let mut p_info = TMFPrecisionInfo::default();
+        if let Some(prec) = self.flag::<FloatType>("vertex-precision")? {
+            p_info.vertex_precision = VertexPrecisionMode(prec);
+        }
+        if let Some(deg) = self.flag::<FloatType>("normal-precision")? {
+            p_info.normal_precision = NormalPrecisionMode::from_deg_dev(deg);
+        }
+        let uv_resolution = self.flag::<f32>("uv-resolution")?;
+        let uv_deviation = self.flag::<f32>("uv-pixel-deviation")?;
+        if uv_resolution.is_some() || uv_deviation.is_some() {
+            p_info.uv_prec = UvPrecisionMode::form_texture_resolution(
+                uv_resolution.unwrap_or(1024.0),
+                uv_deviation.unwrap_or(0.1),
+            );
+        }
+        if let Some(deg) = self.flag::<FloatType>("tangent-precision")? {
+            p_info.tangent_prec = TangentPrecisionMode::from_deg_dev(deg);
+        }
+        if let Some(bits) = self.flag::<u8>("color-bits")? {
+            p_info.color_prec = ColorPrecisionMode::from_bits(bits);
+        }
+        if let Some(mode) = self.flag::<String>("triangle-encoding")? {
+            p_info.triangle_encoding = match mode.as_str() {
+                "standard" => TriangleEncodingMode::Standard,
+                "predictive" => TriangleEncodingMode::Predictive,
+                "connectivity" => TriangleEncodingMode::Connectivity,
+                _ => {
+                    return Err(format!(
+                        "Invalid value '{mode}' of flag --triangle-encoding"
+                    ))
+                }
+            };
+        }
+        Ok(p_info)
+    }
+}
+const PRECISION_FLAGS: &[&str] = &[
+    "vertex-precision",
+    "normal-precision",
+    "uv-resolution",
+    "uv-pixel-deviation",
+    "tangent-precision",
+    "color-bits",
+    "triangle-encoding",
+];
+fn is_tmf(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("tmf"))
+}
+fn read_meshes(path: &str) -> CliResult<Vec<(TMFMesh, String)>> {
+    let file = File::open(path).map_err(|err| format!("Could not open '{path}': {err}"))?;
+    let mut reader = BufReader::new(file);
+    if is_tmf(path) {
+        TMFMesh::read_tmf(&mut reader).map_err(|err| format!("Could not read '{path}': {err}"))
+    } else {
+        TMFMesh::read_from_obj(&mut reader).map_err(|err| format!("Could not read '{path}': {err}"))
+    }
+}
+fn convert(args: &Args) -> CliResult<()> {
+    args.check_flags(PRECISION_FLAGS)?;
+    let input = args.positional(1, "INPUT")?;
+    let output = args.positional(2, "OUTPUT")?;
+    let p_info = args.precision_info()?;
+    let meshes = read_meshes(input)?;
+    let file = File::create(output).map_err(|err| format!("Could not create '{output}': {err}"))?;
+    let mut writer = BufWriter::new(file);
+    if is_tmf(output) {
+        TMFMesh::write_tmf(&meshes, &mut writer, &p_info)
+            .map_err(|err| format!("Could not write '{output}': {err}"))
+    } else {
+        TMFMesh::write_obj(&meshes, &mut writer)
+            .map_err(|err| format!("Could not write '{output}': {err}"))
+    }
+}
+fn info(args: &Args) -> CliResult<()> {
+    args.check_flags(&[])?;
+    let path = args.positional(1, "FILE")?;
+    let report =
+        tmf::inspect_file(path).map_err(|err| format!("Could not inspect '{path}': {err}"))?;
+    println!(
+        "TMF version {}.{} (requires reader {}.{}), {} meshes, {} bytes of segment data",
+        report.version.0,
+        report.version.1,
+        report.min_version.0,
+        report.min_version.1,
+        report.meshes.len(),
+        report.total_byte_len()
+    );
+    for mesh in &report.meshes {
+        println!("Mesh '{}': {} bytes", mesh.name, mesh.total_byte_len());
+        for seg in &mesh.segments {
+            let count = seg
+                .element_count
+                .map_or_else(|| "?".to_owned(), |count| count.to_string());
+            println!(
+                "    {:?}: {} bytes, {:?} compression, {} elements, precision bits {:?}",
+                seg.seg_type, seg.byte_len, seg.compression_type, count, seg.precision_bits
+            );
+        }
+    }
+    Ok(())
+}
+fn verify(args: &Args) -> CliResult<()> {
+    args.check_flags(&[])?;
+    let path = args.positional(1, "FILE")?;
+    let meshes = read_meshes(path)?;
+    let mut failed = 0;
+    for (mesh, name) in &meshes {
+        match mesh.verify() {
+            Ok(()) => println!("Mesh '{name}': OK"),
+            Err(err) => {
+                println!("Mesh '{name}': {err}");
+                failed += 1;
+            }
+        }
+    }
+    if failed == 0 {
+        Ok(())
+    } else {
+        Err(format!("{failed} of {} meshes are invalid", meshes.len()))
+    }
+}
+/// Returns the maximal distance between vertices used by corresponding triangle corners of *a* and *b*, or `None` if triangles do not match.
+fn max_vertex_deviation(a: &TMFMesh, b: &TMFMesh) -> Option<FloatType> {
+    let (a_vertices, a_triangles) = (a.get_vertices()?, a.get_vertex_triangles()?);
+    let (b_vertices, b_triangles) = (b.get_vertices()?, b.get_vertex_triangles()?);
+    if a_triangles.len() != b_triangles.len() {
+        return None;
+    }
+    let mut max_dev: FloatType = 0.0;
+    for (a_index, b_index) in a_triangles.iter().zip(b_triangles.iter()) {
+        let a_vertex = a_vertices.get(*a_index as usize)?;
+        let b_vertex = b_vertices.get(*b_index as usize)?;
+        let (dx, dy, dz) = (
+            a_vertex.0 - b_vertex.0,
+            a_vertex.1 - b_vertex.1,
+            a_vertex.2 - b_vertex.2,
+        );
+        max_dev = max_dev.max((dx * dx + dy * dy + dz * dz).sqrt());
+    }
+    Some(max_dev)
+}
+/// Compares two meshes, printing all differences. Returns `true` if the meshes match.
+fn diff_mesh(name: &str, a: &TMFMesh, b: &TMFMesh, tolerance: FloatType) -> bool {
+    let mut matches = true;
+    let counts = [
+        (
+            "vertices",
+            a.get_vertices().map(<[_]>::len),
+            b.get_vertices().map(<[_]>::len),
+        ),
+        (
+            "normals",
+            a.get_normals().map(<[_]>::len),
+            b.get_normals().map(<[_]>::len),
+        ),
+        (
+            "uvs",
+            a.get_uvs().map(<[_]>::len),
+            b.get_uvs().map(<[_]>::len),
+        ),
+        (
+            "vertex triangles",
+            a.get_vertex_triangles().map(<[_]>::len),
+            b.get_vertex_triangles().map(<[_]>::len),
+        ),
+        (
+            "normal triangles",
+            a.get_normal_triangles().map(<[_]>::len),
+            b.get_normal_triangles().map(<[_]>::len),
+        ),
+        (
+            "uv triangles",
+            a.get_uv_triangles().map(<[_]>::len),
+            b.get_uv_triangles().map(<[_]>::len),
+        ),
+    ];
+    for (kind, a_count, b_count) in counts {
+        if a_count != b_count {
+            println!("Mesh '{name}': {kind} differ: {a_count:?} != {b_count:?}");
+            matches = false;
+        }
+    }
+    match max_vertex_deviation(a, b) {
+        Some(dev) if dev > tolerance => {
+            println!("Mesh '{name}': vertices deviate by up to {dev}, over tolerance {tolerance}");
+            matches = false;
+        }
+        Some(dev) => println!("Mesh '{name}': vertices deviate by up to {dev}"),
+        None => (),
+    }
+    matches
+}
+fn diff(args: &Args) -> CliResult<()> {
+    args.check_flags(&["tolerance"])?;
+    let a_path = args.positional(1, "A")?;
+    let b_path = args.positional(2, "B")?;
+    let tolerance = args.flag::<FloatType>("tolerance")?.unwrap_or(0.01);
+    let a_meshes = read_meshes(a_path)?;
+    let b_meshes = read_meshes(b_path)?;
+    let mut differences = 0;
+    for (a_mesh, name) in &a_meshes {
+        match b_meshes.iter().find(|(_, b_name)| b_name == name) {
+            Some((b_mesh, _)) => {
+                if !diff_mesh(name, a_mesh, b_mesh, tolerance) {
+                    differences += 1;
+                }
+            }
+            None => {
+                println!("Mesh '{name}': only in '{a_path}'");
+                differences += 1;
+            }
+        }
+    }
+    for (_, name) in &b_meshes {
+        if !a_meshes.iter().any(|(_, a_name)| a_name == name) {
+            println!("Mesh '{name}': only in '{b_path}'");
+            differences += 1;
+        }
+    }
+    if differences == 0 {
+        Ok(())
+    } else {
+        Err(format!("{differences} meshes differ"))
+    }
+}
+fn run<I: Iterator<Item = String>>(args: I) -> CliResult<()> {
+    let args = Args::parse(args)?;
+    if args.help {
+        println!("{USAGE}");
+        return Ok(());
+    }
+    match args.positional.first().map(String::as_str) {
+        Some("convert") => convert(&args),
+        Some("info") => info(&args),
+        Some("verify") => verify(&args),
+        Some("diff") => diff(&args),
+        Some("help") | None => {
+            println!("{USAGE}");
+            Ok(())
+        }
+        Some(command) => Err(format!("Unknown command '{command}'\n{USAGE}")),
+    }
+}
+fn main() -> ExitCode {
+    match run(std::env::args().skip(1)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{err}");
+            ExitCode::FAILURE
+        }
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    fn run_args(args: &[&str]) -> CliResult<()> {
+        run(args.iter().map(|arg| (*arg).to_owned()))
+    }
+    #[test]
+    fn help() {
+        for flag in ["--help", "-h"] {
+            let args = Args::parse([flag.to_owned()].into_iter()).unwrap();
+            assert!(args.help);
+            run_args(&[flag]).unwrap();
+            run_args(&["convert", flag]).unwrap();
+        }
+    }
+    #[test]
+    fn convert_and_diff() {
+        let dir = std::env::temp_dir();
+        let tmf_path = dir.join(format!("tmf_cli_susan_{}.tmf", std::process::id()));
+        let tmf_path = tmf_path.to_str().unwrap();
+        run_args(&["convert", "testing/susan.obj", tmf_path]).unwrap();
+        let res = run_args(&["diff", "testing/susan.obj", tmf_path]);
+        std::fs::remove_file(tmf_path).unwrap();
+        res.unwrap();
+        assert!(run_args(&["diff", "testing/susan.obj", "testing/multi_mtl.obj"]).is_err());
+    }
+}
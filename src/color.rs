@@ -0,0 +1,192 @@
+use crate::read_extension::ReadExt;
+use crate::typed_mesh::{ColorOf, MeshFloat};
+use crate::unaligned_rw::{UnalignedRWMode, UnalignedReader, UnalignedWriter};
+use crate::TMFImportError;
+use crate::MAX_SEG_SIZE;
+use crate::{FloatType, Vector4};
+use std::io::{Read, Write};
+/// Type used for representing RGBA colors. Each channel is in range 0.0 - 1.0.
+pub type Color = Vector4;
+/// Setting dictating how precisely the vertex colors should be saved, as a number of bits per color channel.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ColorPrecisionMode(u8);
+impl ColorPrecisionMode {
+    /// Creates a new [`ColorPrecisionMode`] saving each color channel using *bits* bits.
+    /// ```
+    /// # use tmf::ColorPrecisionMode;
+    /// // Same precision as 8-bit per channel textures.
+    /// let mode = ColorPrecisionMode::from_bits(8);
+    /// ```
+    pub fn from_bits(bits: u8) -> Self {
+        Self(bits.clamp(1, u64::BITS as u8 - 1))
+    }
+    /// Creates a new [`ColorPrecisionMode`] from maximal allowed deviation of each color channel.
+    /// ```
+    /// # use tmf::ColorPrecisionMode;
+    /// let mode = ColorPrecisionMode::from_max_deviation(0.01);
+    /// assert!(mode.bits() >= 6);
+    /// ```
+    pub fn from_max_deviation(deviation: FloatType) -> Self {
+        Self::from_bits((0.5 / deviation + 1.0).log2().ceil() as u8)
+    }
+    /// Returns the amount of bits used to save each color channel.
+    #[must_use]
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+}
+impl Default for ColorPrecisionMode {
+    /// Default color save precision. 8 bits per channel, the same as most common textures and displays.
+    /// ```
+    /// # use tmf::ColorPrecisionMode;
+    /// let mode = ColorPrecisionMode::from_bits(8);
+    /// let default_mode = ColorPrecisionMode::default();
+    /// // The same
+    /// assert!(mode == default_mode);
+    /// ```
+    fn default() -> Self {
+        Self::from_bits(8)
+    }
+}
+pub fn save_colors<W: Write>(
+    colors: &[Color],
+    writer: &mut W,
+    precision: ColorPrecisionMode,
+) -> std::io::Result<()> {
+    let precision = precision.0;
+    let multpiler = ((1_u64 << precision) - 1) as FloatType;
+    writer.write_all(&[precision])?;
+    writer.write_all(&(colors.len() as u64).to_le_bytes())?;
+    let precision = UnalignedRWMode::precision_bits(precision);
+    let mut writer = UnalignedWriter::new(writer);
+    let quantize = |channel: FloatType| (channel.clamp(0.0, 1.0) * multpiler).round() as u64;
+    for color in colors.iter() {
+        writer.write_unaligned(precision, quantize(color.0))?;
+        writer.write_unaligned(precision, quantize(color.1))?;
+        writer.write_unaligned(precision, quantize(color.2))?;
+        writer.write_unaligned(precision, quantize(color.3))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+pub fn read_colors<R: Read>(reader: &mut R) -> Result<Box<[Color]>, TMFImportError> {
+    let mut colors = Vec::new();
+    read_colors_into(reader, &mut colors)?;
+    Ok(colors.into())
+}
+/// Reads colors, appending them to *colors*.
+pub(crate) fn read_colors_into<F: MeshFloat, R: Read>(
+    reader: &mut R,
+    colors: &mut Vec<ColorOf<F>>,
+) -> Result<(), TMFImportError> {
+    let precision = reader.read_u8()?;
+    let count = reader.read_u64()?;
+    if count > MAX_SEG_SIZE as u64 {
+        return Err(TMFImportError::SegmentTooLong);
+    }
+    if precision == 0 {
+        colors.resize(colors.len() + count as usize, Default::default());
+        return Ok(());
+    }
+    if precision >= u64::BITS as u8 {
+        return Err(TMFImportError::InvalidPrecision(precision));
+    }
+    colors.reserve(count as usize);
+    let divisor = F::from_u64((1_u64 << precision) - 1);
+    let precision = UnalignedRWMode::precision_bits(precision);
+    let mut reader = UnalignedReader::new(reader);
+    for _ in 0..count {
+        let (r, g) = reader.read2_unaligned(precision)?;
+        let (b, a) = reader.read2_unaligned(precision)?;
+        colors.push((
+            F::from_u64(r) / divisor,
+            F::from_u64(g) / divisor,
+            F::from_u64(b) / divisor,
+            F::from_u64(a) / divisor,
+        ));
+    }
+    Ok(())
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::IndexType;
+    fn max_dev(a: Color, b: Color) -> FloatType {
+        (a.0 - b.0)
+            .abs()
+            .max((a.1 - b.1).abs())
+            .max((a.2 - b.2).abs())
+            .max((a.3 - b.3).abs())
+    }
+    #[test]
+    fn rw_colors() {
+        use rand::{thread_rng, Rng};
+        let mut rng = thread_rng();
+        let color_count = (rng.gen::<IndexType>() % 0x800) + 0x800;
+        let colors: Vec<Color> = (0..color_count)
+            .map(|_| (rng.gen(), rng.gen(), rng.gen(), rng.gen()))
+            .collect();
+        let prec = ColorPrecisionMode::from_max_deviation(0.01);
+        let mut res = Vec::with_capacity(color_count as usize);
+        save_colors(&colors, &mut res, prec).unwrap();
+        let r_colors = read_colors(&mut (&res as &[u8])).unwrap();
+        assert_eq!(r_colors.len(), colors.len());
+        for (r_color, color) in r_colors.iter().zip(colors.iter()) {
+            assert!(
+                max_dev(*r_color, *color) <= 0.01,
+                "{r_color:?} {color:?} {}",
+                max_dev(*r_color, *color)
+            );
+        }
+    }
+    #[test]
+    fn rw_8bit_colors_exact() {
+        let colors: Vec<Color> = (0..=255)
+            .map(|c| {
+                let c = c as FloatType / 255.0;
+                (c, 1.0 - c, c, 1.0)
+            })
+            .collect();
+        let mut res = Vec::new();
+        save_colors(&colors, &mut res, ColorPrecisionMode::default()).unwrap();
+        let r_colors = read_colors(&mut (&res as &[u8])).unwrap();
+        for (r_color, color) in r_colors.iter().zip(colors.iter()) {
+            assert!(max_dev(*r_color, *color) < 0.0001);
+        }
+    }
+    #[test]
+    #[cfg(feature = "obj_import")]
+    fn rw_susan_tmf_colors() {
+        use crate::{TMFMesh, TMFPrecisionInfo};
+        let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+        let (mut tmf_mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+        let vertices = tmf_mesh.get_vertices().unwrap();
+        let colors: Vec<Color> = vertices
+            .iter()
+            .map(|v| {
+                (
+                    v.0.abs().min(1.0),
+                    v.1.abs().min(1.0),
+                    v.2.abs().min(1.0),
+                    1.0,
+                )
+            })
+            .collect();
+        let color_triangles = tmf_mesh.get_vertex_triangles().unwrap().to_vec();
+        tmf_mesh.set_colors(colors.clone());
+        tmf_mesh.set_color_triangles(color_triangles.clone());
+        tmf_mesh.verify().unwrap();
+        let mut out = Vec::new();
+        tmf_mesh
+            .write_tmf_one(&mut out, &TMFPrecisionInfo::default(), name)
+            .unwrap();
+        let (r_mesh, _) = TMFMesh::read_tmf_one(&mut (&out as &[u8])).unwrap();
+        r_mesh.verify().unwrap();
+        assert_eq!(r_mesh.get_color_triangles().unwrap(), &color_triangles[..]);
+        let r_colors = r_mesh.get_colors().unwrap();
+        assert_eq!(r_colors.len(), colors.len());
+        for (r_color, color) in r_colors.iter().zip(colors.iter()) {
+            assert!(max_dev(*r_color, *color) < 0.005);
+        }
+    }
+}
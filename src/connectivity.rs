@@ -0,0 +1,416 @@
+use crate::delta::{best_order, read_code, unzigzag, write_code, zigzag};
+use crate::read_extension::ReadExt;
+use crate::typed_mesh::{index_from_u64, MeshIndex};
+use crate::unaligned_rw::{UnalignedRWMode, UnalignedReader, UnalignedWriter};
+use crate::{IndexType, TMFImportError, MAX_SEG_SIZE};
+use std::collections::{HashSet, VecDeque};
+/// Amount of bits used to store the position of an edge in the edge FIFO.
+const EDGE_BITS: u8 = 4;
+/// Amount of bits used to store the position of a vertex in the vertex FIFO.
+const VERTEX_BITS: u8 = 4;
+/// Biggest FIFO size a reader accepts, in bits of the position inside it.
+const MAX_FIFO_BITS: u8 = 16;
+const ROTATION_PREC: UnalignedRWMode = UnalignedRWMode::precision_bits(2);
+/// Checks if *triangles* form an edge-manifold, consistently oriented mesh: no triangle is degenerate, and no directed edge is used by more than one triangle.
+/// Only such meshes are saved using the connectivity coder.
+pub(crate) fn is_edge_manifold(triangles: &[IndexType]) -> bool {
+    if triangles.is_empty() || triangles.len() % 3 != 0 {
+        return false;
+    }
+    let mut edges = HashSet::with_capacity(triangles.len());
+    triangles.chunks_exact(3).all(|triangle| {
+        let (a, b, c) = (triangle[0], triangle[1], triangle[2]);
+        a != b
+            && b != c
+            && c != a
+            && edges.insert((a, b))
+            && edges.insert((b, c))
+            && edges.insert((c, a))
+    })
+}
+/// How a single vertex of a triangle is saved.
+enum VertexCode {
+    /// Vertex is one bigger than the biggest vertex seen so far.
+    New,
+    /// Vertex is at this position in the vertex FIFO.
+    Cached(usize),
+    /// Zigzag encoded difference between the vertex and the next new vertex.
+    Explicit(u64),
+}
+/// How a single triangle is saved.
+enum TriangleCode {
+    /// Triangle shares the edge at this position in the edge FIFO(in the opposite direction). The rotation says which corner of the triangle begins the shared edge.
+    Edge(usize, usize, VertexCode),
+    /// Triangle shares no recent edge, and all its vertices are saved.
+    Triangle([VertexCode; 3]),
+}
+/// State of the coder, updated the same way by the encoder and the decoder after each triangle.
+struct CoderState {
+    edges: VecDeque<(u64, u64)>,
+    edge_capacity: usize,
+    vertices: VecDeque<u64>,
+    vertex_capacity: usize,
+    next: u64,
+}
+impl CoderState {
+    fn new(edge_bits: u8, vertex_bits: u8) -> Self {
+        let edge_capacity = 1 << edge_bits;
+        let vertex_capacity = 1 << vertex_bits;
+        Self {
+            edges: VecDeque::with_capacity(edge_capacity + 3),
+            edge_capacity,
+            vertices: VecDeque::with_capacity(vertex_capacity + 3),
+            vertex_capacity,
+            next: 0,
+        }
+    }
+    /// Returns the position of an edge *triangle* shares with a recent triangle, and the rotation of *triangle*, at which the shared edge begins.
+    fn find_edge(&self, triangle: [u64; 3]) -> Option<(usize, usize)> {
+        self.edges.iter().enumerate().find_map(|(slot, edge)| {
+            (0..3)
+                .find(|rotation| *edge == (triangle[(rotation + 1) % 3], triangle[*rotation]))
+                .map(|rotation| (slot, rotation))
+        })
+    }
+    fn vertex_code(&self, vertex: u64) -> VertexCode {
+        if vertex == self.next {
+            VertexCode::New
+        } else if let Some(slot) = self.vertices.iter().position(|cached| *cached == vertex) {
+            VertexCode::Cached(slot)
+        } else {
+            VertexCode::Explicit(zigzag(vertex as i64 - self.next as i64))
+        }
+    }
+    fn see(&mut self, vertex: u64) {
+        self.next = self.next.max(vertex + 1);
+    }
+    /// Updates the FIFOs with *triangle*. *shared* is the position and rotation of the shared edge, if any.
+    fn update(&mut self, triangle: [u64; 3], shared: Option<(usize, usize)>) {
+        let rotation = match shared {
+            Some((slot, rotation)) => {
+                // In a manifold mesh, no other triangle can use this edge.
+                self.edges.remove(slot);
+                rotation
+            }
+            None => {
+                self.edges.push_front((triangle[0], triangle[1]));
+                0
+            }
+        };
+        let (a, b, c) = (
+            triangle[rotation],
+            triangle[(rotation + 1) % 3],
+            triangle[(rotation + 2) % 3],
+        );
+        self.edges.push_front((b, c));
+        self.edges.push_front((c, a));
+        self.edges.truncate(self.edge_capacity);
+        for vertex in triangle {
+            if !self.vertices.contains(&vertex) {
+                self.vertices.push_front(vertex);
+            }
+        }
+        self.vertices.truncate(self.vertex_capacity);
+    }
+}
+fn write_vertex<W: std::io::Write>(
+    writer: &mut UnalignedWriter<W>,
+    code: &VertexCode,
+    vertex_prec: UnalignedRWMode,
+    order: u8,
+) -> std::io::Result<()> {
+    match code {
+        VertexCode::New => writer.write_bit(true),
+        VertexCode::Cached(slot) => {
+            writer.write_bit(false)?;
+            writer.write_bit(true)?;
+            writer.write_unaligned(vertex_prec, *slot as u64)
+        }
+        VertexCode::Explicit(delta) => {
+            writer.write_bit(false)?;
+            writer.write_bit(false)?;
+            write_code(writer, *delta, order)
+        }
+    }
+}
+/// Saves *triangles*, which must be edge-manifold(see [`is_edge_manifold`]), using the connectivity coder.
+/// Like in Edgebreaker, each triangle is described by how it attaches to the already saved ones, but triangles and their corners keep their order,
+/// so normal, uv and other triangles, as well as material and group ranges, still match them.
+pub(crate) fn save_triangles_connectivity<W: std::io::Write>(
+    triangles: &[IndexType],
+    writer: &mut W,
+) -> std::io::Result<()> {
+    let min = *triangles.iter().min().unwrap_or(&0);
+    let mut state = CoderState::new(EDGE_BITS, VERTEX_BITS);
+    let mut codes = Vec::with_capacity(triangles.len() / 3);
+    for triangle in triangles.chunks_exact(3) {
+        let triangle = [0, 1, 2].map(|corner| u64::from(triangle[corner] - min));
+        let shared = state.find_edge(triangle);
+        let code = match shared {
+            Some((slot, rotation)) => {
+                let third = triangle[(rotation + 2) % 3];
+                let code = state.vertex_code(third);
+                state.see(third);
+                TriangleCode::Edge(slot, rotation, code)
+            }
+            None => TriangleCode::Triangle(triangle.map(|vertex| {
+                let code = state.vertex_code(vertex);
+                state.see(vertex);
+                code
+            })),
+        };
+        state.update(triangle, shared);
+        codes.push(code);
+    }
+    let explicit: Vec<u64> = codes
+        .iter()
+        .flat_map(|code| match code {
+            TriangleCode::Edge(_, _, vertex) => std::slice::from_ref(vertex),
+            TriangleCode::Triangle(vertices) => &vertices[..],
+        })
+        .filter_map(|vertex| match vertex {
+            VertexCode::Explicit(delta) => Some(*delta),
+            _ => None,
+        })
+        .collect();
+    let order = best_order(&explicit);
+    writer.write_all(&(triangles.len() as u64).to_le_bytes())?;
+    writer.write_all(&u64::from(min).to_le_bytes())?;
+    writer.write_all(&[EDGE_BITS, VERTEX_BITS, order])?;
+    let edge_prec = UnalignedRWMode::precision_bits(EDGE_BITS);
+    let vertex_prec = UnalignedRWMode::precision_bits(VERTEX_BITS);
+    let mut writer = UnalignedWriter::new(writer);
+    for code in codes {
+        match code {
+            TriangleCode::Edge(slot, rotation, vertex) => {
+                writer.write_bit(true)?;
+                writer.write_unaligned(edge_prec, slot as u64)?;
+                writer.write_unaligned(ROTATION_PREC, rotation as u64)?;
+                write_vertex(&mut writer, &vertex, vertex_prec, order)?;
+            }
+            TriangleCode::Triangle(vertices) => {
+                writer.write_bit(false)?;
+                for vertex in &vertices {
+                    write_vertex(&mut writer, vertex, vertex_prec, order)?;
+                }
+            }
+        }
+    }
+    writer.flush()
+}
+fn invalid_data(msg: &'static str) -> TMFImportError {
+    TMFImportError::IO(std::io::Error::new(std::io::ErrorKind::InvalidData, msg))
+}
+fn read_vertex<I: MeshIndex, R: std::io::Read>(
+    reader: &mut UnalignedReader<R>,
+    state: &mut CoderState,
+    vertex_prec: UnalignedRWMode,
+    order: u8,
+) -> Result<u64, TMFImportError> {
+    let vertex = if reader.read_bit()? {
+        state.next
+    } else if reader.read_bit()? {
+        let slot = reader.read_unaligned(vertex_prec)? as usize;
+        *state
+            .vertices
+            .get(slot)
+            .ok_or_else(|| invalid_data("Connectivity vertex outside of the vertex FIFO."))?
+    } else {
+        let delta = unzigzag(read_code(reader, order)?);
+        let vertex = i64::try_from(state.next)
+            .ok()
+            .and_then(|next| next.checked_add(delta))
+            .ok_or_else(|| invalid_data("Connectivity vertex index too big."))?;
+        u64::try_from(vertex).map_err(|_| invalid_data("Connectivity vertex index negative."))?
+    };
+    if vertex >= u64::MAX >> (u64::BITS - I::BITS) {
+        return Err(invalid_data("Connectivity vertex index too big."));
+    }
+    state.see(vertex);
+    Ok(vertex)
+}
+pub(crate) fn read_triangles_connectivity<I: MeshIndex, R: std::io::Read>(
+    mut src: R,
+    data: &mut Vec<I>,
+) -> Result<(), TMFImportError> {
+    let length = src.read_u64()?;
+    let min = src.read_u64()?;
+    if length > MAX_SEG_SIZE as u64 {
+        return Err(TMFImportError::SegmentTooLong);
+    }
+    if length % 3 != 0 {
+        return Err(invalid_data(
+            "Connectivity coded triangles not a multiple of 3.",
+        ));
+    }
+    let edge_bits = src.read_u8()?;
+    let vertex_bits = src.read_u8()?;
+    let order = src.read_u8()?;
+    for bits in [edge_bits, vertex_bits] {
+        if bits > MAX_FIFO_BITS {
+            return Err(TMFImportError::InvalidPrecision(bits));
+        }
+    }
+    if order >= u64::BITS as u8 {
+        return Err(TMFImportError::InvalidPrecision(order));
+    }
+    let edge_prec = UnalignedRWMode::precision_bits(edge_bits);
+    let vertex_prec = UnalignedRWMode::precision_bits(vertex_bits);
+    let mut state = CoderState::new(edge_bits, vertex_bits);
+    let mut reader = UnalignedReader::new(src);
+    data.reserve(length as usize);
+    for _ in 0..(length / 3) {
+        let mut triangle = [0; 3];
+        let shared = if reader.read_bit()? {
+            let slot = reader.read_unaligned(edge_prec)? as usize;
+            let rotation = reader.read_unaligned(ROTATION_PREC)? as usize;
+            let (b, a) = *state
+                .edges
+                .get(slot)
+                .ok_or_else(|| invalid_data("Connectivity edge outside of the edge FIFO."))?;
+            if rotation > 2 {
+                return Err(invalid_data("Connectivity triangle rotation invalid."));
+            }
+            triangle[rotation] = a;
+            triangle[(rotation + 1) % 3] = b;
+            triangle[(rotation + 2) % 3] =
+                read_vertex::<I, _>(&mut reader, &mut state, vertex_prec, order)?;
+            Some((slot, rotation))
+        } else {
+            for corner in &mut triangle {
+                *corner = read_vertex::<I, _>(&mut reader, &mut state, vertex_prec, order)?;
+            }
+            None
+        };
+        state.update(triangle, shared);
+        for vertex in triangle {
+            let index = vertex
+                .checked_add(min)
+                .ok_or_else(|| invalid_data("Connectivity vertex index too big."))?;
+            data.push(index_from_u64(index)?);
+        }
+    }
+    Ok(())
+}
+#[cfg(test)]
+fn round_trip(triangles: &[IndexType]) -> usize {
+    assert!(is_edge_manifold(triangles));
+    let mut compressed = Vec::new();
+    save_triangles_connectivity(triangles, &mut compressed).unwrap();
+    let mut decoded = vec![7];
+    read_triangles_connectivity(&compressed[..], &mut decoded).unwrap();
+    assert_eq!(&decoded[1..], triangles);
+    compressed.len()
+}
+/// Triangulated *size* x *size* grid of quads.
+#[cfg(test)]
+fn grid(size: IndexType) -> Vec<IndexType> {
+    let mut triangles = Vec::new();
+    for y in 0..size {
+        for x in 0..size {
+            let i = y * (size + 1) + x;
+            triangles.extend_from_slice(&[i, i + 1, i + size + 2, i, i + size + 2, i + size + 1]);
+        }
+    }
+    triangles
+}
+#[test]
+fn manifold_check() {
+    assert!(is_edge_manifold(&[0, 1, 2, 2, 1, 3]));
+    // Edge 1-2 used thrice.
+    assert!(!is_edge_manifold(&[0, 1, 2, 2, 1, 3, 1, 2, 4]));
+    // Inconsistent orientation.
+    assert!(!is_edge_manifold(&[0, 1, 2, 1, 2, 3]));
+    // Degenerate triangle.
+    assert!(!is_edge_manifold(&[0, 1, 1]));
+    assert!(!is_edge_manifold(&[0, 1, 2, 3]));
+}
+#[test]
+fn compress_grid() {
+    let triangles = grid(100);
+    let compressed_len = round_trip(&triangles);
+    let mut delta = Vec::new();
+    let max_index = *triangles.iter().max().unwrap() as usize;
+    crate::delta::save_triangles_delta(&triangles, max_index, &mut delta).unwrap();
+    assert!(compressed_len < delta.len());
+    // Offset indices and a rotated, far away triangle.
+    let mut triangles: Vec<_> = grid(10).iter().map(|index| index + 50).collect();
+    triangles.extend_from_slice(&[10_000, 9_000, 11_000]);
+    round_trip(&triangles);
+}
+#[test]
+fn reject_invalid_edge() {
+    let mut data = Vec::new();
+    data.extend_from_slice(&3_u64.to_le_bytes());
+    data.extend_from_slice(&0_u64.to_le_bytes());
+    data.extend_from_slice(&[EDGE_BITS, VERTEX_BITS, 0]);
+    {
+        let mut writer = UnalignedWriter::new(&mut data);
+        // No edges are known before the first triangle.
+        writer.write_bit(true).unwrap();
+        writer
+            .write_unaligned(UnalignedRWMode::precision_bits(EDGE_BITS), 0)
+            .unwrap();
+        writer.write_unaligned(ROTATION_PREC, 0).unwrap();
+        writer.write_bit(true).unwrap();
+    }
+    assert!(read_triangles_connectivity(&data[..], &mut Vec::<IndexType>::new()).is_err());
+}
+#[test]
+fn reject_overflowing_vertex() {
+    let mut data = Vec::new();
+    data.extend_from_slice(&3_u64.to_le_bytes());
+    data.extend_from_slice(&0_u64.to_le_bytes());
+    data.extend_from_slice(&[EDGE_BITS, VERTEX_BITS, 0]);
+    {
+        let mut writer = UnalignedWriter::new(&mut data);
+        writer.write_bit(false).unwrap();
+        // A new vertex, followed by one whose difference from the next new vertex overflows.
+        writer.write_bit(true).unwrap();
+        writer.write_bit(false).unwrap();
+        writer.write_bit(false).unwrap();
+        write_code(&mut writer, zigzag(i64::MAX), 0).unwrap();
+        writer.write_bit(true).unwrap();
+    }
+    assert!(read_triangles_connectivity(&data[..], &mut Vec::<IndexType>::new()).is_err());
+}
+#[test]
+#[cfg(feature = "obj_import")]
+fn connectivity_susan() {
+    use crate::{SectionType, TMFMesh, TMFPrecisionInfo, TriangleEncodingMode};
+    let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+    let (mut mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+    mesh.reorder_data();
+    let triangle_bytes = |mode| {
+        let prec = TMFPrecisionInfo {
+            triangle_encoding: mode,
+            ..TMFPrecisionInfo::default()
+        };
+        let report = mesh.inspect(&name, &prec).unwrap();
+        let triangles: Vec<_> = report
+            .segments
+            .into_iter()
+            .filter(|seg| seg.seg_type.is_triangle())
+            .collect();
+        (
+            triangles.iter().map(|seg| seg.byte_len).sum::<usize>(),
+            triangles,
+        )
+    };
+    let (predictive, _) = triangle_bytes(TriangleEncodingMode::Predictive);
+    let (connectivity, segments) = triangle_bytes(TriangleEncodingMode::Connectivity);
+    assert!(connectivity < predictive);
+    assert!(segments
+        .iter()
+        .any(|seg| seg.seg_type == SectionType::ConnectivityTriangleSegment));
+    let prec = TMFPrecisionInfo {
+        triangle_encoding: TriangleEncodingMode::Connectivity,
+        ..TMFPrecisionInfo::default()
+    };
+    let mut out = Vec::new();
+    mesh.write_tmf_one(&mut out, &prec, name.as_str()).unwrap();
+    let (read, _) = TMFMesh::read_tmf_one(&mut &out[..]).unwrap();
+    assert_eq!(read.get_vertex_triangles(), mesh.get_vertex_triangles());
+    assert_eq!(read.get_normal_triangles(), mesh.get_normal_triangles());
+    assert_eq!(read.get_uv_triangles(), mesh.get_uv_triangles());
+}
@@ -1,7 +1,12 @@
 use crate::read_extension::ReadExt;
-use crate::tmf::SectionType;
-use crate::{FloatType, IndexType, TMFImportError, Vector4, MAX_SEG_SIZE};
-#[derive(Clone, Debug)]
+use crate::tmf::{CompressionType, SectionType};
+use crate::tmf_importer::read_compressed_triangles;
+use crate::unaligned_rw::{UnalignedRWMode, UnalignedReader, UnalignedWriter};
+use crate::{
+    FloatType, IndexType, NormalPrecisionMode, TMFImportError, Vector2, Vector3, Vector4,
+    MAX_SEG_SIZE,
+};
+#[derive(Clone, Debug, PartialEq)]
 pub(crate) struct CustomDataSegment {
     name: Vec<u8>,
     name_len: u8,
@@ -33,6 +38,20 @@ impl CustomDataSegment {
     pub(crate) fn name_bytes(&self) -> &[u8] {
         &self.name
     }
+    /// Finds the data named *name* among *segments*. Returns `None` if not present, or name too long(over 255 bytes).
+    pub(crate) fn lookup<'a>(segments: &'a [Self], name: &str) -> Option<&'a CustomData> {
+        let bytes = name.as_bytes();
+        let Ok(bytes_len) = u8::try_from(bytes.len()) else {
+            return None;
+        };
+        segments
+            .iter()
+            .find(|data| {
+                data.name_len() == bytes_len
+                    && bytes == &data.name_bytes()[..(data.name_len() as usize)]
+            })
+            .map(Self::custom_data)
+    }
     pub fn new(data: CustomData, name: &str) -> Result<Self, DataSegmentError> {
         let bytes = name.as_bytes();
         let len = name.len();
@@ -48,12 +67,91 @@ impl CustomDataSegment {
     }
 }
 /// Custom mesh data.
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum CustomData {
     CustomIndex(Box<[IndexType]>, usize),
     CustomIntiger(Box<[IndexType]>, usize),
     CustomFloat(Box<[FloatType]>, FloatType),
     CustomColorRGBA(Box<[Vector4]>, FloatType),
+    CustomUnit2(Box<[Vector2]>, NormalPrecisionMode),
+    CustomUnit3(Box<[Vector3]>, NormalPrecisionMode),
+    CustomVector2(Box<[Vector2]>, FloatType),
+    CustomVector3(Box<[Vector3]>, FloatType),
+    CustomVector4(Box<[Vector4]>, FloatType),
+}
+/// Saves an array of vectors with *N* components. Each component is saved relative to the bounding box of all vectors, with all components sharing the same precision.
+fn save_vector_array<const N: usize, W: std::io::Write>(
+    data: &[[FloatType; N]],
+    prec: FloatType,
+    target: &mut W,
+) -> std::io::Result<()> {
+    let mut min = [FloatType::MAX; N];
+    let mut max = [FloatType::MIN; N];
+    for entry in data {
+        for component in 0..N {
+            min[component] = min[component].min(entry[component]);
+            max[component] = max[component].max(entry[component]);
+        }
+    }
+    let span = (0..N).fold(0.0, |span: FloatType, component| {
+        span.max(max[component] - min[component])
+    });
+    //Ensure precision is never 0(messes up the reader/writer) or over 63 bits;
+    let prec_bits = ((span / prec).log2().ceil() as u8).clamp(1, u64::BITS as u8 - 1);
+    let mul = ((1_u64 << prec_bits) - 1) as FloatType;
+    target.write_all(&(data.len() as u64).to_le_bytes())?;
+    for component in 0..N {
+        target.write_all(&(min[component] as f64).to_le_bytes())?;
+        target.write_all(&(max[component] as f64).to_le_bytes())?;
+    }
+    target.write_all(&[prec_bits])?;
+    let prec = UnalignedRWMode::precision_bits(prec_bits);
+    let mut writer = UnalignedWriter::new(target);
+    for entry in data {
+        for component in 0..N {
+            let span = max[component] - min[component];
+            let value = if span > 0.0 {
+                (((entry[component] - min[component]) / span) * mul).round() as u64
+            } else {
+                0
+            };
+            writer.write_unaligned(prec, value)?;
+        }
+    }
+    writer.flush()
+}
+/// Reads an array of vectors saved with [`save_vector_array`]. Returns the vectors and precision they were saved with.
+fn read_vector_array<const N: usize, R: std::io::Read>(
+    mut src: R,
+) -> Result<(Vec<[FloatType; N]>, FloatType), TMFImportError> {
+    let len = src.read_u64()?;
+    if len > MAX_SEG_SIZE as u64 {
+        return Err(TMFImportError::SegmentTooLong);
+    }
+    let mut min = [0.0; N];
+    let mut span = [0.0; N];
+    for component in 0..N {
+        min[component] = src.read_f64()?;
+        span[component] = src.read_f64()? - min[component];
+    }
+    let prec_bits = src.read_u8()?;
+    if prec_bits >= u64::BITS as u8 {
+        return Err(TMFImportError::InvalidPrecision(prec_bits));
+    }
+    let div = ((1_u64 << prec_bits) - 1) as f64;
+    let prec = UnalignedRWMode::precision_bits(prec_bits);
+    let mut reader = UnalignedReader::new(src);
+    let mut res = vec![[0.0; N]; len as usize];
+    for entry in &mut res {
+        for component in 0..N {
+            let val = reader.read_unaligned(prec)?;
+            entry[component] =
+                (((val as f64) / div) * span[component] + min[component]) as FloatType;
+        }
+    }
+    let max_span = span.iter().fold(0.0, |max: f64, span| max.max(*span));
+    let prec = ((max_span / ((1_u64 << prec_bits) as f64)) as FloatType) * 0.99999;
+    Ok((res, prec))
 }
 impl CustomData {
     /// Returns the index data if custom segment is an index segment. Returns the index array and max index.
@@ -81,14 +179,65 @@ impl CustomData {
             _ => None,
         }
     }
-    fn write<W: std::io::Write>(&self, target: &mut W) -> std::io::Result<()> {
+    /// Returns the 2D unit vector data if custom segment is an unit 2 segment. Returns the unit vectors and precision they are saved with.
+    pub fn as_unit2(&self) -> Option<(&[Vector2], NormalPrecisionMode)> {
         match self {
-            Self::CustomIndex(data, max_index) => {
-                crate::vertices::save_triangles(data, *max_index, target)
-            }
-            Self::CustomIntiger(data, max_index) => {
-                crate::vertices::save_triangles(data, *max_index, target)
-            }
+            Self::CustomUnit2(array, prec) => Some((array, *prec)),
+            _ => None,
+        }
+    }
+    /// Returns the 3D unit vector data if custom segment is an unit 3 segment. Returns the unit vectors and precision they are saved with.
+    pub fn as_unit3(&self) -> Option<(&[Vector3], NormalPrecisionMode)> {
+        match self {
+            Self::CustomUnit3(array, prec) => Some((array, *prec)),
+            _ => None,
+        }
+    }
+    /// Returns the 2D vector data if custom segment is a vector 2 segment. Returns the vectors and maximal deviation of each component.
+    pub fn as_vector2(&self) -> Option<(&[Vector2], FloatType)> {
+        match self {
+            Self::CustomVector2(array, prec) => Some((array, *prec)),
+            _ => None,
+        }
+    }
+    /// Returns the 3D vector data if custom segment is a vector 3 segment. Returns the vectors and maximal deviation of each component.
+    pub fn as_vector3(&self) -> Option<(&[Vector3], FloatType)> {
+        match self {
+            Self::CustomVector3(array, prec) => Some((array, *prec)),
+            _ => None,
+        }
+    }
+    /// Returns the 4D vector data if custom segment is a vector 4 segment. Returns the vectors and maximal deviation of each component.
+    pub fn as_vector4(&self) -> Option<(&[Vector4], FloatType)> {
+        match self {
+            Self::CustomVector4(array, prec) => Some((array, *prec)),
+            _ => None,
+        }
+    }
+    /// Returns the amount of elements stored in this custom data.
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Self::CustomIndex(data, _) | Self::CustomIntiger(data, _) => data.len(),
+            Self::CustomFloat(data, _) => data.len(),
+            Self::CustomColorRGBA(data, _) | Self::CustomVector4(data, _) => data.len(),
+            Self::CustomUnit2(data, _) | Self::CustomVector2(data, _) => data.len(),
+            Self::CustomUnit3(data, _) | Self::CustomVector3(data, _) => data.len(),
+        }
+    }
+    fn write<W: std::io::Write>(&self, target: &mut W) -> std::io::Result<CompressionType> {
+        match self {
+            Self::CustomIndex(data, max_index) => crate::tmf_exporter::encode_triangles(
+                data,
+                *max_index,
+                crate::TriangleEncodingMode::Standard,
+                target,
+            ),
+            Self::CustomIntiger(data, max_index) => crate::tmf_exporter::encode_triangles(
+                data,
+                *max_index,
+                crate::TriangleEncodingMode::Standard,
+                target,
+            ),
             Self::CustomFloat(data, prec) => {
                 use crate::unaligned_rw::{UnalignedRWMode, UnalignedWriter};
                 let mut max = FloatType::MIN;
@@ -112,7 +261,7 @@ impl CustomData {
                     let entry = (((entry - min) / span) * mul) as u64;
                     writer.write_unaligned(prec, entry)?;
                 }
-                Ok(())
+                Ok(CompressionType::None)
             }
             Self::CustomColorRGBA(data, prec) => {
                 use crate::unaligned_rw::{UnalignedRWMode, UnalignedWriter};
@@ -132,10 +281,38 @@ impl CustomData {
                     writer.write_unaligned(prec, entry_b)?;
                     writer.write_unaligned(prec, entry_a)?;
                 }
-                Ok(())
+                Ok(CompressionType::None)
+            }
+            Self::CustomUnit2(data, prec) => {
+                target.write_all(&(data.len() as u64).to_le_bytes())?;
+                target.write_all(&[prec.bits()])?;
+                let mut writer = UnalignedWriter::new(target);
+                for unit in data.iter() {
+                    crate::normals::save_unit2(*unit, *prec, &mut writer)?;
+                }
+                writer.flush()?;
+                Ok(CompressionType::None)
+            }
+            Self::CustomUnit3(data, prec) => {
+                crate::normals::save_normal_array(data, target, *prec)?;
+                Ok(CompressionType::None)
+            }
+            Self::CustomVector2(data, prec) => {
+                let data: Box<[_]> = data.iter().map(|v| [v.0, v.1]).collect();
+                save_vector_array(&data, *prec, target)?;
+                Ok(CompressionType::None)
+            }
+            Self::CustomVector3(data, prec) => {
+                let data: Box<[_]> = data.iter().map(|v| [v.0, v.1, v.2]).collect();
+                save_vector_array(&data, *prec, target)?;
+                Ok(CompressionType::None)
+            }
+            Self::CustomVector4(data, prec) => {
+                let data: Box<[_]> = data.iter().map(|v| [v.0, v.1, v.2, v.3]).collect();
+                save_vector_array(&data, *prec, target)?;
+                Ok(CompressionType::None)
             }
-        }?;
-        Ok(())
+        }
     }
     fn section_type(&self) -> SectionType {
         match self {
@@ -143,6 +320,11 @@ impl CustomData {
             Self::CustomIntiger(_, _) => SectionType::CustomIntigerSegment,
             Self::CustomFloat(_, _) => SectionType::CustomFloatSegment,
             Self::CustomColorRGBA(_, _) => SectionType::CustomColorRGBASegment,
+            Self::CustomUnit2(_, _) => SectionType::CustomUnit2Segment,
+            Self::CustomUnit3(_, _) => SectionType::CustomUnit3Segment,
+            Self::CustomVector2(_, _) => SectionType::CustomVector2Segment,
+            Self::CustomVector3(_, _) => SectionType::CustomVector3Segment,
+            Self::CustomVector4(_, _) => SectionType::CustomVector4Segment,
         }
     }
     fn new_float(floats: &[FloatType], prec: FloatType) -> Self {
@@ -181,17 +363,31 @@ impl From<&[Vector4]> for CustomData {
         Self::new_color_rgba(colors, 0.01)
     }
 }
+impl From<&[Vector2]> for CustomData {
+    fn from(vectors: &[Vector2]) -> Self {
+        Self::CustomVector2(vectors.into(), 0.01)
+    }
+}
+impl From<&[Vector3]> for CustomData {
+    fn from(vectors: &[Vector3]) -> Self {
+        Self::CustomVector3(vectors.into(), 0.01)
+    }
+}
 impl CustomDataSegment {
-    pub(crate) fn encode<W: std::io::Write>(&self, target: &mut W) -> std::io::Result<SectionType> {
+    pub(crate) fn encode<W: std::io::Write>(
+        &self,
+        target: &mut W,
+    ) -> std::io::Result<(SectionType, CompressionType)> {
         target.write_all(&[self.name_len])?;
         target.write_all(&self.name[..(self.name_len as usize)])?;
-        self.data.write(target)?;
-        Ok(self.data.section_type())
+        let compression_type = self.data.write(target)?;
+        Ok((self.data.section_type(), compression_type))
     }
     //return Err(std::io::Error::new(std::io::ErrorKind::Other,format!("Invalid custom se"),)),
     pub(crate) fn read<R: std::io::Read>(
         mut src: R,
         kind: SectionType,
+        compression_type: CompressionType,
         ctx: &crate::tmf_importer::TMFImportContext,
     ) -> Result<Self, TMFImportError> {
         let name_len = src.read_u8()?;
@@ -202,7 +398,8 @@ impl CustomDataSegment {
 
             }*/
             SectionType::CustomIndexSegment => {
-                let result = crate::vertices::read_triangles(&mut src, ctx)?;
+                let mut result = Vec::new();
+                read_compressed_triangles(&mut src, &mut result, ctx, compression_type)?;
                 Ok(Self::new_raw(
                     CustomData::new_index(&result, None),
                     name,
@@ -210,7 +407,8 @@ impl CustomDataSegment {
                 ))
             }
             SectionType::CustomIntigerSegment => {
-                let result = crate::vertices::read_triangles(&mut src, ctx)?;
+                let mut result = Vec::new();
+                read_compressed_triangles(&mut src, &mut result, ctx, compression_type)?;
                 Ok(Self::new_raw(
                     CustomData::new_intiger(&result, None),
                     name,
@@ -271,6 +469,62 @@ impl CustomDataSegment {
                     name_len,
                 ))
             }
+            SectionType::CustomUnit2Segment => {
+                let len = src.read_u64()?;
+                if len > MAX_SEG_SIZE as u64 {
+                    return Err(TMFImportError::SegmentTooLong);
+                }
+                let prec_bits = src.read_u8()?;
+                if prec_bits >= u64::BITS as u8 {
+                    return Err(TMFImportError::InvalidPrecision(prec_bits));
+                }
+                let prec = NormalPrecisionMode::from_bits(prec_bits);
+                let mut reader = UnalignedReader::new(src);
+                let mut res = Vec::with_capacity(len as usize);
+                for _ in 0..len {
+                    res.push(crate::normals::read_unit2(prec, &mut reader)?);
+                }
+                Ok(Self::new_raw(
+                    CustomData::CustomUnit2(res.into(), prec),
+                    name,
+                    name_len,
+                ))
+            }
+            SectionType::CustomUnit3Segment => {
+                let (res, prec) = crate::normals::read_normal_array_prec(&mut src)?;
+                Ok(Self::new_raw(
+                    CustomData::CustomUnit3(res, prec),
+                    name,
+                    name_len,
+                ))
+            }
+            SectionType::CustomVector2Segment => {
+                let (res, prec) = read_vector_array::<2, _>(src)?;
+                let res = res.iter().map(|v| (v[0], v[1])).collect();
+                Ok(Self::new_raw(
+                    CustomData::CustomVector2(res, prec),
+                    name,
+                    name_len,
+                ))
+            }
+            SectionType::CustomVector3Segment => {
+                let (res, prec) = read_vector_array::<3, _>(src)?;
+                let res = res.iter().map(|v| (v[0], v[1], v[2])).collect();
+                Ok(Self::new_raw(
+                    CustomData::CustomVector3(res, prec),
+                    name,
+                    name_len,
+                ))
+            }
+            SectionType::CustomVector4Segment => {
+                let (res, prec) = read_vector_array::<4, _>(src)?;
+                let res = res.iter().map(|v| (v[0], v[1], v[2], v[3])).collect();
+                Ok(Self::new_raw(
+                    CustomData::CustomVector4(res, prec),
+                    name,
+                    name_len,
+                ))
+            }
             _ => panic!("InternalError: Invalid custom section type, must be custom!"),
         }
     }
@@ -406,3 +660,119 @@ fn color_rgba_data() {
         );
     }
 }
+#[test]
+#[cfg(feature = "obj_import")]
+fn unit_data() {
+    use crate::{TMFMesh, TMFPrecisionInfo};
+    init_test_env();
+    let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+    let (mut tmf_mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+    let unit2_data: Box<[Vector2]> = (0..64).map(|i| (i as FloatType * 0.1).sin_cos()).collect();
+    let unit3_data: Box<[Vector3]> = tmf_mesh.get_normals().unwrap().into();
+    let prec = NormalPrecisionMode::from_deg_dev(0.01);
+    tmf_mesh
+        .add_custom_data(CustomData::CustomUnit2(unit2_data.clone(), prec), "unit2")
+        .unwrap();
+    tmf_mesh
+        .add_custom_data(CustomData::CustomUnit3(unit3_data.clone(), prec), "unit3")
+        .unwrap();
+    tmf_mesh.verify().unwrap();
+    let mut out = Vec::new();
+    tmf_mesh
+        .write_tmf_one(&mut out, &TMFPrecisionInfo::default(), name)
+        .unwrap();
+    let (r_mesh, _) = TMFMesh::read_tmf_one(&mut (&out as &[u8])).unwrap();
+    r_mesh.verify().unwrap();
+    let (r_unit2, r_prec) = r_mesh
+        .lookup_custom_data("unit2")
+        .expect("Could not find the custom unit2 array!")
+        .as_unit2()
+        .unwrap();
+    assert_eq!(r_prec, prec);
+    assert_eq!(r_unit2.len(), unit2_data.len());
+    for (r_unit, unit) in r_unit2.iter().zip(unit2_data.iter()) {
+        let n_dot = (1.0 - (r_unit.0 * unit.0 + r_unit.1 * unit.1)) * 180.0;
+        assert!(n_dot < 0.1, "{r_unit:?} {unit:?} angle:{n_dot}");
+    }
+    let (r_unit3, r_prec) = r_mesh
+        .lookup_custom_data("unit3")
+        .expect("Could not find the custom unit3 array!")
+        .as_unit3()
+        .unwrap();
+    assert_eq!(r_prec, prec);
+    assert_eq!(r_unit3.len(), unit3_data.len());
+    for (r_unit, unit) in r_unit3.iter().zip(unit3_data.iter()) {
+        let n_dot = (1.0 - (r_unit.0 * unit.0 + r_unit.1 * unit.1 + r_unit.2 * unit.2)) * 180.0;
+        assert!(n_dot < 0.1, "{r_unit:?} {unit:?} angle:{n_dot}");
+    }
+}
+#[test]
+#[cfg(feature = "obj_import")]
+fn vector_data() {
+    use crate::{TMFMesh, TMFPrecisionInfo};
+    init_test_env();
+    let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+    let (mut tmf_mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+    let vector2_data: Box<[Vector2]> = tmf_mesh.get_uvs().unwrap().into();
+    let vector3_data: Box<[Vector3]> = tmf_mesh.get_vertices().unwrap().into();
+    let vector4_data: Box<[Vector4]> = vector3_data
+        .iter()
+        .map(|v| (v.0, v.1 * 10.0, v.2, -v.0))
+        .collect();
+    tmf_mesh
+        .add_custom_data(vector2_data[..].into(), "vector2")
+        .unwrap();
+    tmf_mesh
+        .add_custom_data(vector3_data[..].into(), "vector3")
+        .unwrap();
+    tmf_mesh
+        .add_custom_data(
+            CustomData::CustomVector4(vector4_data.clone(), 0.001),
+            "vector4",
+        )
+        .unwrap();
+    tmf_mesh.verify().unwrap();
+    let mut out = Vec::new();
+    tmf_mesh
+        .write_tmf_one(&mut out, &TMFPrecisionInfo::default(), name)
+        .unwrap();
+    let (r_mesh, _) = TMFMesh::read_tmf_one(&mut (&out as &[u8])).unwrap();
+    r_mesh.verify().unwrap();
+    let (r_vector2, _) = r_mesh
+        .lookup_custom_data("vector2")
+        .expect("Could not find the custom vector2 array!")
+        .as_vector2()
+        .unwrap();
+    assert_eq!(r_vector2.len(), vector2_data.len());
+    for (r_vec, vec) in r_vector2.iter().zip(vector2_data.iter()) {
+        let dev = (r_vec.0 - vec.0).abs().max((r_vec.1 - vec.1).abs());
+        assert!(dev <= 0.01, "{r_vec:?} {vec:?} diff {dev} > 0.01!");
+    }
+    let (r_vector3, _) = r_mesh
+        .lookup_custom_data("vector3")
+        .expect("Could not find the custom vector3 array!")
+        .as_vector3()
+        .unwrap();
+    assert_eq!(r_vector3.len(), vector3_data.len());
+    for (r_vec, vec) in r_vector3.iter().zip(vector3_data.iter()) {
+        let dev = (r_vec.0 - vec.0)
+            .abs()
+            .max((r_vec.1 - vec.1).abs())
+            .max((r_vec.2 - vec.2).abs());
+        assert!(dev <= 0.01, "{r_vec:?} {vec:?} diff {dev} > 0.01!");
+    }
+    let (r_vector4, _) = r_mesh
+        .lookup_custom_data("vector4")
+        .expect("Could not find the custom vector4 array!")
+        .as_vector4()
+        .unwrap();
+    assert_eq!(r_vector4.len(), vector4_data.len());
+    for (r_vec, vec) in r_vector4.iter().zip(vector4_data.iter()) {
+        let dev = (r_vec.0 - vec.0)
+            .abs()
+            .max((r_vec.1 - vec.1).abs())
+            .max((r_vec.2 - vec.2).abs())
+            .max((r_vec.3 - vec.3).abs());
+        assert!(dev <= 0.001, "{r_vec:?} {vec:?} diff {dev} > 0.001!");
+    }
+}
@@ -0,0 +1,252 @@
+use crate::read_extension::ReadExt;
+use crate::typed_mesh::{index_from_u64, MeshIndex};
+use crate::unaligned_rw::{UnalignedRWMode, UnalignedReader, UnalignedWriter};
+use crate::vertices::calc_prec;
+use crate::{IndexType, TMFImportError, MAX_SEG_SIZE};
+/// Setting dictating which encodings may be used for triangle segments. Triangles are always saved losslessly.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum TriangleEncodingMode {
+    /// Indices are saved with fixed bit width, Unaligned LZZ or Sequence compression, whichever is the smallest.
+    #[default]
+    Standard,
+    /// Like [`Self::Standard`], but delta-predicted encoding is tried too. Indices are saved as differences from the biggest index of the previous triangle,
+    /// using variable-length codes. Smallest for dense meshes with vertices ordered by first use(e.g. after [`crate::TMFMesh::reorder_data`]).
+    /// ```
+    /// # use tmf::{TMFPrecisionInfo,TriangleEncodingMode};
+    /// let prec = TMFPrecisionInfo {
+    ///     triangle_encoding: TriangleEncodingMode::Predictive,
+    ///     ..TMFPrecisionInfo::default()
+    /// };
+    /// ```
+    Predictive,
+    /// Like [`Self::Predictive`], but vertex triangles of edge-manifold, consistently oriented meshes are also tried with an Edgebreaker-style connectivity coder,
+    /// and saved as [`crate::SectionType::ConnectivityTriangleSegment`] if smaller. Each triangle is saved as a recently seen edge it shares and its third vertex,
+    /// which is most often just the next new vertex. Unlike Edgebreaker, the order of triangles and their corners is kept. Non-manifold triangles are saved as with [`Self::Predictive`].
+    Connectivity,
+}
+/// Highest order of the Exp-Golomb codes tried by the encoder.
+const MAX_CODE_ORDER: u8 = 16;
+/// Longest prefix of an Exp-Golomb code a valid value may have.
+const MAX_PREFIX: u8 = u64::BITS as u8 - 1;
+pub(crate) fn zigzag(delta: i64) -> u64 {
+    ((delta << 1) ^ (delta >> 63)) as u64
+}
+pub(crate) fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+/// Returns zigzag encoded differences between each index and the biggest index of the previous triangle.
+fn predict(data: &[u64]) -> Vec<u64> {
+    let mut base = 0;
+    data.chunks(3)
+        .flat_map(|triangle| {
+            let prev_base = base;
+            base = *triangle.iter().max().unwrap_or(&0);
+            triangle
+                .iter()
+                .map(move |index| zigzag(*index as i64 - prev_base as i64))
+        })
+        .collect()
+}
+/// Size of *value* saved as an Exp-Golomb code of *order*, in bits.
+fn code_len(value: u64, order: u8) -> usize {
+    let prefix = u64::BITS - ((value >> order) + 1).leading_zeros() - 1;
+    (prefix * 2 + 1) as usize + order as usize
+}
+/// Returns the Exp-Golomb code order resulting in the smallest encoding of *values*.
+pub(crate) fn best_order(values: &[u64]) -> u8 {
+    (0..=MAX_CODE_ORDER)
+        .min_by_key(|order| {
+            values
+                .iter()
+                .map(|value| code_len(*value, *order))
+                .sum::<usize>()
+        })
+        .unwrap_or(0)
+}
+pub(crate) fn write_code<W: std::io::Write>(
+    writer: &mut UnalignedWriter<W>,
+    value: u64,
+    order: u8,
+) -> std::io::Result<()> {
+    let high = (value >> order) + 1;
+    let prefix = (u64::BITS - high.leading_zeros() - 1) as u8;
+    for _ in 0..prefix {
+        writer.write_bit(false)?;
+    }
+    writer.write_bit(true)?;
+    writer.write_unaligned(UnalignedRWMode::precision_bits(prefix), high)?;
+    writer.write_unaligned(UnalignedRWMode::precision_bits(order), value)
+}
+pub(crate) fn read_code<R: std::io::Read>(
+    reader: &mut UnalignedReader<R>,
+    order: u8,
+) -> Result<u64, TMFImportError> {
+    let mut prefix = 0;
+    while !reader.read_bit()? {
+        prefix += 1;
+        if prefix > MAX_PREFIX - order {
+            return Err(TMFImportError::IO(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Delta code too long.",
+            )));
+        }
+    }
+    let high = (1 << prefix) | reader.read_unaligned(UnalignedRWMode::precision_bits(prefix))?;
+    let low = reader.read_unaligned(UnalignedRWMode::precision_bits(order))?;
+    Ok(((high - 1) << order) | low)
+}
+/// Saves *triangles* using the Delta compression. Header is the same as for uncompressed triangles, followed by the order of Exp-Golomb codes.
+pub(crate) fn save_triangles_delta<W: std::io::Write>(
+    triangles: &[IndexType],
+    max_index: usize,
+    writer: &mut W,
+) -> std::io::Result<()> {
+    let min = *triangles.iter().min().unwrap_or(&0);
+    let precision = calc_prec(max_index);
+    let data: Box<_> = triangles
+        .iter()
+        .map(|index| u64::from(index - min))
+        .collect();
+    let deltas = predict(&data);
+    let order = best_order(&deltas);
+    writer.write_all(&precision.to_le_bytes())?;
+    writer.write_all(&(triangles.len() as u64).to_le_bytes())?;
+    writer.write_all(&u64::from(min).to_le_bytes())?;
+    writer.write_all(&[order])?;
+    let mut writer = UnalignedWriter::new(writer);
+    for delta in deltas {
+        write_code(&mut writer, delta, order)?;
+    }
+    writer.flush()
+}
+pub(crate) fn read_triangles_delta<I: MeshIndex, R: std::io::Read>(
+    mut src: R,
+    data: &mut Vec<I>,
+    ctx: &crate::tmf_importer::TMFImportContext,
+) -> Result<(), TMFImportError> {
+    let precision = src.read_u8()?;
+    let length = src.read_u64()?;
+    let min = ctx.read_traingle_min(&mut src)?;
+    if length > MAX_SEG_SIZE as u64 {
+        return Err(TMFImportError::SegmentTooLong);
+    }
+    let order = src.read_u8()?;
+    for bits in [precision, order] {
+        if bits >= u64::BITS as u8 {
+            return Err(TMFImportError::InvalidPrecision(bits));
+        }
+    }
+    let max = (1_u64 << precision) - 1;
+    data.reserve(length as usize);
+    let mut reader = UnalignedReader::new(src);
+    let mut base: i64 = 0;
+    let mut triangle_max = 0;
+    for position in 0..(length as usize) {
+        if position % 3 == 0 {
+            base = triangle_max;
+            triangle_max = 0;
+        }
+        let index = match base.checked_add(unzigzag(read_code(&mut reader, order)?)) {
+            Some(index) if index >= 0 && index as u64 <= max => index,
+            _ => {
+                return Err(TMFImportError::IO(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Delta decoded index out of range.",
+                )))
+            }
+        };
+        triangle_max = triangle_max.max(index);
+        data.push(index_from_u64(index as u64 + min)?);
+    }
+    Ok(())
+}
+#[cfg(test)]
+fn test_ctx() -> crate::tmf_importer::TMFImportContext {
+    crate::tmf_importer::TMFImportContext::default()
+}
+#[cfg(test)]
+fn round_trip(triangles: &[IndexType]) -> usize {
+    let max_index = *triangles.iter().max().unwrap_or(&0) as usize;
+    let mut compressed = Vec::new();
+    save_triangles_delta(triangles, max_index, &mut compressed).unwrap();
+    let mut decoded = vec![7];
+    read_triangles_delta(&compressed[..], &mut decoded, &test_ctx()).unwrap();
+    assert_eq!(&decoded[1..], triangles);
+    compressed.len()
+}
+#[test]
+fn exp_golomb_codes() {
+    let values = [0, 1, 2, 3, 17, 255, 1 << 40, u64::MAX >> 2];
+    for order in [0, 1, 5, 16] {
+        let mut data = Vec::new();
+        {
+            let mut writer = UnalignedWriter::new(&mut data);
+            for value in values {
+                write_code(&mut writer, value, order).unwrap();
+            }
+        }
+        let bits: usize = values.iter().map(|value| code_len(*value, order)).sum();
+        assert_eq!(data.len(), bits.div_ceil(8));
+        let mut reader = UnalignedReader::new(&data[..]);
+        for value in values {
+            assert_eq!(read_code(&mut reader, order).unwrap(), value);
+        }
+    }
+    for delta in [0, 1, -1, 1000, -1000, i64::MAX >> 1, i64::MIN >> 1] {
+        assert_eq!(unzigzag(zigzag(delta)), delta);
+    }
+}
+#[test]
+fn compress_strip() {
+    // A triangle strip, each triangle using two vertices of the previous one.
+    let triangles: Vec<IndexType> = (0..10_000).flat_map(|i| [i + 3, i + 1, i + 2]).collect();
+    let compressed_len = round_trip(&triangles);
+    let mut uncompressed = Vec::new();
+    crate::vertices::save_triangles(&triangles, 10_002, &mut uncompressed).unwrap();
+    assert!(compressed_len * 3 < uncompressed.len());
+    round_trip(&[]);
+    round_trip(&[5, 5, 5, 6]);
+    round_trip(&[900, 0, 3, 2, 1000, 1]);
+}
+#[test]
+fn reject_overflowing_delta() {
+    // The biggest index of the first triangle is the base of the next one, and the difference from it overflows.
+    let mut data = vec![63];
+    data.extend(6_u64.to_le_bytes());
+    data.extend(0_u64.to_le_bytes());
+    data.push(0);
+    {
+        let mut writer = UnalignedWriter::new(&mut data);
+        for delta in [i64::MAX >> 1, 0, 0, i64::MAX, 0, 0] {
+            write_code(&mut writer, zigzag(delta), 0).unwrap();
+        }
+    }
+    let res = read_triangles_delta(&data[..], &mut Vec::<IndexType>::new(), &test_ctx());
+    assert!(res.is_err());
+}
+#[test]
+#[cfg(feature = "obj_import")]
+fn delta_susan() {
+    use crate::{CompressionType, TMFMesh, TMFPrecisionInfo};
+    let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+    let (mut mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+    mesh.reorder_data();
+    let prec = TMFPrecisionInfo {
+        triangle_encoding: TriangleEncodingMode::Predictive,
+        ..TMFPrecisionInfo::default()
+    };
+    let predictive = mesh.inspect(&name, &prec).unwrap();
+    let standard = mesh.inspect(&name, &TMFPrecisionInfo::default()).unwrap();
+    assert!(predictive.total_byte_len() < standard.total_byte_len());
+    assert!(predictive
+        .segments
+        .iter()
+        .filter(|seg| seg.seg_type.is_triangle())
+        .all(|seg| seg.compression_type == CompressionType::Delta));
+    let mut out = Vec::new();
+    mesh.write_tmf_one(&mut out, &prec, name.as_str()).unwrap();
+    let (read, _) = TMFMesh::read_tmf_one(&mut &out[..]).unwrap();
+    assert_eq!(read.get_vertex_triangles(), mesh.get_vertex_triangles());
+    assert_eq!(read.get_normal_triangles(), mesh.get_normal_triangles());
+    assert_eq!(read.get_uv_triangles(), mesh.get_uv_triangles());
+}
@@ -0,0 +1,119 @@
+use std::future::Future;
+use std::sync::{Arc, PoisonError, RwLock};
+/// Default maximal number of elements(vertices or triangle indices) in a segment encoded on a [`TMFExecutor`].
+pub const DEFAULT_MAX_SEGMENT_LENGTH: usize = 0x10_00_00;
+/// A unit of work(encoding or decoding of a segment) run by a [`TMFExecutor`].
+pub type TMFTask = Box<dyn FnOnce() + Send + 'static>;
+/// Runs encoding and decoding of segments in parallel. Any function taking a [`TMFTask`](e.g. one handing it to a thread pool) is an executor.
+///
+/// Tasks are waited on by the thread reading or writing the file, so an executor must not require that thread to run them(e.g. a thread pool with only the calling thread in it).
+pub trait TMFExecutor: Send + Sync {
+    /// Runs *task*, either right away or at some later point. Every task must eventually be run.
+    fn spawn(&self, task: TMFTask);
+    /// Segments of vertices and triangle indices longer than this are split while saving, so that their parts can be encoded in parallel. Defaults to [`DEFAULT_MAX_SEGMENT_LENGTH`].
+    fn max_segment_length(&self) -> usize {
+        DEFAULT_MAX_SEGMENT_LENGTH
+    }
+}
+impl<F: Fn(TMFTask) + Send + Sync> TMFExecutor for F {
+    fn spawn(&self, task: TMFTask) {
+        self(task);
+    }
+}
+/// Executor running tasks on a [`rayon`] thread pool.
+#[cfg(feature = "rayon")]
+#[derive(Clone)]
+pub struct RayonExecutor {
+    pool: Option<Arc<rayon::ThreadPool>>,
+    max_segment_length: usize,
+}
+#[cfg(feature = "rayon")]
+impl RayonExecutor {
+    /// Creates an executor running tasks on the global rayon thread pool.
+    /// # Example
+    /// ```
+    /// // Encode and decode segments of all following reads and writes on the global rayon thread pool.
+    /// tmf::set_executor(tmf::RayonExecutor::new());
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            pool: None,
+            max_segment_length: DEFAULT_MAX_SEGMENT_LENGTH,
+        }
+    }
+    /// Creates an executor running tasks on *pool*.
+    #[must_use]
+    pub fn with_pool(pool: Arc<rayon::ThreadPool>) -> Self {
+        Self {
+            pool: Some(pool),
+            ..Self::new()
+        }
+    }
+    /// Sets the maximal length of segments encoded by this executor. See [`TMFExecutor::max_segment_length`].
+    #[must_use]
+    pub fn with_max_segment_length(mut self, max_segment_length: usize) -> Self {
+        self.max_segment_length = max_segment_length;
+        self
+    }
+}
+#[cfg(feature = "rayon")]
+impl Default for RayonExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+#[cfg(feature = "rayon")]
+impl TMFExecutor for RayonExecutor {
+    fn spawn(&self, task: TMFTask) {
+        match &self.pool {
+            Some(pool) => pool.spawn(task),
+            None => rayon::spawn(task),
+        }
+    }
+    fn max_segment_length(&self) -> usize {
+        self.max_segment_length
+    }
+}
+static EXECUTOR: RwLock<Option<Arc<dyn TMFExecutor>>> = RwLock::new(None);
+/// Makes all following reads and writes of .tmf files encode and decode segments on *executor*.
+/// # Example
+/// ```
+/// # use tmf::{TMFMesh,TMFPrecisionInfo};
+/// // Runs each task on a new thread.
+/// tmf::set_executor(|task: tmf::TMFTask| {
+///     std::thread::spawn(task);
+/// });
+/// # let mut out = Vec::new();
+/// TMFMesh::empty().write_tmf_one(&mut out, &TMFPrecisionInfo::default(), "mesh").unwrap();
+/// ```
+pub fn set_executor<E: TMFExecutor + 'static>(executor: E) {
+    *EXECUTOR.write().unwrap_or_else(PoisonError::into_inner) = Some(Arc::new(executor));
+}
+/// Makes all following reads and writes of .tmf files go back to encoding and decoding segments without an executor.
+pub fn clear_executor() {
+    *EXECUTOR.write().unwrap_or_else(PoisonError::into_inner) = None;
+}
+/// Returns the executor set by [`set_executor`], if any.
+pub(crate) fn current() -> Option<Arc<dyn TMFExecutor>> {
+    EXECUTOR
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+/// Runs *job* on *executor*, returning a future resolving to its result.
+pub(crate) fn run_on<T: Send + 'static, F: FnOnce() -> T + Send + 'static>(
+    executor: &dyn TMFExecutor,
+    job: F,
+) -> impl Future<Output = T> {
+    let (sender, receiver) = futures::channel::oneshot::channel();
+    executor.spawn(Box::new(move || {
+        // The receiver is only gone if the read or write was abandoned.
+        let _ = sender.send(job());
+    }));
+    async move {
+        receiver
+            .await
+            .expect("Task run on a TMFExecutor panicked or was dropped")
+    }
+}
@@ -0,0 +1,675 @@
+// glTF always stores f32, while `FloatType` is f64 with `double_precision`, so casts are needed only in some configurations.
+#![allow(clippy::unnecessary_cast)]
+use crate::{Color, FloatType, IndexType, MaterialInfo, TMFMesh, Tangent, Vector2, Vector3};
+use gltf::json;
+use gltf::json::validation::{Checked, USize64};
+use std::borrow::{Borrow, Cow};
+use std::collections::HashMap;
+use std::io::{Error, Read, Result, Write};
+const DEFAULT_NORMAL: Vector3 = (0.0, 0.0, 1.0);
+const DEFAULT_UV: Vector2 = (0.0, 0.0);
+const DEFAULT_TANGENT: Tangent = ((1.0, 0.0, 0.0), 1.0);
+const DEFAULT_COLOR: Color = (1.0, 1.0, 1.0, 1.0);
+/// Appends attribute *values* of a primitive with *count* vertices to *target*, which already holds attributes of *offset* vertices. Vertices of primitives missing an attribute present in other primitives get the *default* value.
+fn extend_attribute<T: Copy, I: Iterator<Item = T>>(
+    target: &mut Option<Vec<T>>,
+    values: Option<I>,
+    offset: usize,
+    count: usize,
+    default: T,
+) {
+    match values {
+        Some(values) => {
+            let target = target.get_or_insert_with(|| vec![default; offset]);
+            target.extend(values.take(count));
+            target.resize(offset + count, default);
+        }
+        None => {
+            if let Some(target) = target {
+                target.resize(offset + count, default);
+            }
+        }
+    }
+}
+fn to_index(index: usize) -> Result<IndexType> {
+    IndexType::try_from(index)
+        .map_err(|_| Error::other("glTF mesh has more vertices than IndexType can represent"))
+}
+/// Reads all primitives of a glTF mesh into a single [`TMFMesh`]. Each material used by a primitive becomes a material of the mesh.
+/// Primitives using the same attribute accessors share their vertices. A mesh made only of point primitives is read as a point cloud.
+fn read_mesh(mesh: &gltf::Mesh, buffers: &[gltf::buffer::Data]) -> Result<TMFMesh> {
+    let mut vertices: Vec<Vector3> = Vec::new();
+    let mut normals = None;
+    let mut uvs = None;
+    let mut tangents = None;
+    let mut colors = None;
+    let mut triangles: Vec<IndexType> = Vec::new();
+    let mut has_triangles = false;
+    let mut materials = MaterialInfo::new();
+    // Offset and count of vertices read from each set of attribute accessors.
+    let mut read_attributes: HashMap<Vec<usize>, (usize, usize)> = HashMap::new();
+    for primitive in mesh.primitives() {
+        let is_points = match primitive.mode() {
+            gltf::mesh::Mode::Triangles => false,
+            gltf::mesh::Mode::Points => true,
+            mode => {
+                return Err(Error::other(format!(
+                "Unsupported glTF primitive mode {mode:?}, only triangles and points are supported"
+            )))
+            }
+        };
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data[..]));
+        let accessors: Vec<usize> = primitive
+            .attributes()
+            .map(|(_, accessor)| accessor.index())
+            .collect();
+        let (offset, count) = match read_attributes.get(&accessors) {
+            Some(read) => *read,
+            None => {
+                let read = read_attributes_of(
+                    &reader,
+                    &mut vertices,
+                    &mut normals,
+                    &mut uvs,
+                    &mut tangents,
+                    &mut colors,
+                )?;
+                read_attributes.insert(accessors, read);
+                read
+            }
+        };
+        if is_points {
+            continue;
+        }
+        has_triangles = true;
+        let first_triangle = triangles.len() / 3;
+        match reader.read_indices() {
+            Some(indices) => {
+                for index in indices.into_u32() {
+                    let index = index as usize;
+                    if index >= count {
+                        return Err(Error::other("glTF primitive index out of bounds"));
+                    }
+                    triangles.push(to_index(offset + index)?);
+                }
+            }
+            None => {
+                for index in offset..(offset + count) {
+                    triangles.push(to_index(index)?);
+                }
+            }
+        }
+        if triangles.len() % 3 != 0 {
+            return Err(Error::other(
+                "glTF primitive index count is not a multiple of 3",
+            ));
+        }
+        let material = primitive.material();
+        if let Some(index) = material.index() {
+            let name = material
+                .name()
+                .map_or_else(|| format!("material{index}"), str::to_owned);
+            let material = materials.add_material(&name);
+            let last_triangle = triangles.len() / 3;
+            if last_triangle > first_triangle {
+                materials.add_triangle_range(
+                    material,
+                    to_index(first_triangle)?..to_index(last_triangle)?,
+                );
+            }
+        }
+    }
+    let mut res = TMFMesh::empty();
+    res.set_vertices(vertices);
+    if let Some(normals) = normals {
+        res.set_normals(normals);
+    }
+    if let Some(uvs) = uvs {
+        res.set_uvs(uvs);
+    }
+    if let Some(tangents) = tangents {
+        res.set_tangents(tangents);
+    }
+    if let Some(colors) = colors {
+        res.set_colors(colors);
+    }
+    if !has_triangles {
+        return Ok(res);
+    }
+    if res.get_normals().is_some() {
+        res.set_normal_triangles(triangles.clone());
+    }
+    if res.get_uvs().is_some() {
+        res.set_uv_triangles(triangles.clone());
+    }
+    if res.get_tangents().is_some() {
+        res.set_tangent_triangles(triangles.clone());
+    }
+    if res.get_colors().is_some() {
+        res.set_color_triangles(triangles.clone());
+    }
+    if !materials.materials().is_empty() {
+        res.set_materials(materials);
+    }
+    res.set_vertex_triangles(triangles);
+    Ok(res)
+}
+/// Appends vertex attributes of the primitive read by *reader* to the attribute arrays, returning the offset of its first vertex and its vertex count.
+fn read_attributes_of<'a, 's, F: Clone + Fn(gltf::Buffer<'a>) -> Option<&'s [u8]>>(
+    reader: &gltf::mesh::Reader<'a, 's, F>,
+    vertices: &mut Vec<Vector3>,
+    normals: &mut Option<Vec<Vector3>>,
+    uvs: &mut Option<Vec<Vector2>>,
+    tangents: &mut Option<Vec<Tangent>>,
+    colors: &mut Option<Vec<Color>>,
+) -> Result<(usize, usize)> {
+    let positions = reader
+        .read_positions()
+        .ok_or_else(|| Error::other("glTF primitive has no positions"))?;
+    let offset = vertices.len();
+    vertices.extend(positions.map(|[x, y, z]| (x as FloatType, y as FloatType, z as FloatType)));
+    let count = vertices.len() - offset;
+    extend_attribute(
+        normals,
+        reader
+            .read_normals()
+            .map(|iter| iter.map(|[x, y, z]| (x as FloatType, y as FloatType, z as FloatType))),
+        offset,
+        count,
+        DEFAULT_NORMAL,
+    );
+    // glTF places the UV origin in the top left corner, while TMF(like .obj) uses the bottom left one.
+    extend_attribute(
+        uvs,
+        reader.read_tex_coords(0).map(|iter| {
+            iter.into_f32()
+                .map(|[u, v]| (u as FloatType, 1.0 - v as FloatType))
+        }),
+        offset,
+        count,
+        DEFAULT_UV,
+    );
+    extend_attribute(
+        tangents,
+        reader.read_tangents().map(|iter| {
+            iter.map(|[x, y, z, w]| {
+                (
+                    (x as FloatType, y as FloatType, z as FloatType),
+                    w as FloatType,
+                )
+            })
+        }),
+        offset,
+        count,
+        DEFAULT_TANGENT,
+    );
+    extend_attribute(
+        colors,
+        reader.read_colors(0).map(|iter| {
+            iter.into_rgba_f32().map(|[r, g, b, a]| {
+                (
+                    r as FloatType,
+                    g as FloatType,
+                    b as FloatType,
+                    a as FloatType,
+                )
+            })
+        }),
+        offset,
+        count,
+        DEFAULT_COLOR,
+    );
+    Ok((offset, count))
+}
+pub fn read_from_gltf<R: Read>(reader: &mut R) -> Result<Vec<(TMFMesh, String)>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes)?;
+    let gltf = gltf::Gltf::from_slice(&bytes).map_err(Error::other)?;
+    let buffers =
+        gltf::import_buffers(&gltf.document, None, gltf.blob.clone()).map_err(Error::other)?;
+    let mut res = Vec::with_capacity(gltf.document.meshes().len());
+    for mesh in gltf.document.meshes() {
+        let name = mesh
+            .name()
+            .map_or_else(|| format!("mesh{}", mesh.index()), str::to_owned);
+        res.push((read_mesh(&mesh, &buffers)?, name));
+    }
+    Ok(res)
+}
+/// Builds a GLB file: its JSON document and the binary chunk holding all accessor data.
+struct GltfBuilder {
+    root: json::Root,
+    bin: Vec<u8>,
+    materials: HashMap<String, json::Index<json::Material>>,
+}
+impl GltfBuilder {
+    fn new() -> Self {
+        Self {
+            root: json::Root::default(),
+            bin: Vec::new(),
+            materials: HashMap::new(),
+        }
+    }
+    /// Appends *data* to the binary chunk and creates an accessor of *count* elements reading it.
+    fn push_accessor(
+        &mut self,
+        data: &[u8],
+        count: usize,
+        component_type: json::accessor::ComponentType,
+        type_: json::accessor::Type,
+        target: json::buffer::Target,
+        bounds: Option<(json::Value, json::Value)>,
+    ) -> json::Index<json::Accessor> {
+        // Buffer views must be aligned to the size of their components.
+        self.bin.resize(self.bin.len().next_multiple_of(4), 0);
+        let byte_offset = self.bin.len();
+        self.bin.extend_from_slice(data);
+        let view = self.root.push(json::buffer::View {
+            buffer: json::Index::new(0),
+            byte_length: USize64::from(data.len()),
+            byte_offset: Some(USize64::from(byte_offset)),
+            byte_stride: None,
+            name: None,
+            target: Some(Checked::Valid(target)),
+            extensions: None,
+            extras: Default::default(),
+        });
+        let (min, max) = match bounds {
+            Some((min, max)) => (Some(min), Some(max)),
+            None => (None, None),
+        };
+        self.root.push(json::Accessor {
+            buffer_view: Some(view),
+            byte_offset: None,
+            count: USize64::from(count),
+            component_type: Checked::Valid(json::accessor::GenericComponentType(component_type)),
+            extensions: None,
+            extras: Default::default(),
+            type_: Checked::Valid(type_),
+            min,
+            max,
+            name: None,
+            normalized: false,
+            sparse: None,
+        })
+    }
+    fn push_floats<const N: usize>(
+        &mut self,
+        values: &[[f32; N]],
+        bounds: bool,
+    ) -> json::Index<json::Accessor> {
+        let type_ = match N {
+            2 => json::accessor::Type::Vec2,
+            3 => json::accessor::Type::Vec3,
+            _ => json::accessor::Type::Vec4,
+        };
+        let bounds = bounds.then(|| {
+            let mut min = [f32::INFINITY; N];
+            let mut max = [f32::NEG_INFINITY; N];
+            for value in values {
+                for axis in 0..N {
+                    min[axis] = min[axis].min(value[axis]);
+                    max[axis] = max[axis].max(value[axis]);
+                }
+            }
+            (json::Value::from(&min[..]), json::Value::from(&max[..]))
+        });
+        let data: Vec<u8> = values
+            .iter()
+            .flat_map(|value| value.iter().flat_map(|component| component.to_le_bytes()))
+            .collect();
+        self.push_accessor(
+            &data,
+            values.len(),
+            json::accessor::ComponentType::F32,
+            type_,
+            json::buffer::Target::ArrayBuffer,
+            bounds,
+        )
+    }
+    fn push_indices(&mut self, indices: &[u32]) -> json::Index<json::Accessor> {
+        let data: Vec<u8> = indices
+            .iter()
+            .flat_map(|index| index.to_le_bytes())
+            .collect();
+        self.push_accessor(
+            &data,
+            indices.len(),
+            json::accessor::ComponentType::U32,
+            json::accessor::Type::Scalar,
+            json::buffer::Target::ElementArrayBuffer,
+            None,
+        )
+    }
+    fn material(&mut self, name: &str) -> json::Index<json::Material> {
+        if let Some(index) = self.materials.get(name) {
+            return *index;
+        }
+        let index = self.root.push(json::Material {
+            name: Some(name.to_owned()),
+            ..Default::default()
+        });
+        self.materials.insert(name.to_owned(), index);
+        index
+    }
+    /// Returns the triangle array *triangles* if it has an index for each corner of the mesh.
+    fn corner_indices(
+        triangles: Option<&[IndexType]>,
+        corner_count: usize,
+    ) -> Option<&[IndexType]> {
+        triangles.filter(|triangles| triangles.len() == corner_count)
+    }
+    fn push_mesh(&mut self, mesh: &TMFMesh, name: &str) -> Result<json::Index<json::Mesh>> {
+        let vertices = mesh.get_vertices().unwrap_or(&[]);
+        let Some(vertex_triangles) = mesh.get_vertex_triangles() else {
+            // A mesh without triangles is saved as a point cloud.
+            let positions: Vec<[f32; 3]> = vertices
+                .iter()
+                .map(|v| [v.0 as f32, v.1 as f32, v.2 as f32])
+                .collect();
+            let mut attributes = std::collections::BTreeMap::new();
+            attributes.insert(
+                Checked::Valid(json::mesh::Semantic::Positions),
+                self.push_floats(&positions, true),
+            );
+            // Point clouds have no triangles, so only attributes with a value for each point can be saved.
+            let per_point = |len: usize| len == vertices.len();
+            if let Some(normals) = mesh
+                .get_normals()
+                .filter(|normals| per_point(normals.len()))
+            {
+                let normals: Vec<[f32; 3]> = normals
+                    .iter()
+                    .map(|n| [n.0 as f32, n.1 as f32, n.2 as f32])
+                    .collect();
+                attributes.insert(
+                    Checked::Valid(json::mesh::Semantic::Normals),
+                    self.push_floats(&normals, false),
+                );
+            }
+            if let Some(colors) = mesh.get_colors().filter(|colors| per_point(colors.len())) {
+                let colors: Vec<[f32; 4]> = colors
+                    .iter()
+                    .map(|c| [c.0 as f32, c.1 as f32, c.2 as f32, c.3 as f32])
+                    .collect();
+                attributes.insert(
+                    Checked::Valid(json::mesh::Semantic::Colors(0)),
+                    self.push_floats(&colors, false),
+                );
+            }
+            return Ok(self.root.push(json::Mesh {
+                extensions: None,
+                extras: Default::default(),
+                name: Some(name.to_owned()),
+                primitives: vec![json::mesh::Primitive {
+                    attributes,
+                    extensions: None,
+                    extras: Default::default(),
+                    indices: None,
+                    material: None,
+                    mode: Checked::Valid(json::mesh::Mode::Points),
+                    targets: None,
+                }],
+                weights: None,
+            }));
+        };
+        let corner_count = vertex_triangles.len();
+        let normals = mesh.get_normals().zip(Self::corner_indices(
+            mesh.get_normal_triangles(),
+            corner_count,
+        ));
+        let uvs = mesh
+            .get_uvs()
+            .zip(Self::corner_indices(mesh.get_uv_triangles(), corner_count));
+        let tangents = mesh.get_tangents().zip(Self::corner_indices(
+            mesh.get_tangent_triangles(),
+            corner_count,
+        ));
+        let colors = mesh.get_colors().zip(Self::corner_indices(
+            mesh.get_color_triangles(),
+            corner_count,
+        ));
+        // glTF has one index per corner, shared by all attributes, so each unique combination of TMF indices becomes a separate vertex.
+        let mut unified: HashMap<[IndexType; 5], u32> = HashMap::new();
+        let mut positions: Vec<[f32; 3]> = Vec::new();
+        let mut out_normals: Vec<[f32; 3]> = Vec::new();
+        let mut out_uvs: Vec<[f32; 2]> = Vec::new();
+        let mut out_tangents: Vec<[f32; 4]> = Vec::new();
+        let mut out_colors: Vec<[f32; 4]> = Vec::new();
+        let mut indices: Vec<u32> = Vec::with_capacity(corner_count);
+        fn lookup<T: Copy>(values: &[T], index: IndexType) -> Result<T> {
+            values
+                .get(index as usize)
+                .copied()
+                .ok_or_else(|| Error::other("Triangle index out of bounds"))
+        }
+        for corner in 0..corner_count {
+            let key = [
+                vertex_triangles[corner],
+                normals.map_or(0, |(_, tris)| tris[corner]),
+                uvs.map_or(0, |(_, tris)| tris[corner]),
+                tangents.map_or(0, |(_, tris)| tris[corner]),
+                colors.map_or(0, |(_, tris)| tris[corner]),
+            ];
+            if let Some(index) = unified.get(&key) {
+                indices.push(*index);
+                continue;
+            }
+            let index = u32::try_from(positions.len())
+                .map_err(|_| Error::other("Mesh has too many vertices for glTF"))?;
+            let v = lookup(vertices, key[0])?;
+            positions.push([v.0 as f32, v.1 as f32, v.2 as f32]);
+            if let Some((normals, _)) = normals {
+                let n = lookup(normals, key[1])?;
+                out_normals.push([n.0 as f32, n.1 as f32, n.2 as f32]);
+            }
+            if let Some((uvs, _)) = uvs {
+                let uv = lookup(uvs, key[2])?;
+                out_uvs.push([uv.0 as f32, 1.0 - uv.1 as f32]);
+            }
+            if let Some((tangents, _)) = tangents {
+                let (t, w) = lookup(tangents, key[3])?;
+                out_tangents.push([t.0 as f32, t.1 as f32, t.2 as f32, w as f32]);
+            }
+            if let Some((colors, _)) = colors {
+                let c = lookup(colors, key[4])?;
+                out_colors.push([c.0 as f32, c.1 as f32, c.2 as f32, c.3 as f32]);
+            }
+            unified.insert(key, index);
+            indices.push(index);
+        }
+        let mut attributes = std::collections::BTreeMap::new();
+        attributes.insert(
+            Checked::Valid(json::mesh::Semantic::Positions),
+            self.push_floats(&positions, true),
+        );
+        if normals.is_some() {
+            attributes.insert(
+                Checked::Valid(json::mesh::Semantic::Normals),
+                self.push_floats(&out_normals, false),
+            );
+        }
+        if uvs.is_some() {
+            attributes.insert(
+                Checked::Valid(json::mesh::Semantic::TexCoords(0)),
+                self.push_floats(&out_uvs, false),
+            );
+        }
+        if tangents.is_some() {
+            attributes.insert(
+                Checked::Valid(json::mesh::Semantic::Tangents),
+                self.push_floats(&out_tangents, false),
+            );
+        }
+        if colors.is_some() {
+            attributes.insert(
+                Checked::Valid(json::mesh::Semantic::Colors(0)),
+                self.push_floats(&out_colors, false),
+            );
+        }
+        // Each run of triangles using the same material becomes a separate primitive.
+        let mut runs: Vec<(Option<&str>, Vec<u32>)> = Vec::new();
+        for (triangle, corners) in indices.chunks(3).enumerate() {
+            let material = mesh
+                .get_materials()
+                .and_then(|materials| materials.lookup_material(triangle as IndexType));
+            match runs.last_mut() {
+                Some((last, run)) if *last == material => run.extend_from_slice(corners),
+                _ => runs.push((material, corners.to_vec())),
+            }
+        }
+        let mut primitives = Vec::with_capacity(runs.len());
+        for (material, run) in runs {
+            let material = material.map(|name| self.material(name));
+            primitives.push(json::mesh::Primitive {
+                attributes: attributes.clone(),
+                extensions: None,
+                extras: Default::default(),
+                indices: Some(self.push_indices(&run)),
+                material,
+                mode: Checked::Valid(json::mesh::Mode::Triangles),
+                targets: None,
+            });
+        }
+        Ok(self.root.push(json::Mesh {
+            extensions: None,
+            extras: Default::default(),
+            name: Some(name.to_owned()),
+            primitives,
+            weights: None,
+        }))
+    }
+    fn write<W: Write>(mut self, w: &mut W) -> Result<()> {
+        self.bin.resize(self.bin.len().next_multiple_of(4), 0);
+        self.root.push(json::Buffer {
+            byte_length: USize64::from(self.bin.len()),
+            name: None,
+            uri: None,
+            extensions: None,
+            extras: Default::default(),
+        });
+        let json = self.root.to_vec().map_err(Error::other)?;
+        let glb = gltf::binary::Glb {
+            header: gltf::binary::Header {
+                magic: *b"glTF",
+                version: 2,
+                // Computed by `to_writer`
+                length: 0,
+            },
+            bin: Some(Cow::Borrowed(&self.bin)),
+            json: Cow::Owned(json),
+        };
+        glb.to_writer(w).map_err(Error::other)
+    }
+}
+pub fn write_gltf<W: Write, S: Borrow<str>>(meshes: &[(TMFMesh, S)], w: &mut W) -> Result<()> {
+    let mut builder = GltfBuilder::new();
+    let mut nodes = Vec::with_capacity(meshes.len());
+    for (mesh, name) in meshes {
+        let mesh = builder.push_mesh(mesh, name.borrow())?;
+        nodes.push(builder.root.push(json::Node {
+            mesh: Some(mesh),
+            name: Some(name.borrow().to_owned()),
+            ..Default::default()
+        }));
+    }
+    let scene = builder.root.push(json::Scene {
+        extensions: None,
+        extras: Default::default(),
+        name: None,
+        nodes,
+    });
+    builder.root.scene = Some(scene);
+    builder.write(w)
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    #[cfg(feature = "obj_import")]
+    fn rw_susan_gltf() {
+        let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+        let (tmf_mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+        let mut out = Vec::new();
+        write_gltf(&[(tmf_mesh.clone(), name.as_str())], &mut out).unwrap();
+        let meshes = read_from_gltf(&mut &out[..]).unwrap();
+        assert_eq!(meshes.len(), 1);
+        let (read_mesh, read_name) = &meshes[0];
+        assert_eq!(*read_name, name);
+        read_mesh.verify().unwrap();
+        let expected = tmf_mesh.get_vertex_triangles().unwrap();
+        let read = read_mesh.get_vertex_triangles().unwrap();
+        assert_eq!(expected.len(), read.len());
+        for (expected, read) in expected.iter().zip(read.iter()) {
+            let expected = tmf_mesh.get_vertices().unwrap()[*expected as usize];
+            let read = read_mesh.get_vertices().unwrap()[*read as usize];
+            assert!((expected.0 - read.0).abs() < 0.0001);
+            assert!((expected.1 - read.1).abs() < 0.0001);
+            assert!((expected.2 - read.2).abs() < 0.0001);
+        }
+        let expected_uvs = tmf_mesh.get_uv_buffer().unwrap();
+        let read_uvs = read_mesh.get_uv_buffer().unwrap();
+        for (expected, read) in expected_uvs.iter().zip(read_uvs.iter()) {
+            assert!((expected.0 - read.0).abs() < 0.0001);
+            assert!((expected.1 - read.1).abs() < 0.0001);
+        }
+        assert!(read_mesh.get_normals().is_some());
+    }
+    #[test]
+    fn rw_tangents_colors() {
+        let mut mesh = TMFMesh::empty();
+        mesh.set_vertices([
+            (0.0, 0.0, 0.0),
+            (1.0, 0.0, 0.0),
+            (0.0, 1.0, 0.0),
+            (1.0, 1.0, 0.0),
+        ]);
+        mesh.set_vertex_triangles([0, 1, 2, 2, 1, 3]);
+        mesh.set_tangents([((1.0, 0.0, 0.0), 1.0), ((0.0, 1.0, 0.0), -1.0)]);
+        mesh.set_tangent_triangles([0, 0, 0, 1, 1, 1]);
+        mesh.set_colors([(1.0, 0.0, 0.0, 1.0), (0.0, 0.0, 1.0, 0.5)]);
+        mesh.set_color_triangles([0, 0, 0, 1, 1, 1]);
+        mesh.set_materials(MaterialInfo::create(
+            ["red".to_owned(), "blue".to_owned()],
+            [(0, 0..1), (1, 1..2)],
+        ));
+        let mut out = Vec::new();
+        write_gltf(&[(mesh, "quad")], &mut out).unwrap();
+        let (read, name) = read_from_gltf(&mut &out[..]).unwrap().remove(0);
+        assert_eq!(name, "quad");
+        read.verify().unwrap();
+        let tangents = read.get_tangents().unwrap();
+        let tangent_triangles = read.get_tangent_triangles().unwrap();
+        assert_eq!(tangents[tangent_triangles[0] as usize].1, 1.0);
+        assert_eq!(tangents[tangent_triangles[5] as usize].1, -1.0);
+        let colors = read.get_colors().unwrap();
+        let color_triangles = read.get_color_triangles().unwrap();
+        assert_eq!(colors[color_triangles[4] as usize], (0.0, 0.0, 1.0, 0.5));
+        let materials = read.get_materials().unwrap();
+        assert_eq!(materials.lookup_material(0), Some("red"));
+        assert_eq!(materials.lookup_material(1), Some("blue"));
+        // Both primitives share one set of attributes, which is read only once.
+        assert_eq!(read.get_vertices().unwrap().len(), 6);
+    }
+    #[test]
+    fn rw_point_cloud() {
+        let mut mesh = TMFMesh::empty();
+        mesh.set_vertices([(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.5)]);
+        mesh.set_normals([(0.0, 0.0, 1.0), (0.0, 1.0, 0.0), (1.0, 0.0, 0.0)]);
+        mesh.set_colors([
+            (1.0, 0.0, 0.0, 1.0),
+            (0.0, 1.0, 0.0, 1.0),
+            (0.0, 0.0, 1.0, 0.5),
+        ]);
+        let mut out = Vec::new();
+        write_gltf(&[(mesh.clone(), "cloud")], &mut out).unwrap();
+        let (read, name) = read_from_gltf(&mut &out[..]).unwrap().remove(0);
+        assert_eq!(name, "cloud");
+        read.verify().unwrap();
+        assert_eq!(read.get_vertices(), mesh.get_vertices());
+        assert_eq!(read.get_normals(), mesh.get_normals());
+        assert_eq!(read.get_colors(), mesh.get_colors());
+        assert!(read.get_vertex_triangles().is_none());
+        assert!(read.get_normal_triangles().is_none());
+    }
+}
@@ -0,0 +1,180 @@
+use crate::IndexType;
+use std::ops::Range;
+/// A range of triangles belonging to the group at index *0*. Triangle indices are indices of whole triangles(not of the triangle index array).
+pub type GroupTriangleRange = (IndexType, Range<IndexType>);
+/// A range of triangles in the smoothing group with number *0*. Normals are interpolated only between adjacent triangles in the same smoothing group. Smoothing group numbers are not indices, and must not be 0(which means no smoothing).
+pub type SmoothingGroupRange = (IndexType, Range<IndexType>);
+/// Named groups of triangles within a mesh(e.g. parts of a model, which can be hidden or selected separately). A triangle may belong to any number of groups.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GroupInfo {
+    groups: Vec<String>,
+    group_ranges: Vec<GroupTriangleRange>,
+}
+impl GroupInfo {
+    /// Creates a new [`GroupInfo`] with no groups.
+    /// # Example
+    /// ```
+    /// # use tmf::GroupInfo;
+    /// let groups = GroupInfo::new();
+    /// assert!(groups.groups().is_empty());
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Creates a [`GroupInfo`] from group names and triangle ranges. Data is **not** checked, so call [`crate::TMFMesh::verify`] before saving.
+    /// # Example
+    /// ```
+    /// # use tmf::GroupInfo;
+    /// let groups = GroupInfo::create(["head".to_owned(), "ears".to_owned()], [(0, 0..12), (1, 12..16)]);
+    /// assert_eq!(groups.groups().len(), 2);
+    /// ```
+    pub fn create<G: Into<Vec<String>>, GR: Into<Vec<GroupTriangleRange>>>(
+        groups: G,
+        group_ranges: GR,
+    ) -> Self {
+        Self {
+            groups: groups.into(),
+            group_ranges: group_ranges.into(),
+        }
+    }
+    /// Adds group *name* and returns its index. If a group with the same name is already present, the index of it is returned instead.
+    /// # Example
+    /// ```
+    /// # use tmf::GroupInfo;
+    /// let mut groups = GroupInfo::new();
+    /// let head = groups.add_group("head");
+    /// let ears = groups.add_group("ears");
+    /// assert_ne!(head, ears);
+    /// assert_eq!(head, groups.add_group("head"));
+    /// ```
+    pub fn add_group(&mut self, name: &str) -> IndexType {
+        match self.groups.iter().position(|group| group == name) {
+            Some(index) => index as IndexType,
+            None => {
+                self.groups.push(name.to_owned());
+                (self.groups.len() - 1) as IndexType
+            }
+        }
+    }
+    /// Adds *triangles* to group with index *group*. If the range directly follows the last range of the same group, they are merged.
+    /// # Example
+    /// ```
+    /// # use tmf::GroupInfo;
+    /// let mut groups = GroupInfo::new();
+    /// let head = groups.add_group("head");
+    /// groups.add_triangle_range(head, 0..10);
+    /// groups.add_triangle_range(head, 10..14);
+    /// assert_eq!(groups.group_ranges(), &[(head, 0..14)]);
+    /// ```
+    pub fn add_triangle_range(&mut self, group: IndexType, triangles: Range<IndexType>) {
+        let last_range = self
+            .group_ranges
+            .iter_mut()
+            .rev()
+            .find(|(last_group, _)| *last_group == group);
+        if let Some((_, last_range)) = last_range {
+            if last_range.end == triangles.start {
+                last_range.end = triangles.end;
+                return;
+            }
+        }
+        self.group_ranges.push((group, triangles));
+    }
+    /// Returns the names of all groups.
+    #[must_use]
+    pub fn groups(&self) -> &[String] {
+        &self.groups
+    }
+    /// Returns all ranges of triangles and indices of groups they belong to.
+    #[must_use]
+    pub fn group_ranges(&self) -> &[GroupTriangleRange] {
+        &self.group_ranges
+    }
+    /// Returns the ranges of triangles belonging to group *name*.
+    /// # Example
+    /// ```
+    /// # use tmf::GroupInfo;
+    /// let groups = GroupInfo::create(["head".to_owned(), "ears".to_owned()], [(0, 0..12), (1, 12..16), (0, 20..24)]);
+    /// let head: Vec<_> = groups.lookup_group("head").collect();
+    /// assert_eq!(head, [0..12, 20..24]);
+    /// ```
+    pub fn lookup_group<'a>(&'a self, name: &str) -> impl Iterator<Item = Range<IndexType>> + 'a {
+        let group = self
+            .groups
+            .iter()
+            .position(|group| group == name)
+            .map(|group| group as IndexType);
+        self.group_ranges
+            .iter()
+            .filter(move |(range_group, _)| Some(*range_group) == group)
+            .map(|(_, range)| range.clone())
+    }
+    /// Returns the names of all groups triangle *triangle* belongs to.
+    /// # Example
+    /// ```
+    /// # use tmf::GroupInfo;
+    /// let groups = GroupInfo::create(["head".to_owned(), "ears".to_owned()], [(0, 0..16), (1, 12..16)]);
+    /// let groups: Vec<_> = groups.triangle_groups(13).collect();
+    /// assert_eq!(groups, ["head", "ears"]);
+    /// ```
+    pub fn triangle_groups(&self, triangle: IndexType) -> impl Iterator<Item = &str> {
+        self.group_ranges
+            .iter()
+            .filter(move |(_, range)| range.contains(&triangle))
+            .filter_map(|(group, _)| self.groups.get(*group as usize))
+            .map(String::as_str)
+    }
+    /// Appends groups in *other* to self, remapping group indices of triangle ranges in *other* to match groups in self.
+    pub(crate) fn append(&mut self, other: &Self) {
+        let map: Vec<IndexType> = other
+            .groups
+            .iter()
+            .map(|name| self.add_group(name))
+            .collect();
+        for (group, range) in &other.group_ranges {
+            let group = map.get(*group as usize).copied().unwrap_or(*group);
+            self.add_triangle_range(group, range.clone());
+        }
+    }
+    pub(crate) fn extend_groups(&mut self, groups: &[String]) {
+        self.groups.extend_from_slice(groups);
+    }
+    pub(crate) fn extend_ranges(&mut self, ranges: &[GroupTriangleRange]) {
+        self.group_ranges.extend_from_slice(ranges);
+    }
+}
+#[test]
+#[cfg(feature = "obj_import")]
+fn rw_groups_tmf() {
+    use crate::{TMFMesh, TMFPrecisionInfo};
+    let obj = b"o Quads
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+v 0 0 1
+vt 0 0
+vn 0 0 1
+g base
+f 1/1/1 2/1/1 3/1/1
+f 1/1/1 3/1/1 4/1/1
+g top base
+f 1/1/1 2/1/1 5/1/1
+g
+f 2/1/1 3/1/1 5/1/1
+";
+    let (tmf_mesh, name) = TMFMesh::read_from_obj_one(&mut &obj[..]).unwrap();
+    tmf_mesh.verify().unwrap();
+    let groups = tmf_mesh.get_groups().expect("Mesh has no groups!");
+    assert_eq!(groups.groups(), ["base", "top"]);
+    assert_eq!(groups.group_ranges(), &[(0, 0..3), (1, 2..3)]);
+    assert_eq!(groups.triangle_groups(3).count(), 0);
+    let mut out = Vec::new();
+    tmf_mesh
+        .write_tmf_one(&mut out, &TMFPrecisionInfo::default(), name)
+        .unwrap();
+    let (r_mesh, _) = TMFMesh::read_tmf_one(&mut (&out as &[u8])).unwrap();
+    r_mesh.verify().unwrap();
+    assert_eq!(r_mesh.get_groups(), Some(groups));
+}
@@ -0,0 +1,279 @@
+use crate::read_extension::ReadExt;
+use crate::tmf::{CompressionType, DecodedSegment, EncodedSegment, SectionType};
+use crate::tmf_importer::{read_string, TMFImportContext};
+use crate::{TMFExportError, TMFImportError, TMFMesh, TMFPrecisionInfo};
+use std::io::Read;
+/// Report describing a single segment of a TMF file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TMFSegmentReport {
+    /// Type of this segment.
+    pub seg_type: SectionType,
+    /// Compression used by this segment.
+    pub compression_type: CompressionType,
+    /// Length of segment data in bytes, excluding the segment header.
+    pub byte_len: usize,
+    /// Amount of bits used to save each value in this segment. Segments saving a few kinds of values have more than one entry(e.g. vertices have precision of x, y and z axis, LZZ compressed triangles have precision of indices, back-reference offsets and lengths, Sequence compressed triangles have precision of indices and run lengths, Delta compressed triangles have precision of indices and order of variable-length codes, connectivity coded triangles have precision of edge and vertex FIFO positions and order of variable-length codes). Empty if not known for this segment type.
+    pub precision_bits: Vec<u8>,
+    /// Amount of elements(vertices, indices, names, ranges, etc.) stored in this segment, or `None` if the segment could not be decoded.
+    pub element_count: Option<usize>,
+}
+/// Report describing a single mesh inside a TMF file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TMFMeshReport {
+    /// Name of the mesh.
+    pub name: String,
+    /// All segments of the mesh, in the order they are stored in.
+    pub segments: Vec<TMFSegmentReport>,
+}
+impl TMFMeshReport {
+    /// Returns the total length of data of all segments, in bytes.
+    #[must_use]
+    pub fn total_byte_len(&self) -> usize {
+        self.segments.iter().map(|seg| seg.byte_len).sum()
+    }
+    /// Returns the total length of data of all segments of type *seg_type*, in bytes.
+    /// # Example
+    /// ```
+    /// # use tmf::{TMFMesh,TMFPrecisionInfo,SectionType};
+    /// # let mut mesh = TMFMesh::empty();
+    /// # mesh.set_vertices([(0.0,0.0,0.0),(1.0,0.0,0.0),(0.0,1.0,0.0)]);
+    /// # mesh.set_vertex_triangles([0,1,2]);
+    /// let report = mesh.inspect("mesh", &TMFPrecisionInfo::default()).unwrap();
+    /// let vertex_bytes = report.byte_len_of(SectionType::VertexSegment);
+    /// assert!(vertex_bytes > 0);
+    /// ```
+    #[must_use]
+    pub fn byte_len_of(&self, seg_type: SectionType) -> usize {
+        self.segments
+            .iter()
+            .filter(|seg| seg.seg_type == seg_type)
+            .map(|seg| seg.byte_len)
+            .sum()
+    }
+}
+/// Report describing the structure of a TMF file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TMFFileReport {
+    /// Version of format used to save the file, as (major, minor).
+    pub version: (u16, u16),
+    /// Minimal version of reader required to read the file, as (major, minor).
+    pub min_version: (u16, u16),
+    /// All meshes in the file, in the order they are stored in.
+    pub meshes: Vec<TMFMeshReport>,
+}
+impl TMFFileReport {
+    /// Returns the total length of data of all segments in all meshes, in bytes.
+    #[must_use]
+    pub fn total_byte_len(&self) -> usize {
+        self.meshes.iter().map(TMFMeshReport::total_byte_len).sum()
+    }
+}
+/// Reads precision of values stored in a segment from its data, without decoding it.
+fn precision_bits(seg: &EncodedSegment, ctx: &TMFImportContext) -> Result<Vec<u8>, TMFImportError> {
+    let mut src = seg.data();
+    Ok(match seg.seg_type() {
+        SectionType::VertexSegment => {
+            // Skip vertex count and bounding box
+            let _count = src.read_u64()?;
+            for _ in 0..6 {
+                src.read_f64()?;
+            }
+            vec![src.read_u8()?, src.read_u8()?, src.read_u8()?]
+        }
+        SectionType::NormalSegment | SectionType::TangentSegment => {
+            let _count = src.read_u64()?;
+            vec![src.read_u8()?]
+        }
+        SectionType::UvSegment | SectionType::ColorSegment => vec![src.read_u8()?],
+        SectionType::MaterialTriangleRanges
+        | SectionType::GroupTriangleRanges
+        | SectionType::SmoothingGroupRanges => {
+            let _count = src.read_u64()?;
+            vec![src.read_u8()?, src.read_u8()?]
+        }
+        SectionType::ConnectivityTriangleSegment => {
+            let _kind = src.read_u8()?;
+            let _length = src.read_u64()?;
+            let _min = src.read_u64()?;
+            vec![src.read_u8()?, src.read_u8()?, src.read_u8()?]
+        }
+        seg_type if seg_type.is_triangle() => {
+            if seg_type == SectionType::SharedTriangleSegment {
+                let _kind = src.read_u8()?;
+            }
+            let precision = src.read_u8()?;
+            let _length = src.read_u64()?;
+            match seg.compresion_type() {
+                CompressionType::None => vec![precision],
+                CompressionType::UnalignedLZZ => {
+                    let _min = ctx.read_traingle_min(&mut src)?;
+                    vec![precision, src.read_u8()?, src.read_u8()?]
+                }
+                CompressionType::Delta => {
+                    let _min = ctx.read_traingle_min(&mut src)?;
+                    vec![precision, src.read_u8()?]
+                }
+                CompressionType::Sequence => {
+                    let _min = ctx.read_traingle_min(&mut src)?;
+                    vec![precision, src.read_u8()?]
+                }
+                _ => Vec::new(),
+            }
+        }
+        _ => Vec::new(),
+    })
+}
+async fn inspect_segment(seg: EncodedSegment, ctx: &TMFImportContext) -> TMFSegmentReport {
+    let seg_type = seg.seg_type();
+    let compression_type = seg.compresion_type();
+    let byte_len = seg.data().len();
+    let precision_bits = precision_bits(&seg, ctx).unwrap_or_default();
+    let element_count = DecodedSegment::decode(seg, ctx)
+        .await
+        .ok()
+        .and_then(|decoded| decoded.element_count());
+    TMFSegmentReport {
+        seg_type,
+        compression_type,
+        byte_len,
+        precision_bits,
+        element_count,
+    }
+}
+async fn inspect_mesh<R: Read>(
+    mut src: R,
+    ctx: &TMFImportContext,
+) -> Result<TMFMeshReport, TMFImportError> {
+    let name = read_string(&mut src)?;
+    let segment_count = src.read_u16()?;
+    let mut segments = Vec::with_capacity(segment_count as usize);
+    for _ in 0..segment_count {
+        let encoded = EncodedSegment::read(ctx, &mut src)?;
+        segments.push(inspect_segment(encoded, ctx).await);
+    }
+    Ok(TMFMeshReport { name, segments })
+}
+pub(crate) async fn inspect_tmf<R: Read>(mut src: R) -> Result<TMFFileReport, TMFImportError> {
+    let (ctx, mesh_count) = TMFImportContext::read_header(&mut src).await?;
+    let mut meshes = Vec::with_capacity((u16::MAX as usize).min(mesh_count as usize));
+    for _ in 0..mesh_count {
+        meshes.push(inspect_mesh(&mut src, &ctx).await?);
+    }
+    Ok(TMFFileReport {
+        version: ctx.version(),
+        min_version: ctx.min_version(),
+        meshes,
+    })
+}
+/// Reads the structure of a TMF file from *reader*, returning a report describing its meshes and segments. Segments are decoded only to count their elements, and no meshes are created.
+/// # Example
+/// ```
+/// # use std::fs::File;
+/// let mut file = File::open("testing/susan.tmf").expect("Could not open .tmf file");
+/// let report = tmf::inspect(&mut file).expect("Could not inspect .tmf file!");
+/// for mesh in &report.meshes{
+///     println!("{}: {} bytes", mesh.name, mesh.total_byte_len());
+/// }
+/// ```
+/// # Errors
+/// Returns: an IO error if it occurs, `NotTMFFile` if not tmf file, `NewerVersionRequired` if a newer importer is required for importing the file, and other errors for malformed tmf files.
+pub fn inspect<R: Read>(reader: &mut R) -> Result<TMFFileReport, TMFImportError> {
+    runtime_agnostic_block_on!(inspect_tmf(reader))
+}
+/// Reads the structure of a TMF file at *path*. See [`inspect`].
+/// # Example
+/// ```
+/// let report = tmf::inspect_file("testing/susan.tmf").expect("Could not inspect .tmf file!");
+/// assert_eq!(report.meshes.len(), 1);
+/// ```
+/// # Errors
+/// Returns: an IO error if it occurs, and the same errors as [`inspect`].
+pub fn inspect_file<P: AsRef<std::path::Path>>(path: P) -> Result<TMFFileReport, TMFImportError> {
+    let file = std::fs::File::open(path)?;
+    inspect(&mut std::io::BufReader::new(file))
+}
+impl TMFMesh {
+    /// Saves this mesh to memory, using precision settings *p_info*, and returns a report describing the saved segments.
+    /// # Example
+    /// ```
+    /// # use tmf::{TMFMesh,TMFPrecisionInfo};
+    /// # let mut mesh = TMFMesh::empty();
+    /// # mesh.set_vertices([(0.0,0.0,0.0),(1.0,0.0,0.0),(0.0,1.0,0.0)]);
+    /// # mesh.set_vertex_triangles([0,1,2]);
+    /// let report = mesh.inspect("mesh", &TMFPrecisionInfo::default()).expect("Could not save mesh!");
+    /// println!("Mesh takes {} bytes", report.total_byte_len());
+    /// ```
+    /// # Errors
+    /// Returns IO error if occurs.
+    pub fn inspect(
+        &self,
+        name: &str,
+        p_info: &TMFPrecisionInfo,
+    ) -> Result<TMFMeshReport, TMFExportError> {
+        let mut out = Vec::new();
+        self.write_tmf_one(&mut out, p_info, name)?;
+        let mut report =
+            inspect(&mut &out[..]).map_err(|err| std::io::Error::other(err.to_string()))?;
+        Ok(report.meshes.remove(0))
+    }
+}
+#[cfg(all(test, feature = "obj_import"))]
+mod test {
+    use super::*;
+    #[test]
+    fn inspect_susan() {
+        let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+        let (tmf_mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+        let mut out = Vec::new();
+        tmf_mesh
+            .write_tmf_one(&mut out, &TMFPrecisionInfo::default(), name.as_str())
+            .unwrap();
+        let report = inspect(&mut &out[..]).unwrap();
+        assert_eq!(report.version, (crate::TMF_MAJOR, crate::TMF_MINOR));
+        assert_eq!(report.meshes.len(), 1);
+        let mesh = &report.meshes[0];
+        assert_eq!(mesh.name, name);
+        // Segment data and headers(type, length and compression) and the mesh header make up the whole mesh.
+        let header_len = 3 + 4 * 2 + 4 + 2 + name.len() + 2;
+        assert_eq!(
+            report.total_byte_len() + header_len + mesh.segments.len() * 6,
+            out.len()
+        );
+        // Vertices may be split into multiple segments
+        let vertices: Vec<_> = mesh
+            .segments
+            .iter()
+            .filter(|seg| seg.seg_type == SectionType::VertexSegment)
+            .collect();
+        assert!(vertices.iter().all(|seg| seg.precision_bits.len() == 3));
+        assert_eq!(
+            vertices
+                .iter()
+                .map(|seg| seg.element_count.unwrap())
+                .sum::<usize>(),
+            tmf_mesh.get_vertices().unwrap().len()
+        );
+        let normals = mesh
+            .segments
+            .iter()
+            .find(|seg| seg.seg_type == SectionType::NormalSegment)
+            .expect("No normal segment!");
+        assert_eq!(
+            normals.precision_bits,
+            [crate::NormalPrecisionMode::default().bits()]
+        );
+        let triangle_count: usize = mesh
+            .segments
+            .iter()
+            .filter(|seg| seg.seg_type.is_triangle())
+            .map(|seg| seg.element_count.unwrap())
+            .sum();
+        assert!(triangle_count >= tmf_mesh.get_vertex_triangles().unwrap().len());
+        assert_eq!(
+            tmf_mesh
+                .inspect(&name, &TMFPrecisionInfo::default())
+                .unwrap(),
+            *mesh
+        );
+    }
+}
@@ -24,23 +24,44 @@ macro_rules! runtime_agnostic_block_on {
         }
     }};
 }
+mod color;
+mod connectivity;
 #[doc(hidden)]
 pub mod custom_data;
-#[allow(dead_code)]
+mod delta;
+mod executor;
+#[cfg(feature = "gltf")]
+mod gltf_io;
+mod group;
+mod inspect;
+mod lz77;
 mod material;
+mod material_library;
 #[cfg(feature = "model_importer")]
 mod model_importer;
 mod normals;
 #[cfg(feature = "obj_import")]
 mod obj;
+#[cfg(feature = "ply")]
+mod ply;
 mod read_extension;
 #[allow(dead_code)]
 mod reorder_triangles;
+mod sequence;
+#[cfg(feature = "stl")]
+mod stl;
 #[doc(hidden)]
 pub mod tangents;
 mod tmf;
 mod tmf_exporter;
 mod tmf_importer;
+mod tmf_index;
+mod tmf_reader;
+mod tmf_slice_reader;
+mod tmf_writer;
+#[cfg(feature = "triangulation")]
+mod triangulation;
+mod typed_mesh;
 /// Module used to handle reads of data which is not bit aligned(for example, 3 or 17 bits). This is the module that allows for heavy compression used in this format.
 #[doc(hidden)]
 pub mod unaligned_rw;
@@ -48,29 +69,30 @@ mod unify_data;
 mod utilis;
 mod uv;
 mod verify;
+mod vertex_buffer;
 mod vertices;
-// Unfinished
-#[allow(dead_code)]
-mod lz77;
 
 const TMF_MAJOR: u16 = 0;
-const TMF_MINOR: u16 = 2;
+const TMF_MINOR: u16 = 3;
 const MIN_TMF_MAJOR: u16 = 0;
-const MIN_TMF_MINOR: u16 = 2;
+const MIN_TMF_MINOR: u16 = 3;
 pub(crate) const MAX_SEG_SIZE: usize = 0x80_00_00_00; //Standard
                                                       //pub(crate) const MAX_SEG_SIZE: usize = 2_00_00;// for fuzzing!
-/// Index type used for representing triangle indices.
+/// Index type used for representing triangle indices in a [`TMFMesh`]. [`TMFMesh::read_tmf_as`] can read triangles into a different index type.
 #[cfg(not(any(feature = "long_indices", feature = "short_indices")))]
 pub type IndexType = u32;
 #[cfg(all(feature = "long_indices", feature = "short_indices"))]
 compile_error!("Size of indices can't be both long(u64) or short(u16)");
+/// Index type used for representing triangle indices in a [`TMFMesh`]. [`TMFMesh::read_tmf_as`] can read triangles into a different index type.
 #[cfg(feature = "long_indices")]
 pub type IndexType = u64;
+/// Index type used for representing triangle indices in a [`TMFMesh`]. [`TMFMesh::read_tmf_as`] can read triangles into a different index type.
 #[cfg(feature = "short_indices")]
 pub type IndexType = u16;
-/// Type used for representing floating-point numbers.
+/// Type used for representing floating-point numbers in a [`TMFMesh`]. [`TMFMesh::read_tmf_as`] can read data into a different float type.
 #[cfg(not(feature = "double_precision"))]
 pub type FloatType = f32;
+/// Type used for representing floating-point numbers in a [`TMFMesh`]. [`TMFMesh::read_tmf_as`] can read data into a different float type.
 #[cfg(feature = "double_precision")]
 pub type FloatType = f64;
 /// Type used for representing 4d floating-point vectors
@@ -79,18 +101,62 @@ pub type Vector4 = (FloatType, FloatType, FloatType, FloatType);
 pub type Vector3 = (FloatType, FloatType, FloatType);
 /// Type used for representing 2d floating-point vectors
 pub type Vector2 = (FloatType, FloatType);
+#[doc(inline)]
+pub use crate::color::{Color, ColorPrecisionMode};
 use crate::custom_data::CustomDataSegment;
 #[doc(inline)]
 pub use crate::custom_data::{CustomData, DataSegmentError};
-//#[doc(inline)]
-//use crate::material::MaterialInfo;
+#[doc(inline)]
+pub use crate::delta::TriangleEncodingMode;
+#[cfg(feature = "rayon")]
+#[doc(inline)]
+pub use crate::executor::RayonExecutor;
+#[doc(inline)]
+pub use crate::executor::{
+    clear_executor, set_executor, TMFExecutor, TMFTask, DEFAULT_MAX_SEGMENT_LENGTH,
+};
+#[doc(inline)]
+pub use crate::group::{GroupInfo, GroupTriangleRange, SmoothingGroupRange};
+#[doc(inline)]
+pub use crate::inspect::{inspect, inspect_file, TMFFileReport, TMFMeshReport, TMFSegmentReport};
+#[doc(inline)]
+pub use crate::material::{MaterialInfo, MaterialTriangleRange};
+#[doc(inline)]
+pub use crate::material_library::{MaterialDefinition, MaterialLibrary};
 #[doc(inline)]
 pub use crate::normals::NormalPrecisionMode;
+#[cfg(feature = "ply")]
+#[doc(inline)]
+pub use crate::ply::PlyFormat;
+#[cfg(feature = "stl")]
+#[doc(inline)]
+pub use crate::stl::StlFormat;
 #[doc(inline)]
 pub use crate::tangents::*;
 #[doc(inline)]
+pub use crate::tmf::{CompressionType, SectionType};
+#[doc(inline)]
+pub use crate::tmf_index::{TMFIndex, TMFSeekReader};
+#[doc(inline)]
+pub use crate::tmf_reader::TMFReader;
+#[doc(inline)]
+pub use crate::tmf_slice_reader::{TMFMeshBuffers, TMFSliceReader};
+#[doc(inline)]
+pub use crate::tmf_writer::TMFWriter;
+#[cfg(feature = "triangulation")]
+#[doc(inline)]
+pub use crate::triangulation::triangulate_polygon;
+#[doc(inline)]
+pub use crate::typed_mesh::{
+    ColorOf, MeshFloat, MeshIndex, TMFMeshOf, TangentOf, Vector2Of, Vector3Of,
+};
+#[doc(inline)]
 pub use crate::uv::UvPrecisionMode;
 #[doc(inline)]
+pub use crate::vertex_buffer::{
+    AttributeFormat, IndexBuffer, IndexFormat, InterleavedBuffers, VertexAttribute, VertexLayout,
+};
+#[doc(inline)]
 pub use crate::vertices::VertexPrecisionMode;
 use std::io::{Read, Write};
 use thiserror::Error;
@@ -102,6 +168,7 @@ lazy_static::lazy_static! {
     pub(crate) static ref TOKIO_RUNTIME: tokio::runtime::Runtime = tokio::runtime::Runtime::new().unwrap();
 }
 /// Settings for saving of a TMF mesh.
+#[derive(Clone, Copy)]
 pub struct TMFPrecisionInfo {
     /// How much can the position of any vertex deviate, as a portion of the shortest edge in the model.
     pub vertex_precision: VertexPrecisionMode,
@@ -111,6 +178,10 @@ pub struct TMFPrecisionInfo {
     pub uv_prec: crate::UvPrecisionMode,
     /// How much can saved tangents deviate
     pub tangent_prec: TangentPrecisionMode,
+    /// How much can saved vertex colors deviate.
+    pub color_prec: ColorPrecisionMode,
+    /// Which encodings may be used for triangles. Triangles are always saved losslessly.
+    pub triangle_encoding: TriangleEncodingMode,
 }
 impl Default for TMFPrecisionInfo {
     /// Returns the default, middle-ground settings for saving meshes. Should be indistinguishable by human eye, but the LOD may be not enough for some rare cases (eg. procedural generation).
@@ -120,6 +191,8 @@ impl Default for TMFPrecisionInfo {
             normal_precision: NormalPrecisionMode::default(),
             uv_prec: crate::UvPrecisionMode::default(),
             tangent_prec: TangentPrecisionMode::default(),
+            color_prec: ColorPrecisionMode::default(),
+            triangle_encoding: TriangleEncodingMode::default(),
         }
     }
 }
@@ -134,7 +207,12 @@ pub struct TMFMesh {
     uv_triangles: Option<Vec<IndexType>>,
     tangents: Option<Vec<Tangent>>,
     tangent_triangles: Option<Vec<IndexType>>,
-    //materials: Option<MaterialInfo>,
+    colors: Option<Vec<Color>>,
+    color_triangles: Option<Vec<IndexType>>,
+    materials: Option<MaterialInfo>,
+    groups: Option<GroupInfo>,
+    smoothing_groups: Option<Vec<SmoothingGroupRange>>,
+    material_library: Option<MaterialLibrary>,
     custom_data: Vec<CustomDataSegment>,
 }
 impl Default for TMFMesh {
@@ -186,6 +264,12 @@ impl TMFMesh {
             self.set_uvs(uvs);
             self.set_uv_triangles(uv_triangles);
         }
+        if let Some((colors, color_triangles)) = self.get_colors().zip(self.get_color_triangles()) {
+            let (color_triangles, colors) =
+                utilis::optimize_triangle_indices(color_triangles, colors);
+            self.set_colors(colors);
+            self.set_color_triangles(color_triangles);
+        }
     }
     /// Changes mesh data to make all index arrays(e.g. `vertex_triangle_array`,`normal_triangle_array`, etc.) exactly the same. Does not support custom index segments,  and will leave them unaffected.
     /// Very often drastically reduces mesh size.
@@ -209,16 +293,18 @@ impl TMFMesh {
     /// tmf_mesh.unify_index_data();
     /// ```
     pub fn unify_index_data(&mut self) {
-        let (vertices, normals, uvs, tangents, indices) = unify_data::smart_merge_data_4(
+        let (vertices, normals, uvs, tangents, colors, indices) = unify_data::smart_merge_data_5(
             self.get_vertices(),
             self.get_normals(),
             self.get_uvs(),
             self.get_tangents(),
+            self.get_colors(),
             [
                 self.get_vertex_triangles(),
                 self.get_normal_triangles(),
                 self.get_uv_triangles(),
                 self.get_tangent_triangles(),
+                self.get_color_triangles(),
             ],
         );
         if let Some(indices) = indices {
@@ -240,7 +326,11 @@ impl TMFMesh {
             if let Some(tangents) = tangents {
                 //println!("unfied uvs!");
                 self.set_tangents(tangents);
-                self.set_tangent_triangles(indices);
+                self.set_tangent_triangles(indices.clone());
+            }
+            if let Some(colors) = colors {
+                self.set_colors(colors);
+                self.set_color_triangles(indices);
             }
         }
         //todo!();
@@ -400,6 +490,44 @@ impl TMFMesh {
     ) -> Option<Vec<IndexType>> {
         self.tangent_triangles.replace(triangles.into())
     }
+    /// Sets mesh vertex color array and returns old color array if present. New mesh data is **not** checked during this function call, so to ensure mesh is valid call [`Self::verify`] before saving.
+    /// # Examples
+    /// ```
+    /// # use tmf::TMFMesh;
+    /// # let mut mesh = TMFMesh::empty();
+    /// let colors = [(1.0,0.0,0.0,1.0),(0.0,1.0,0.0,1.0),(0.0,0.0,1.0,1.0)];
+    /// mesh.set_colors(colors);
+    /// ```
+    pub fn set_colors<T: Into<Vec<Color>>>(&mut self, colors: T) -> Option<Vec<Color>> {
+        self.colors.replace(colors.into())
+    }
+    /// Sets color index array to *triangles* and returns old triangles if present.
+    /// # Example
+    ///```
+    /// # use tmf::TMFMesh;
+    /// # let mut mesh = TMFMesh::empty();
+    /// # let triangles = [0,1,2,3,2,1];
+    /// mesh.set_color_triangles(triangles);
+    ///```
+    pub fn set_color_triangles<T: Into<Vec<IndexType>>>(
+        &mut self,
+        triangles: T,
+    ) -> Option<Vec<IndexType>> {
+        self.color_triangles.replace(triangles.into())
+    }
+    /// Sets material info of this mesh to *materials* and returns old material info if present.
+    /// # Example
+    ///```
+    /// # use tmf::{TMFMesh,MaterialInfo};
+    /// # let mut mesh = TMFMesh::empty();
+    /// let mut materials = MaterialInfo::new();
+    /// let wood = materials.add_material("wood");
+    /// materials.add_triangle_range(wood, 0..2);
+    /// mesh.set_materials(materials);
+    ///```
+    pub fn set_materials(&mut self, materials: MaterialInfo) -> Option<MaterialInfo> {
+        self.materials.replace(materials)
+    }
     /// Gets the vertex array of this [`TMFMesh`].
     /// # Example
     ///```
@@ -512,6 +640,113 @@ impl TMFMesh {
             None => None,
         }
     }
+    /// Sets group info of this mesh to *groups* and returns old group info if present.
+    /// # Example
+    ///```
+    /// # use tmf::{TMFMesh,GroupInfo};
+    /// # let mut mesh = TMFMesh::empty();
+    /// let mut groups = GroupInfo::new();
+    /// let head = groups.add_group("head");
+    /// groups.add_triangle_range(head, 0..2);
+    /// mesh.set_groups(groups);
+    ///```
+    pub fn set_groups(&mut self, groups: GroupInfo) -> Option<GroupInfo> {
+        self.groups.replace(groups)
+    }
+    /// Gets the group info of this [`TMFMesh`].
+    /// # Example
+    ///```
+    /// # use tmf::TMFMesh;
+    /// # let mesh = TMFMesh::empty();
+    /// let groups = mesh.get_groups();
+    ///```
+    #[must_use]
+    pub fn get_groups(&self) -> Option<&GroupInfo> {
+        self.groups.as_ref()
+    }
+    /// Sets the smoothing groups of this [`TMFMesh`], and returns old smoothing groups if present. Triangles outside of all ranges are not smoothed. New data is **not** checked during this function call, so to ensure mesh is valid call [`Self::verify`] before saving.
+    /// # Example
+    ///```
+    /// # use tmf::TMFMesh;
+    /// # let mut mesh = TMFMesh::empty();
+    /// // Smooth the first 12 triangles together, and the next 4 separately from them.
+    /// mesh.set_smoothing_groups([(1, 0..12), (2, 12..16)]);
+    ///```
+    pub fn set_smoothing_groups<T: Into<Vec<SmoothingGroupRange>>>(
+        &mut self,
+        smoothing_groups: T,
+    ) -> Option<Vec<SmoothingGroupRange>> {
+        self.smoothing_groups.replace(smoothing_groups.into())
+    }
+    /// Gets the smoothing groups of this [`TMFMesh`].
+    /// # Example
+    ///```
+    /// # use tmf::TMFMesh;
+    /// # let mesh = TMFMesh::empty();
+    /// let smoothing_groups = mesh.get_smoothing_groups();
+    ///```
+    #[must_use]
+    pub fn get_smoothing_groups(&self) -> Option<&[SmoothingGroupRange]> {
+        self.smoothing_groups.as_deref()
+    }
+    /// Sets the material library of this [`TMFMesh`], and returns old material library if present. The library is saved alongside the mesh, defining properties of materials it uses.
+    /// # Example
+    ///```
+    /// # use tmf::{TMFMesh, MaterialDefinition, MaterialLibrary};
+    /// # let mut mesh = TMFMesh::empty();
+    /// let mut library = MaterialLibrary::new();
+    /// let mut wood = MaterialDefinition::new("wood");
+    /// wood.diffuse_map = Some("wood.png".to_owned());
+    /// library.add_material(wood);
+    /// mesh.set_material_library(library);
+    ///```
+    pub fn set_material_library(&mut self, library: MaterialLibrary) -> Option<MaterialLibrary> {
+        self.material_library.replace(library)
+    }
+    /// Gets the material library of this [`TMFMesh`].
+    /// # Example
+    ///```
+    /// # use tmf::TMFMesh;
+    /// # let mesh = TMFMesh::empty();
+    /// let library = mesh.get_material_library();
+    ///```
+    #[must_use]
+    pub fn get_material_library(&self) -> Option<&MaterialLibrary> {
+        self.material_library.as_ref()
+    }
+    /// Gets the vertex color array of this [`TMFMesh`].
+    /// # Example
+    ///```
+    /// # use tmf::TMFMesh;
+    /// # let mesh = TMFMesh::empty();
+    /// let colors = mesh.get_colors();
+    ///```
+    #[must_use]
+    pub fn get_colors(&self) -> Option<&[Color]> {
+        self.colors.as_deref()
+    }
+    /// Gets the color triangle index array of this [`TMFMesh`].
+    /// # Example
+    ///```
+    /// # use tmf::TMFMesh;
+    /// # let mesh = TMFMesh::empty();
+    /// let color_triangles = mesh.get_color_triangles();
+    ///```
+    #[must_use]
+    pub fn get_color_triangles(&self) -> Option<&[IndexType]> {
+        self.color_triangles.as_deref()
+    }
+    /// Gets the material info of this [`TMFMesh`].
+    /// # Example
+    ///```
+    /// # use tmf::TMFMesh;
+    /// # let mesh = TMFMesh::empty();
+    /// let materials = mesh.get_materials();
+    ///```
+    #[must_use]
+    pub fn get_materials(&self) -> Option<&MaterialInfo> {
+        self.materials.as_ref()
+    }
     /// Returns array containing points laid out in such a way that each 3 points create the next triangle.
     /// If mesh has no vertex array or no vertex triangle array [`None`] is returned.
     /// # Example
@@ -594,6 +829,50 @@ impl TMFMesh {
         }
         Some(uv_buffer.into())
     }
+    /// Returns a single interleaved vertex buffer, laid out as described by *layout*, and an index buffer, ready for upload to the GPU.
+    /// Index data is unified(as in [`Self::unify_index_data`]) on the fly: each unique combination of indices of attributes in *layout* becomes one vertex.
+    /// If mesh is missing any attribute in *layout*, has index arrays of different lengths, or out of bounds indices, or if indices do not fit in the index format set in *layout*, [`None`] is returned.
+    /// # Example
+    ///```
+    /// # use tmf::{TMFMesh,VertexLayout,VertexAttribute,AttributeFormat,IndexBuffer};
+    /// # let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+    /// # let (mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+    /// let layout = VertexLayout::new()
+    ///     .with_attribute(VertexAttribute::Position, AttributeFormat::F32)
+    ///     .with_attribute(VertexAttribute::Normal, AttributeFormat::Snorm16)
+    ///     .with_attribute(VertexAttribute::Uv, AttributeFormat::F16);
+    /// let buffers = mesh.get_interleaved_buffers(&layout).expect("Mesh is missing some attributes!");
+    /// assert_eq!(buffers.stride, layout.stride());
+    /// // Upload `buffers.vertices` and `buffers.indices.to_le_bytes()` to the GPU.
+    ///```
+    #[must_use]
+    pub fn get_interleaved_buffers(&self, layout: &VertexLayout) -> Option<InterleavedBuffers> {
+        vertex_buffer::interleave(self, layout)
+    }
+    /// Returns array containing vertex colors laid out in such a way that each 3 colors create the next triangle.
+    /// If mesh has no color array or no color triangle array [`None`] is returned.
+    /// # Example
+    ///```
+    /// # use tmf::TMFMesh;
+    /// # let mut mesh = TMFMesh::empty();
+    /// # let colors = [(1.0,0.0,0.0,1.0),(0.0,1.0,0.0,1.0),(0.0,0.0,1.0,1.0),(1.0,1.0,1.0,1.0)];
+    /// # let color_triangles = [0,1,2,0,2,3];
+    /// # mesh.set_colors(colors);
+    /// # mesh.set_color_triangles(color_triangles);
+    /// let color_buff = mesh.get_color_buffer().expect("Could not create the array of colors creating triangles!");
+    /// // The same number of triangles created by colors and triangles created by indices
+    /// assert!(color_buff.len() == color_triangles.len());
+    ///```
+    #[must_use]
+    pub fn get_color_buffer(&self) -> Option<Box<[Color]>> {
+        let colors = self.get_colors()?;
+        let triangles = self.get_color_triangles()?;
+        let mut color_buffer = Vec::with_capacity(triangles.len());
+        for index in triangles {
+            color_buffer.push(*colors.get(*index as usize)?);
+        }
+        Some(color_buffer.into())
+    }
     /// Normalizes normal array of the mesh, if it is present.
     /// # Examples
     ///```
@@ -643,7 +922,7 @@ impl TMFMesh {
         verify::verify_tmf_mesh(self)
     }
     /// Reads tmf meshes from a .obj file in *reader*
-    /// In order to enable triangulation while importing .obj files feature triangulation must be used. It is still highly experimental so read documentation before enabling. It is highly encouraged to just triangulate `.obj` files before importing them.  
+    /// Faces with more than 3 vertices require the triangulation feature.
     /// # Example
     ///```
     /// # use tmf::TMFMesh;
@@ -666,7 +945,7 @@ impl TMFMesh {
         obj::read_from_obj(reader)
     }
     /// Reads a *single* named tmf mesh from a .obj file in *reader*, if more than one mesh present an error will be returned.
-    /// In order to enable triangulation while importing .obj files feature triangulation must be used. It is still highly experimental so read documentation before enabling. It is highly encouraged to just triangulate `.obj` files before importing them.  
+    /// Faces with more than 3 vertices require the triangulation feature.
     /// # Example
     ///```
     /// # use tmf::TMFMesh;
@@ -685,18 +964,14 @@ impl TMFMesh {
         match meshes.next() {
             Some(mesh) => {
                 if meshes.next().is_some() {
-                    Err(std::io::Error::new(
-                        std::io::ErrorKind::Other,
+                    Err(std::io::Error::other(
                         "More than one mesh present in .obj file while only one expected.",
                     ))
                 } else {
                     Ok(mesh)
                 }
             }
-            None => Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "No meshes present in .obj file",
-            )),
+            None => Err(std::io::Error::other("No meshes present in .obj file")),
         }
     }
     /// Writes this TMF  mesh to a .obj file.
@@ -734,6 +1009,172 @@ impl TMFMesh {
     ) -> std::io::Result<()> {
         obj::write_obj(meshes, w)
     }
+    /// Reads a mesh or a point cloud from an ascii or binary .ply file in *reader*.
+    /// Vertex positions, normals, uvs(`u`/`v` or `s`/`t`) and colors(`red`, `green`, `blue` and optional `alpha`) are read into the mesh, all other scalar vertex properties are saved as custom data named after the property. A file with no faces is read as a point cloud.
+    /// Faces with more than 3 vertices require the triangulation feature.
+    /// # Example
+    ///```
+    /// # use tmf::TMFMesh;
+    /// let ply = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\nproperty float z\nproperty float intensity\nend_header\n0 0 0 0.1\n1 0 0 0.5\n0 1 0 0.9\n";
+    /// let point_cloud = TMFMesh::read_from_ply(&mut ply.as_bytes()).expect("Could not parse .ply file!");
+    /// assert_eq!(point_cloud.get_vertices().unwrap().len(), 3);
+    /// assert!(point_cloud.lookup_custom_data("intensity").is_some());
+    ///```
+    /// # Errors
+    /// Returns IO error if it occurs, or if the .ply file is malformed.
+    #[cfg(feature = "ply")]
+    pub fn read_from_ply<R: Read>(reader: &mut R) -> std::io::Result<Self> {
+        ply::read_from_ply(reader)
+    }
+    /// Writes this mesh to a .ply file in *format*.
+    /// Since PLY stores all attributes per vertex, each unique combination of vertex, normal, uv and color indices becomes a separate vertex. Float and integer custom data with a value for each vertex is saved as vertex properties. A mesh without triangles is saved as a point cloud.
+    /// # Example
+    ///```
+    /// # use tmf::{TMFMesh,PlyFormat};
+    /// # let mut mesh = TMFMesh::empty();
+    /// # mesh.set_vertices([(0.0,0.0,0.0),(1.0,0.0,0.0),(0.0,1.0,0.0)]);
+    /// let mut out = Vec::new();
+    /// mesh.write_ply(&mut out,PlyFormat::BinaryLittleEndian).expect("Could not write .ply file!");
+    ///```
+    /// # Errors
+    /// Returns IO error if it occurs, or if the mesh has out of bounds indices.
+    #[cfg(feature = "ply")]
+    pub fn write_ply<W: Write>(&self, w: &mut W, format: PlyFormat) -> std::io::Result<()> {
+        ply::write_ply(self, w, format)
+    }
+    /// Reads all solids from an ascii or binary .stl file in *reader*. The format is detected automatically.
+    /// Vertices shared by facets and equal facet normals are merged, so the mesh has separate vertex and normal triangle arrays. Zeroed facet normals are calculated from vertices. Call [`Self::unify_index_data`] to get a single index array.
+    /// # Example
+    ///```
+    /// # use tmf::TMFMesh;
+    /// let stl = "solid triangle\nfacet normal 0 0 1\nouter loop\nvertex 0 0 0\nvertex 1 0 0\nvertex 0 1 0\nendloop\nendfacet\nendsolid triangle\n";
+    /// let meshes = TMFMesh::read_from_stl(&mut stl.as_bytes()).expect("Could not parse .stl file!");
+    /// assert_eq!(meshes[0].1, "triangle");
+    ///```
+    /// # Errors
+    /// Returns IO error if it occurs, or if the .stl file is malformed.
+    #[cfg(feature = "stl")]
+    pub fn read_from_stl<R: Read>(reader: &mut R) -> std::io::Result<Vec<(Self, String)>> {
+        stl::read_from_stl(reader)
+    }
+    /// Reads a *single* named mesh from a .stl file in *reader*, if more than one solid present an error will be returned. See [`Self::read_from_stl`].
+    /// # Errors
+    /// Returns IO error if it occurs, the .stl file is malformed, or wrong mesh count.
+    #[cfg(feature = "stl")]
+    pub fn read_from_stl_one<R: Read>(reader: &mut R) -> std::io::Result<(Self, String)> {
+        let mut meshes = stl::read_from_stl(reader)?.into_iter();
+        match meshes.next() {
+            Some(mesh) => {
+                if meshes.next().is_some() {
+                    Err(std::io::Error::other(
+                        "More than one solid present in .stl file while only one expected.",
+                    ))
+                } else {
+                    Ok(mesh)
+                }
+            }
+            None => Err(std::io::Error::other("No solids present in .stl file")),
+        }
+    }
+    /// Writes multiple TMF meshes to a .stl file in *format*. Any mesh with vertex triangles can be written, with facet normals calculated from its vertices. Ascii files store each mesh as a separate solid, while binary files merge all meshes into one.
+    /// # Example
+    ///```
+    /// # use tmf::{TMFMesh,StlFormat};
+    /// # let meshes = [(TMFMesh::empty(),"a".to_owned()),(TMFMesh::empty(),"b".to_owned())];
+    /// let mut out = Vec::new();
+    /// TMFMesh::write_stl(&meshes,&mut out,StlFormat::Binary).expect("Could not export to .stl");
+    ///```
+    /// # Errors
+    /// Returns IO error if it occurs, or if the mesh has out of bounds indices.
+    #[cfg(feature = "stl")]
+    pub fn write_stl<W: Write, S: std::borrow::Borrow<str>>(
+        meshes: &[(TMFMesh, S)],
+        w: &mut W,
+        format: StlFormat,
+    ) -> std::io::Result<()> {
+        stl::write_stl(meshes, w, format)
+    }
+    /// Writes this TMF mesh to a .stl file in *format*. See [`Self::write_stl`].
+    /// # Errors
+    /// Returns IO error if it occurs, or if the mesh has out of bounds indices.
+    #[cfg(feature = "stl")]
+    pub fn write_stl_one<W: Write>(
+        &self,
+        w: &mut W,
+        name: &str,
+        format: StlFormat,
+    ) -> std::io::Result<()> {
+        stl::write_stl(&[(self.clone(), name)], w, format)
+    }
+    /// Reads all meshes from a glTF 2.0 file in *reader*. Both binary(.glb) files and .gltf files with buffers embedded as data URIs are supported. All primitives of a glTF mesh are merged into one [`TMFMesh`], with materials of primitives becoming materials of the mesh.
+    /// Only triangle and point primitives are supported, a mesh made only of points is read as a point cloud. Primitives using the same attribute accessors share their vertices. UVs are flipped vertically, to match the convention used by .obj and TMF.
+    /// # Example
+    ///```
+    /// # use tmf::TMFMesh;
+    /// # let mut mesh = TMFMesh::empty();
+    /// # mesh.set_vertices([(0.0,0.0,0.0),(1.0,0.0,0.0),(0.0,1.0,0.0)]);
+    /// # mesh.set_vertex_triangles([0,1,2]);
+    /// # let mut glb = Vec::new();
+    /// # TMFMesh::write_gltf(&[(mesh,"triangle")],&mut glb).unwrap();
+    /// # let mut reader = &glb[..];
+    /// let meshes = TMFMesh::read_from_gltf(&mut reader).expect("Could not parse glTF file!");
+    /// for (mesh,name) in meshes{
+    ///     println!("{name} has {} vertices", mesh.get_vertices().map_or(0,<[_]>::len));
+    /// }
+    ///```
+    /// # Errors
+    /// Returns IO error if it occurs, or if the glTF file is malformed or uses unsupported primitives.
+    #[cfg(feature = "gltf")]
+    pub fn read_from_gltf<R: Read>(reader: &mut R) -> std::io::Result<Vec<(Self, String)>> {
+        gltf_io::read_from_gltf(reader)
+    }
+    /// Reads a *single* named mesh from a glTF 2.0 file in *reader*, if more than one mesh present an error will be returned. See [`Self::read_from_gltf`].
+    /// # Errors
+    /// Returns IO error if it occurs, the glTF file is malformed, or wrong mesh count.
+    #[cfg(feature = "gltf")]
+    pub fn read_from_gltf_one<R: Read>(reader: &mut R) -> std::io::Result<(Self, String)> {
+        let mut meshes = gltf_io::read_from_gltf(reader)?.into_iter();
+        match meshes.next() {
+            Some(mesh) => {
+                if meshes.next().is_some() {
+                    Err(std::io::Error::other(
+                        "More than one mesh present in glTF file while only one expected.",
+                    ))
+                } else {
+                    Ok(mesh)
+                }
+            }
+            None => Err(std::io::Error::other("No meshes present in glTF file")),
+        }
+    }
+    /// Writes multiple TMF meshes to a binary glTF 2.0(.glb) file, each mesh in its own node of the default scene.
+    /// Since glTF uses one index per vertex for all attributes, each unique combination of vertex, normal, uv, tangent and color indices becomes a separate glTF vertex. Each run of triangles using the same material becomes a separate primitive, sharing vertices with the other runs. Meshes without triangles are saved as point primitives, with their per-point normals and colors.
+    /// # Example
+    ///```
+    /// # use std::fs::File;
+    /// # use tmf::TMFMesh;
+    /// # let meshes = [(TMFMesh::empty(),"a".to_owned()),(TMFMesh::empty(),"b".to_owned())];
+    /// # let path = "target/test_res/doc_multiple_out.glb";
+    /// # std::fs::create_dir_all("target/test_res").unwrap();
+    /// let mut output = File::create(path).expect("Could not create file!");
+    /// TMFMesh::write_gltf(&meshes,&mut output).expect("Could not export to glTF");
+    ///```
+    /// # Errors
+    /// Returns IO error if it occurs, or if the mesh has out of bounds indices.
+    #[cfg(feature = "gltf")]
+    pub fn write_gltf<W: Write, S: std::borrow::Borrow<str>>(
+        meshes: &[(TMFMesh, S)],
+        w: &mut W,
+    ) -> std::io::Result<()> {
+        gltf_io::write_gltf(meshes, w)
+    }
+    /// Writes this TMF mesh to a binary glTF 2.0(.glb) file. See [`Self::write_gltf`].
+    /// # Errors
+    /// Returns IO error if it occurs, or if the mesh has out of bounds indices.
+    #[cfg(feature = "gltf")]
+    pub fn write_gltf_one<W: Write>(&self, w: &mut W, name: &str) -> std::io::Result<()> {
+        gltf_io::write_gltf(&[(self.clone(), name)], w)
+    }
     /// Writes this TMF Mesh to *w*.
     /// # Example
     ///```
@@ -756,7 +1197,7 @@ impl TMFMesh {
     ) -> Result<(), TMFExportError> {
         runtime_agnostic_block_on!(tmf_exporter::write_tmf(&[(self.clone(), name)], w, p_info))
     }
-    /// Writes a number of TMF meshes into one file.
+    /// Writes a number of TMF meshes into one file. To write meshes one at a time, without keeping all of them in memory, use [`TMFWriter`].
     /// # Example
     /// ```
     /// # use std::fs::File;
@@ -777,6 +1218,41 @@ impl TMFMesh {
     ) -> Result<(), TMFExportError> {
         runtime_agnostic_block_on!(tmf_exporter::write_tmf(meshes_names, w, p_info))
     }
+    /// Async version of [`Self::write_tmf_one`], writing to a [`futures::io::AsyncWrite`].
+    /// # Errors
+    /// Returns IO error if occurs.
+    pub async fn write_tmf_one_async<
+        W: futures::io::AsyncWrite + Unpin,
+        S: std::borrow::Borrow<str>,
+    >(
+        &self,
+        w: &mut W,
+        p_info: &TMFPrecisionInfo,
+        name: S,
+    ) -> Result<(), TMFExportError> {
+        tmf_exporter::write_tmf_async(&[(self.clone(), name)], w, p_info).await
+    }
+    /// Async version of [`Self::write_tmf`], writing to a [`futures::io::AsyncWrite`]. Each mesh is written as soon as it is encoded.
+    /// Tokio writers can be used by wrapping them with `tokio_util::compat`.
+    /// # Example
+    /// ```
+    /// # use tmf::{TMFExportError,TMFMesh,TMFPrecisionInfo};
+    /// async fn save_meshes<W:futures::io::AsyncWrite + Unpin>(meshes:&[(TMFMesh,String)],target:&mut W)->Result<(),TMFExportError>{
+    ///     TMFMesh::write_tmf_async(meshes, target, &TMFPrecisionInfo::default()).await
+    /// }
+    /// ```
+    /// # Errors
+    /// Returns IO error if occurs.
+    pub async fn write_tmf_async<
+        W: futures::io::AsyncWrite + Unpin,
+        S: std::borrow::Borrow<str>,
+    >(
+        meshes_names: &[(Self, S)],
+        w: &mut W,
+        p_info: &TMFPrecisionInfo,
+    ) -> Result<(), TMFExportError> {
+        tmf_exporter::write_tmf_async(meshes_names, w, p_info).await
+    }
     /// Creates an empty TMF Mesh(mesh with no data). Equivalent to [`TMFMesh::default`].
     /// # Example
     /// ```
@@ -794,12 +1270,17 @@ impl TMFMesh {
             vertex_triangles: None,
             vertices: None,
             tangents: None,
-            //materials: None,
+            colors: None,
+            color_triangles: None,
+            materials: None,
+            groups: None,
+            smoothing_groups: None,
+            material_library: None,
             custom_data: Vec::new(),
             tangent_triangles: None,
         }
     }
-    /// Reads all meshes from a .tmf file.
+    /// Reads all meshes from a .tmf file. To read meshes one at a time, or skip some of them, use [`TMFReader`].
     /// # Example
     /// ```
     /// # use std::fs::File;
@@ -820,6 +1301,24 @@ impl TMFMesh {
     pub fn read_tmf<R: Read>(reader: &mut R) -> Result<Vec<(Self, String)>, TMFImportError> {
         crate::tmf_importer::import_sync(reader)
     }
+    /// Reads all meshes from a .tmf file, decoding their data directly into float type *F*(`f32` or `f64`) and index type *I*(`u16`, `u32` or `u64`), regardless of [`FloatType`] and [`IndexType`]. Custom data keeps using [`FloatType`] and [`IndexType`].
+    /// # Example
+    /// ```
+    /// # use std::fs::File;
+    /// # use tmf::TMFMesh;
+    /// # let tmf_path = "testing/susan.tmf";
+    /// let mut file = File::open(tmf_path).expect("Could not open .tmf file");
+    /// // Read meshes using double precision floats and 16 bit indices.
+    /// let meshes = TMFMesh::read_tmf_as::<f64, u16, _>(&mut file).expect("Could not load .tmf mesh!");
+    /// ```
+    /// # Errors
+    /// Returns the same errors as [`Self::read_tmf`], and `IndexWidthExceeded` if any mesh has indices not fitting in *I*.
+    #[allow(clippy::type_complexity)]
+    pub fn read_tmf_as<F: MeshFloat, I: MeshIndex, R: Read>(
+        reader: &mut R,
+    ) -> Result<Vec<(TMFMeshOf<F, I>, String)>, TMFImportError> {
+        crate::typed_mesh::read_tmf_as(reader)
+    }
     /// Async version of [`Self::read_tmf`].
     /// # Example
     /// ```
@@ -837,6 +1336,26 @@ impl TMFMesh {
     ) -> Result<Vec<(Self, String)>, TMFImportError> {
         crate::tmf_importer::TMFImportContext::import(reader).await
     }
+    /// Version of [`Self::read_tmf_async`] reading from a [`futures::io::AsyncRead`]. Segments that were already read are decoded while waiting for
+    /// the rest of the file(on the executor or the tokio runtime, if any). Tokio readers can be used by wrapping them with `tokio_util::compat`.
+    /// # Example
+    /// ```
+    /// # use tmf::{TMFImportError,TMFMesh};
+    /// async fn load_meshes_files<R:futures::io::AsyncRead + Unpin>(src:&mut [R])->Result<Vec<(TMFMesh,String)>,TMFImportError>{
+    ///     let mut meshes = Vec::new();
+    ///     for source in src{
+    ///         meshes.extend(TMFMesh::read_tmf_from_async_reader(source).await?);
+    ///     }
+    ///     Ok(meshes)
+    /// }
+    /// ```
+    /// # Errors
+    /// Returns the same errors as [`Self::read_tmf`].
+    pub async fn read_tmf_from_async_reader<R: futures::io::AsyncRead + Unpin>(
+        reader: &mut R,
+    ) -> Result<Vec<(Self, String)>, TMFImportError> {
+        crate::tmf_importer::TMFImportContext::import_async(reader).await
+    }
     /// Reads a single mesh from a .tmf file. Returns [`Err`] if no meshes present or more than one mesh present.
     /// ```
     /// # use tmf::TMFMesh;
@@ -850,17 +1369,7 @@ impl TMFMesh {
     /// # Errors
     /// Returns: an IO error if it occurs, `NotTMFFile` if not tmf file, `NewerVersionRequired` if a newer importer is required for importing the file, and other errors for malformed tmf files. This function also returns `NoMeshes` or `TooManyMeshes` if wrong mesh count present.
     pub fn read_tmf_one<R: Read>(reader: &mut R) -> Result<(Self, String), TMFImportError> {
-        let mut meshes = Self::read_tmf(reader)?.into_iter();
-        match meshes.next() {
-            Some(mesh) => {
-                if meshes.next().is_some() {
-                    Err(TMFImportError::TooManyMeshes)
-                } else {
-                    Ok(mesh)
-                }
-            }
-            None => Err(TMFImportError::NoMeshes),
-        }
+        Self::only_mesh(Self::read_tmf(reader)?)
     }
     /// Async version of [`Self::read_tmf_one`].
     /// # Example
@@ -877,7 +1386,30 @@ impl TMFMesh {
     pub async fn read_tmf_one_async<R: Read>(
         reader: &mut R,
     ) -> Result<(Self, String), TMFImportError> {
-        let mut meshes = Self::read_tmf_async(reader).await?.into_iter();
+        Self::only_mesh(Self::read_tmf_async(reader).await?)
+    }
+    /// Version of [`Self::read_tmf_one_async`] reading from a [`futures::io::AsyncRead`].
+    /// # Example
+    /// ```
+    /// # use tmf::{TMFImportError,TMFMesh};
+    /// async fn load_meshes_files<R:futures::io::AsyncRead + Unpin>(src:&mut [R])->Result<Vec<(TMFMesh,String)>,TMFImportError>{
+    ///     let mut meshes = Vec::new();
+    ///     for source in src{
+    ///         meshes.push(TMFMesh::read_tmf_one_from_async_reader(source).await?);
+    ///     }
+    ///     Ok(meshes)
+    /// }
+    /// ```
+    /// # Errors
+    /// Returns the same errors as [`Self::read_tmf_one`].
+    pub async fn read_tmf_one_from_async_reader<R: futures::io::AsyncRead + Unpin>(
+        reader: &mut R,
+    ) -> Result<(Self, String), TMFImportError> {
+        Self::only_mesh(Self::read_tmf_from_async_reader(reader).await?)
+    }
+    /// Returns the only mesh in *meshes*, or `NoMeshes`/`TooManyMeshes` if there is not exactly one.
+    fn only_mesh(meshes: Vec<(Self, String)>) -> Result<(Self, String), TMFImportError> {
+        let mut meshes = meshes.into_iter();
         match meshes.next() {
             Some(mesh) => {
                 if meshes.next().is_some() {
@@ -947,19 +1479,7 @@ impl TMFMesh {
     /// ```
     #[must_use]
     pub fn lookup_custom_data(&self, name: &str) -> Option<&CustomData> {
-        let bytes = name.as_bytes();
-        if bytes.len() > u8::MAX as usize {
-            return None;
-        }
-        let Ok(bytes_len) = u8::try_from(bytes.len()) else { return None };
-        for data in &self.custom_data {
-            if data.name_len() == bytes_len
-                && bytes == &data.name_bytes()[..(data.name_len() as usize)]
-            {
-                return Some(data.custom_data());
-            }
-        }
-        None
+        CustomDataSegment::lookup(&self.custom_data, name)
     }
     /// Appends vertices to this meshes vertex array.
     /// # Example
@@ -1105,6 +1625,134 @@ impl TMFMesh {
             }
         };
     }
+    /// Appends colors to this meshes vertex color array.
+    /// # Example
+    /// ```
+    /// # use tmf::TMFMesh;
+    /// # let mut tmf_mesh = TMFMesh::empty();
+    /// # tmf_mesh.set_colors(&[(0.2,0.5,0.1,1.0)][..]);
+    /// let colors_len = tmf_mesh.get_colors().unwrap().len();
+    /// tmf_mesh.append_colors(&[(0.2,0.5,0.1,1.0),(0.12,0.78,0.5,0.5)]);
+    /// assert!(colors_len < tmf_mesh.get_colors().unwrap().len());
+    /// ```
+    pub fn append_colors(&mut self, colors: &[Color]) {
+        match &mut self.colors {
+            Some(ref mut self_c) => self_c.extend(colors),
+            None => {
+                self.set_colors(colors);
+            }
+        };
+    }
+    /// Appends indices to this meshes color triangle array.
+    /// # Example
+    /// ```
+    /// # use tmf::TMFMesh;
+    /// # let mut tmf_mesh = TMFMesh::empty();
+    /// # tmf_mesh.set_color_triangles(&[0,4,3,8,7,9][..]);
+    /// let triangle_len = tmf_mesh.get_color_triangles().unwrap().len();
+    /// tmf_mesh.append_color_triangles(&[0,4,3,8,7,9]);
+    /// assert!(triangle_len < tmf_mesh.get_color_triangles().unwrap().len());
+    /// ```
+    pub fn append_color_triangles(&mut self, triangles: &[IndexType]) {
+        match &mut self.color_triangles {
+            Some(ref mut self_ct) => self_ct.extend(triangles),
+            None => {
+                self.set_color_triangles(triangles);
+            }
+        };
+    }
+    /// Triangulates polygon *polygon*, given as a loop of indices into the vertex array, with optional *holes*, and appends resulting triangles to the vertex triangle array. Concave and non-planar polygons, as well as collinear points, are supported.
+    /// Only the vertex triangle array is extended, so to triangulate polygons with normals or uvs use [`triangulate_polygon`] and map its output to each of the triangle arrays.
+    /// # Example
+    /// ```
+    /// # use tmf::TMFMesh;
+    /// let mut mesh = TMFMesh::empty();
+    /// mesh.set_vertices([(0.0, 0.0, 0.0), (2.0, 0.0, 0.0), (2.0, 1.0, 0.0), (1.0, 1.0, 0.0), (1.0, 2.0, 0.0), (0.0, 2.0, 0.0)]);
+    /// mesh.add_polygon(&[0, 1, 2, 3, 4, 5], &[]).expect("Polygon uses vertices not in the mesh!");
+    /// assert_eq!(mesh.get_vertex_triangles().unwrap().len(), 4 * 3);
+    /// ```
+    /// # Errors
+    /// Returns [`TMFIntegrityStatus`] if the vertex array is missing, or the polygon uses vertices outside of it. The mesh is not changed in such a case.
+    #[cfg(feature = "triangulation")]
+    pub fn add_polygon(
+        &mut self,
+        polygon: &[IndexType],
+        holes: &[&[IndexType]],
+    ) -> Result<(), TMFIntegrityStatus> {
+        let vertices = self
+            .get_vertices()
+            .ok_or(TMFIntegrityStatus::VertexArrayMissing)?;
+        let indices: Vec<IndexType> = polygon
+            .iter()
+            .chain(holes.iter().flat_map(|hole| hole.iter()))
+            .copied()
+            .collect();
+        let positions = indices
+            .iter()
+            .map(|index| {
+                vertices.get(*index as usize).copied().ok_or(
+                    TMFIntegrityStatus::IndexOutsideVertexArray(
+                        *index,
+                        vertices.len() as IndexType,
+                    ),
+                )
+            })
+            .collect::<Result<Vec<Vector3>, _>>()?;
+        let (polygon, mut rest) = positions.split_at(polygon.len());
+        let holes: Vec<&[Vector3]> = holes
+            .iter()
+            .map(|hole| {
+                let (hole, remaining) = rest.split_at(hole.len());
+                rest = remaining;
+                hole
+            })
+            .collect();
+        let triangles = triangulate_polygon(polygon, &holes);
+        self.vertex_triangles
+            .get_or_insert_with(Vec::new)
+            .extend(triangles.into_iter().map(|corner| indices[corner]));
+        Ok(())
+    }
+    /// Appends materials and material triangle ranges to this meshes material info. Materials with the same name are merged, and triangle ranges remapped to use them.
+    /// # Example
+    /// ```
+    /// # use tmf::{TMFMesh,MaterialInfo};
+    /// # let mut tmf_mesh = TMFMesh::empty();
+    /// # tmf_mesh.set_materials(MaterialInfo::create(["wood".to_owned()],[(0,0..2)]));
+    /// let other = MaterialInfo::create(["metal".to_owned(),"wood".to_owned()],[(0,2..4),(1,4..6)]);
+    /// tmf_mesh.append_materials(&other);
+    /// let materials = tmf_mesh.get_materials().unwrap();
+    /// assert_eq!(materials.materials().len(), 2);
+    /// assert_eq!(materials.lookup_material(5), Some("wood"));
+    /// ```
+    pub fn append_materials(&mut self, materials: &MaterialInfo) {
+        match &mut self.materials {
+            Some(ref mut self_mat) => self_mat.append(materials),
+            None => {
+                self.set_materials(materials.clone());
+            }
+        };
+    }
+    /// Appends groups and group triangle ranges to this meshes group info. Groups with the same name are merged, and triangle ranges remapped to use them.
+    /// # Example
+    /// ```
+    /// # use tmf::{TMFMesh,GroupInfo};
+    /// # let mut tmf_mesh = TMFMesh::empty();
+    /// # tmf_mesh.set_groups(GroupInfo::create(["head".to_owned()],[(0,0..2)]));
+    /// let other = GroupInfo::create(["ears".to_owned(),"head".to_owned()],[(0,2..4),(1,2..6)]);
+    /// tmf_mesh.append_groups(&other);
+    /// let groups = tmf_mesh.get_groups().unwrap();
+    /// assert_eq!(groups.groups().len(), 2);
+    /// assert_eq!(groups.lookup_group("head").collect::<Vec<_>>(), [0..6]);
+    /// ```
+    pub fn append_groups(&mut self, groups: &GroupInfo) {
+        match &mut self.groups {
+            Some(ref mut self_groups) => self_groups.append(groups),
+            None => {
+                self.set_groups(groups.clone());
+            }
+        };
+    }
 }
 /// An enum describing an error that occurred during loading a TMF mesh.  
 #[derive(Debug, Error)]
@@ -1136,6 +1784,9 @@ pub enum TMFImportError {
     #[error("This compression type {0} is not supported in this particular context.")]
     /// This compression type is not supported in this particular context
     UnsuportedCompressionType(u8),
+    #[error("Mesh has indices which do not fit in the chosen {0} bit index type.")]
+    /// Mesh has indices which do not fit in the chosen index type, of this many bits.
+    IndexWidthExceeded(u32),
 }
 /// An error which occured when a `TMFMesh` is exported.
 #[derive(Debug, Error)]
@@ -1208,15 +1859,25 @@ mod testing {
         panic!();
     }
     #[test]
-    fn analize_suzan() {
-        let mut file = std::fs::File::open("target/test_res/susan.tmf").unwrap();
-        let mut out = Vec::new();
-        file.read_to_end(&mut out).unwrap();
-        let r_mesh = runtime_agnostic_block_on!(tmf_importer::TMFImportContext::analize(
-            &mut (&out as &[u8]),
-        ))
-        .unwrap();
-        //todo!();
+    #[cfg(feature = "obj_import")]
+    fn inspect_suzan() {
+        init_test_env();
+        let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+        let (tmf_mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+        let path = "target/test_res/inspect_susan.tmf";
+        let mut out = std::fs::File::create(path).unwrap();
+        tmf_mesh
+            .write_tmf_one(&mut out, &TMFPrecisionInfo::default(), name.as_str())
+            .unwrap();
+        drop(out);
+        let report = inspect_file(path).unwrap();
+        assert_eq!(report.meshes.len(), 1);
+        assert_eq!(report.meshes[0].name, name);
+        assert!(report.meshes[0]
+            .segments
+            .iter()
+            .all(|seg| seg.seg_type != SectionType::Invalid && seg.element_count.is_some()));
+        assert_eq!(report.total_byte_len(), report.meshes[0].total_byte_len());
     }
     #[test]
     #[cfg(feature = "obj_import")]
@@ -1236,7 +1897,6 @@ mod testing {
         let (r_mesh, name) = TMFMesh::read_tmf_one(&mut (&out as &[u8])).unwrap();
         assert!(name == "Suzanne", "Name should be Suzanne but is {name}");
         r_mesh.verify().unwrap();
-        let mut index = 0;
         assert_eq!(
             tmf_mesh.get_vertex_triangles(),
             r_mesh.get_vertex_triangles()
@@ -1249,6 +1909,7 @@ mod testing {
         let mut out = std::fs::File::create("target/test_res/susan_ftmf.obj").unwrap();
         r_mesh.write_obj_one(&mut out, &name).unwrap();
     }
+    #[test]
     #[cfg(all(feature = "triangulation", feature = "obj_import"))]
     fn rw_cube_obj_not_triangulated() {
         init_test_env();
@@ -1256,6 +1917,17 @@ mod testing {
         let meshes = TMFMesh::read_from_obj(&mut file).unwrap();
         for (mesh, name) in &meshes {
             mesh.verify().unwrap();
+            // Each n-gon is split into n - 2 triangles.
+            let triangle_count = match name.as_str() {
+                "Cube" => 6 * 2,
+                "Circle" => 32 - 2,
+                "Concave_Ngon" => 26 - 2,
+                _ => panic!("Unexpected mesh {name}"),
+            };
+            assert_eq!(
+                mesh.get_vertex_triangles().unwrap().len(),
+                triangle_count * 3
+            );
         }
         let mut out = std::fs::File::create("target/test_res/cube_ftmf.obj").unwrap();
         TMFMesh::write_obj(&meshes, &mut out).unwrap();
@@ -1269,7 +1941,7 @@ mod testing {
         for mesh_name in meshes {
             let (mesh, name) = mesh_name;
             mesh.verify().unwrap();
-            let mut out = std::fs::File::create(&format!("target/test_res/{}.obj", name)).unwrap();
+            let mut out = std::fs::File::create(format!("target/test_res/{}.obj", name)).unwrap();
             mesh.write_obj_one(&mut out, &name).unwrap();
         }
     }
@@ -1285,6 +1957,82 @@ mod testing {
         let mut out = std::fs::File::create("target/test_res/multiple.obj").unwrap();
         TMFMesh::write_obj(&meshes, &mut out).unwrap();
     }
+    #[test]
+    #[cfg(feature = "obj_import")]
+    fn rw_obj_materials_groups_smoothing() {
+        let obj = "mtllib a.mtl b.mtl
+o First
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vn 0 0 1
+g walls outer
+usemtl brick
+s 1
+f 1/1/1 2/1/1 3/1/1
+f 1/1/1 3/1/1 4/1/1
+g walls
+s off
+f 1/1/1 2/1/1 3/1/1
+usemtl glass
+s 2
+f 1/1/1 3/1/1 4/1/1
+o Second
+v 0 0 1
+v 1 0 1
+v 1 1 1
+f 5/1/1 6/1/1 7/1/1
+g
+usemtl
+s off
+f 5/1/1 7/1/1 6/1/1
+";
+        let meshes = TMFMesh::read_from_obj(&mut obj.as_bytes()).unwrap();
+        let (first, second) = (&meshes[0].0, &meshes[1].0);
+        first.verify().unwrap();
+        second.verify().unwrap();
+        let materials = first.get_materials().unwrap();
+        assert_eq!(materials.libraries(), ["a.mtl", "b.mtl"]);
+        assert_eq!(
+            materials.material_groups(),
+            [(0, 0..3), (1, 3..4)] as [MaterialTriangleRange; 2]
+        );
+        let groups = first.get_groups().unwrap();
+        assert_eq!(groups.groups(), ["walls", "outer"]);
+        assert_eq!(
+            first.get_smoothing_groups().unwrap(),
+            [(1, 0..2), (2, 3..4)] as [SmoothingGroupRange; 2]
+        );
+        // Material, groups and smoothing group carry over to the next mesh.
+        assert_eq!(
+            second.get_smoothing_groups().unwrap(),
+            [(2, 0..1)] as [SmoothingGroupRange; 1]
+        );
+        assert_eq!(second.get_groups().unwrap().group_ranges().len(), 1);
+        let mut out = Vec::new();
+        TMFMesh::write_obj(&meshes, &mut out).unwrap();
+        let r_meshes = TMFMesh::read_from_obj(&mut &out[..]).unwrap();
+        assert_eq!(r_meshes.len(), meshes.len());
+        for ((mesh, name), (r_mesh, r_name)) in meshes.iter().zip(r_meshes.iter()) {
+            assert_eq!(name, r_name);
+            assert_eq!(mesh.get_materials(), r_mesh.get_materials());
+            assert_eq!(mesh.get_groups(), r_mesh.get_groups());
+            assert_eq!(mesh.get_smoothing_groups(), r_mesh.get_smoothing_groups());
+            assert_eq!(mesh.get_vertex_triangles(), r_mesh.get_vertex_triangles());
+        }
+        // The same data survives a round trip through TMF.
+        let mut out = Vec::new();
+        TMFMesh::write_tmf(&meshes, &mut out, &TMFPrecisionInfo::default()).unwrap();
+        let r_meshes = TMFMesh::read_tmf(&mut &out[..]).unwrap();
+        for ((mesh, _), (r_mesh, _)) in meshes.iter().zip(r_meshes.iter()) {
+            r_mesh.verify().unwrap();
+            assert_eq!(mesh.get_materials(), r_mesh.get_materials());
+            assert_eq!(mesh.get_groups(), r_mesh.get_groups());
+            assert_eq!(mesh.get_smoothing_groups(), r_mesh.get_smoothing_groups());
+        }
+    }
     /*
     #[ignore]
     #[test]
@@ -1,99 +1,155 @@
-#![allow(dead_code)]
+use crate::read_extension::ReadExt;
+use crate::typed_mesh::{index_from_u64, MeshIndex};
 use crate::unaligned_rw::{UnalignedRWMode, UnalignedReader, UnalignedWriter};
-use crate::IndexType;
-fn calc_sw_size(prec: u8) -> usize {
-    1_usize << prec
+use crate::vertices::calc_prec;
+use crate::{IndexType, TMFImportError, MAX_SEG_SIZE};
+use std::collections::HashMap;
+/// Amount of bits used to store the distance to the beginning of a match.
+const OFFSET_BITS: u8 = 12;
+/// Amount of bits used to store the length of a match.
+const LENGTH_BITS: u8 = 6;
+/// Shortest match worth encoding as a back-reference.
+const MIN_MATCH: usize = 2;
+/// How many previous positions with the same prefix are checked when looking for a match. Bounds encode time for very repetitive data.
+const MAX_CHAIN_DEPTH: usize = 64;
+fn window_size(offset_bits: u8) -> usize {
+    1_usize << offset_bits
 }
-fn find_longest_match(data: &[u64], pos: usize, scrollback_limit: usize) -> (usize, usize) {
-    let mut best_offset = 0;
-    let mut best_len = 0;
-    let start = if pos < scrollback_limit {
-        0
-    } else {
-        pos - scrollback_limit
-    };
-    for offset in start..pos {
-        let len = match_len(data, offset, pos);
-        if len > best_len {
-            best_offset = pos - offset;
-            best_len = len;
-        }
-    }
-    (best_offset, best_len)
+fn max_match_len(length_bits: u8) -> usize {
+    (1_usize << length_bits) - 1 + MIN_MATCH
 }
-fn match_len(data: &[u64], offset: usize, end: usize) -> usize {
-    let mut offset = offset;
-    let mut pos = end;
+fn match_len(data: &[u64], start: usize, pos: usize, max_len: usize) -> usize {
     let mut len = 0;
-    while offset < pos && pos < data.len() && data[offset] == data[pos] {
-        offset += 1;
-        pos += 1;
+    while pos + len < data.len() && len < max_len && data[start + len] == data[pos + len] {
         len += 1;
     }
     len
 }
-use crate::vertices::calc_prec;
+/// Finds matches using hash chains, so that the search cost depends on the amount of similar data in the window, and not on the window size.
+struct MatchFinder {
+    heads: HashMap<(u64, u64), usize>,
+    prev: Vec<usize>,
+    window: usize,
+    max_len: usize,
+}
+impl MatchFinder {
+    const NO_POS: usize = usize::MAX;
+    fn new(len: usize, offset_bits: u8, length_bits: u8) -> Self {
+        Self {
+            heads: HashMap::with_capacity(len),
+            prev: vec![Self::NO_POS; len],
+            window: window_size(offset_bits),
+            max_len: max_match_len(length_bits),
+        }
+    }
+    fn insert(&mut self, data: &[u64], pos: usize) {
+        if pos + 1 >= data.len() {
+            return;
+        }
+        let key = (data[pos], data[pos + 1]);
+        self.prev[pos] = self.heads.insert(key, pos).unwrap_or(Self::NO_POS);
+    }
+    /// Returns (offset, length) of the longest match for data at *pos*.
+    fn find(&self, data: &[u64], pos: usize) -> (usize, usize) {
+        if pos + 1 >= data.len() {
+            return (0, 0);
+        }
+        let key = (data[pos], data[pos + 1]);
+        let mut candidate = *self.heads.get(&key).unwrap_or(&Self::NO_POS);
+        let (mut best_offset, mut best_len) = (0, 0);
+        let mut depth = 0;
+        while candidate != Self::NO_POS && depth < MAX_CHAIN_DEPTH {
+            let offset = pos - candidate;
+            if offset > self.window {
+                break;
+            }
+            let len = match_len(data, candidate, pos, self.max_len);
+            if len > best_len {
+                best_offset = offset;
+                best_len = len;
+                if len == self.max_len {
+                    break;
+                }
+            }
+            candidate = self.prev[candidate];
+            depth += 1;
+        }
+        (best_offset, best_len)
+    }
+}
+/// Saves *data* as an LZ77 compressed UBA. Each token starts with a bit, which is set for raw values(stored with *prec* bits)
+/// and cleared for back-references, stored as (length - [`MIN_MATCH`], offset - 1) using *length_bits* and *offset_bits* bits.
 fn save_compressed_array<T, Mapper: Fn(&T) -> u64, W: std::io::Write>(
     data: &[T],
     mapper: Mapper,
     prec: UnalignedRWMode,
+    offset_bits: u8,
+    length_bits: u8,
     target: &mut W,
 ) -> std::io::Result<()> {
-    println!("data.len():{}", data.len());
     let data: Box<_> = data.iter().map(mapper).collect();
+    let mut finder = MatchFinder::new(data.len(), offset_bits, length_bits);
+    let offset_prec = UnalignedRWMode::precision_bits(offset_bits);
+    let length_prec = UnalignedRWMode::precision_bits(length_bits);
     let mut writer = UnalignedWriter::new(target);
+    let raw_cost = 1 + prec.bits() as usize;
+    let ref_cost = 1 + offset_bits as usize + length_bits as usize;
     let mut pos = 0;
-    let scrollback_limit = 127;
-    println!("data.len():{}", data.len());
     while pos < data.len() {
-        let (offset, len) = find_longest_match(&data, pos, scrollback_limit);
-        //TODO:Write
-        let write_raw = offset == 0 || len < 2;
+        let (offset, len) = finder.find(&data, pos);
+        // Only use a back-reference if it is actually smaller than writing the values directly.
+        let write_raw = len < MIN_MATCH || len * raw_cost <= ref_cost;
         writer.write_bit(write_raw)?;
-        if write_raw {
-            println!("({})", data[pos]);
+        let step = if write_raw {
             writer.write_unaligned(prec, data[pos])?;
-            pos += 1;
+            1
         } else {
-            writer.write_unaligned(prec, len as u64)?;
-            writer.write_unaligned(prec, offset as u64)?;
-            pos += len;
-            println!("({offset}, {len})");
+            writer.write_unaligned(length_prec, (len - MIN_MATCH) as u64)?;
+            writer.write_unaligned(offset_prec, (offset - 1) as u64)?;
+            len
+        };
+        for curr in pos..(pos + step) {
+            finder.insert(&data, curr);
         }
+        pos += step;
     }
     writer.flush()
 }
 fn read_compressed_array<T, Mapper: Fn(u64) -> T, R: std::io::Read>(
     mapper: Mapper,
     prec: UnalignedRWMode,
+    offset_bits: u8,
+    length_bits: u8,
     length: usize,
     src: &mut R,
-) -> std::io::Result<Box<[T]>> {
-    let mut output = Vec::with_capacity(length);
+) -> Result<Box<[T]>, TMFImportError> {
+    let offset_prec = UnalignedRWMode::precision_bits(offset_bits);
+    let length_prec = UnalignedRWMode::precision_bits(length_bits);
+    let mut output: Vec<u64> = Vec::with_capacity(length);
     let mut reader = UnalignedReader::new(src);
     while output.len() < length {
-        //println!("POS: {pos}");
         let is_raw = reader.read_bit()?;
-        let data_or_len = reader.read_unaligned(prec)?;
         if is_raw {
-            output.push(data_or_len);
-            print!("{data_or_len},");
+            output.push(reader.read_unaligned(prec)?);
         } else {
-            // Impossible
-            #[allow(clippy::cast_possible_truncation)]
-            let offset = reader.read_unaligned(prec)? as usize;
-            let Ok(len) = usize::try_from(data_or_len) else { return Err(std::io::Error::new(std::io::ErrorKind::Other, "Data segment length bigger than size of this devices address space.")) };
+            let len = reader.read_unaligned(length_prec)? as usize + MIN_MATCH;
+            let offset = reader.read_unaligned(offset_prec)? as usize + 1;
+            if offset > output.len() || output.len() + len > length {
+                return Err(TMFImportError::IO(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "LZZ back-reference outside of decoded data.",
+                )));
+            }
             let start = output.len() - offset;
-            for i in start..(start + len) {
-                let current = output[i];
-                print!("{current},");
-                output.push(current);
+            // Ranges may overlap with the data being written, so values need to be copied one by one.
+            for index in start..(start + len) {
+                output.push(output[index]);
             }
         }
     }
-    let output: Box<_> = output.into_iter().map(mapper).collect();
-    Ok(output)
+    Ok(output.into_iter().map(mapper).collect())
 }
+/// Saves *triangles* using the Unaligned LZZ compression. Header is the same as for uncompressed triangles, followed by offset and length precision.
 pub(crate) fn save_triangles_lzz<W: std::io::Write>(
     triangles: &[IndexType],
     max_index: usize,
@@ -104,11 +160,59 @@ pub(crate) fn save_triangles_lzz<W: std::io::Write>(
     writer.write_all(&precision.to_le_bytes())?;
     writer.write_all(&(triangles.len() as u64).to_le_bytes())?;
     writer.write_all(&u64::from(min).to_le_bytes())?;
+    writer.write_all(&[OFFSET_BITS, LENGTH_BITS])?;
     let precision = UnalignedRWMode::precision_bits(precision);
-    save_compressed_array(triangles, |index| u64::from(index - min), precision, writer)?;
-    //writer.flush()
+    save_compressed_array(
+        triangles,
+        |index| u64::from(index - min),
+        precision,
+        OFFSET_BITS,
+        LENGTH_BITS,
+        writer,
+    )
+}
+pub(crate) fn read_triangles_lzz<I: MeshIndex, R: std::io::Read>(
+    mut src: R,
+    data: &mut Vec<I>,
+    ctx: &crate::tmf_importer::TMFImportContext,
+) -> Result<(), TMFImportError> {
+    let precision = src.read_u8()?;
+    let length = src.read_u64()?;
+    let min = ctx.read_traingle_min(&mut src)?;
+    if length > MAX_SEG_SIZE as u64 {
+        return Err(TMFImportError::SegmentTooLong);
+    }
+    let offset_bits = src.read_u8()?;
+    let length_bits = src.read_u8()?;
+    for bits in [precision, offset_bits, length_bits] {
+        if bits >= u64::BITS as u8 {
+            return Err(TMFImportError::InvalidPrecision(bits));
+        }
+    }
+    let indices = read_compressed_array(
+        |index| index,
+        UnalignedRWMode::precision_bits(precision),
+        offset_bits,
+        length_bits,
+        length as usize,
+        &mut src,
+    )?;
+    data.reserve(indices.len());
+    for index in indices.iter() {
+        let index = index.checked_add(min).ok_or_else(|| {
+            TMFImportError::IO(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "LZZ decoded index out of range.",
+            ))
+        })?;
+        data.push(index_from_u64(index)?);
+    }
     Ok(())
 }
+#[cfg(test)]
+fn test_ctx() -> crate::tmf_importer::TMFImportContext {
+    crate::tmf_importer::TMFImportContext::default()
+}
 #[test]
 fn encode_ascii_lzz() {
     let data = b"LZ77 algorithms achieve compression by replacing repeated occurrences of data with references to a single copy of that data existing earlier in the uncompressed data stream. A match is encoded by a pair of numbers called a length-distance pair, which is equivalent to the statement \"each of the next length characters is equal to the characters exactly distance characters behind it in the uncompressed stream\". (The distance is sometimes called the offset instead.) To spot matches, the encoder must keep track of some amount of the most recent data, such as the last 2 KB, 4 KB, or 32 KB. The structure in which this data is held is called a sliding window, which is why LZ77 is sometimes called sliding-window compression. The encoder needs to keep this data to look for matches, and the decoder needs to keep this data to interpret the matches the encoder refers to. The larger the sliding window is, the longer back the encoder may search for creating references";
@@ -117,17 +221,75 @@ fn encode_ascii_lzz() {
         &data[..],
         |input: &u8| *input as u64,
         UnalignedRWMode::precision_bits(7),
+        OFFSET_BITS,
+        LENGTH_BITS,
         &mut out,
     )
     .unwrap();
-    let _data = read_compressed_array(
+    assert!(out.len() < data.len());
+    let decoded = read_compressed_array(
         |input: u64| input as u8,
         UnalignedRWMode::precision_bits(7),
+        OFFSET_BITS,
+        LENGTH_BITS,
         data.len(),
         &mut &out[..],
     )
     .unwrap();
-    //panic!("Compressed is:{}%",(out.len() as f64 / data.len() as f64)*100.0);
+    assert_eq!(&decoded[..], &data[..]);
 }
 #[test]
-fn compress_triangles() {}
+fn compress_triangles() {
+    // A grid-like, highly repetitive index pattern
+    let triangles: Vec<IndexType> = (0..10_000).map(|i| i % 6 + (i / 600) * 4).collect();
+    let max_index = *triangles.iter().max().unwrap() as usize;
+    let mut compressed = Vec::new();
+    save_triangles_lzz(&triangles, max_index, &mut compressed).unwrap();
+    let mut uncompressed = Vec::new();
+    crate::vertices::save_triangles(&triangles, max_index, &mut uncompressed).unwrap();
+    assert!(compressed.len() < uncompressed.len());
+    let mut decoded: Vec<IndexType> = Vec::new();
+    read_triangles_lzz(&compressed[..], &mut decoded, &test_ctx()).unwrap();
+    assert_eq!(triangles, decoded);
+}
+#[test]
+fn reject_overflowing_index() {
+    let mut data = vec![8];
+    data.extend_from_slice(&1_u64.to_le_bytes());
+    data.extend_from_slice(&u64::MAX.to_le_bytes());
+    data.extend_from_slice(&[OFFSET_BITS, LENGTH_BITS]);
+    {
+        let mut writer = UnalignedWriter::new(&mut data);
+        // A raw index of 1, above the maximal `min`.
+        writer.write_bit(true).unwrap();
+        writer
+            .write_unaligned(UnalignedRWMode::precision_bits(8), 1)
+            .unwrap();
+    }
+    let res = read_triangles_lzz(&data[..], &mut Vec::<u64>::new(), &test_ctx());
+    assert!(res.is_err());
+}
+#[test]
+fn reject_invalid_backreference() {
+    let mut data = Vec::new();
+    {
+        let mut writer = UnalignedWriter::new(&mut data);
+        // A back-reference as the very first token can't be valid.
+        writer.write_bit(false).unwrap();
+        writer
+            .write_unaligned(UnalignedRWMode::precision_bits(LENGTH_BITS), 0)
+            .unwrap();
+        writer
+            .write_unaligned(UnalignedRWMode::precision_bits(OFFSET_BITS), 3)
+            .unwrap();
+    }
+    let res = read_compressed_array(
+        |input: u64| input,
+        UnalignedRWMode::precision_bits(8),
+        OFFSET_BITS,
+        LENGTH_BITS,
+        4,
+        &mut &data[..],
+    );
+    assert!(res.is_err());
+}
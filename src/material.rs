@@ -1,20 +1,254 @@
-// Module not completed yet
-#![allow(dead_code)]
-use crate::IndexType;
-#[derive(Clone)]
+use crate::read_extension::ReadExt;
+use crate::tmf_importer::read_string;
+use crate::unaligned_rw::{UnalignedRWMode, UnalignedReader, UnalignedWriter};
+use crate::vertices::calc_prec;
+use crate::{IndexType, TMFImportError, MAX_SEG_SIZE};
+use std::ops::Range;
+/// A range of triangles using the material at index *0*. Triangle indices are indices of whole triangles(not of the triangle index array).
+pub type MaterialTriangleRange = (IndexType, Range<IndexType>);
+/// Materials used by a mesh: the names of all materials, the ranges of triangles using each of them, and the names of material libraries(e.g. `.mtl` files) defining them.
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct MaterialInfo {
     // Names of all materials in model
-    materials: Box<[String]>,
-    material_groups: Box<[(IndexType, IndexType)]>,
+    materials: Vec<String>,
+    material_groups: Vec<MaterialTriangleRange>,
+    // Names of material library files referenced by the model
+    libraries: Vec<String>,
 }
 impl MaterialInfo {
-    fn create<M: Into<Box<[String]>>, MB: Into<Box<[(IndexType, IndexType)]>>>(
+    /// Creates a new [`MaterialInfo`] with no materials.
+    /// # Example
+    /// ```
+    /// # use tmf::MaterialInfo;
+    /// let materials = MaterialInfo::new();
+    /// assert!(materials.materials().is_empty());
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Creates a [`MaterialInfo`] from material names and triangle ranges. Data is **not** checked, so call [`crate::TMFMesh::verify`] before saving.
+    /// # Example
+    /// ```
+    /// # use tmf::MaterialInfo;
+    /// let materials = MaterialInfo::create(["wood".to_owned(), "metal".to_owned()], [(0, 0..12), (1, 12..16)]);
+    /// assert_eq!(materials.materials().len(), 2);
+    /// ```
+    pub fn create<M: Into<Vec<String>>, MB: Into<Vec<MaterialTriangleRange>>>(
         materials: M,
         material_groups: MB,
     ) -> Self {
         Self {
             materials: materials.into(),
             material_groups: material_groups.into(),
+            libraries: Vec::new(),
+        }
+    }
+    /// Adds material *name* and returns its index. If a material with the same name is already present, the index of it is returned instead.
+    /// # Example
+    /// ```
+    /// # use tmf::MaterialInfo;
+    /// let mut materials = MaterialInfo::new();
+    /// let wood = materials.add_material("wood");
+    /// let metal = materials.add_material("metal");
+    /// assert_ne!(wood, metal);
+    /// assert_eq!(wood, materials.add_material("wood"));
+    /// ```
+    pub fn add_material(&mut self, name: &str) -> IndexType {
+        match self.materials.iter().position(|material| material == name) {
+            Some(index) => index as IndexType,
+            None => {
+                self.materials.push(name.to_owned());
+                (self.materials.len() - 1) as IndexType
+            }
+        }
+    }
+    /// Assigns *triangles* to material with index *material*. If the range directly follows the last range of the same material, they are merged.
+    /// # Example
+    /// ```
+    /// # use tmf::MaterialInfo;
+    /// let mut materials = MaterialInfo::new();
+    /// let wood = materials.add_material("wood");
+    /// materials.add_triangle_range(wood, 0..10);
+    /// materials.add_triangle_range(wood, 10..14);
+    /// assert_eq!(materials.material_groups(), &[(wood, 0..14)]);
+    /// ```
+    pub fn add_triangle_range(&mut self, material: IndexType, triangles: Range<IndexType>) {
+        if let Some((last_material, last_range)) = self.material_groups.last_mut() {
+            if *last_material == material && last_range.end == triangles.start {
+                last_range.end = triangles.end;
+                return;
+            }
+        }
+        self.material_groups.push((material, triangles));
+    }
+    /// Adds a reference to material library *name*(e.g. a `.mtl` file defining the materials). Libraries already referenced are not added again.
+    /// # Example
+    /// ```
+    /// # use tmf::MaterialInfo;
+    /// let mut materials = MaterialInfo::new();
+    /// materials.add_library("house.mtl");
+    /// materials.add_library("house.mtl");
+    /// assert_eq!(materials.libraries(), ["house.mtl"]);
+    /// ```
+    pub fn add_library(&mut self, name: &str) {
+        if !self.libraries.iter().any(|library| library == name) {
+            self.libraries.push(name.to_owned());
+        }
+    }
+    /// Returns the names of all referenced material libraries.
+    #[must_use]
+    pub fn libraries(&self) -> &[String] {
+        &self.libraries
+    }
+    /// Returns the names of all materials.
+    #[must_use]
+    pub fn materials(&self) -> &[String] {
+        &self.materials
+    }
+    /// Returns all ranges of triangles and indices of materials they use.
+    #[must_use]
+    pub fn material_groups(&self) -> &[MaterialTriangleRange] {
+        &self.material_groups
+    }
+    /// Returns the name of the material used by triangle *triangle*, if it has any.
+    /// # Example
+    /// ```
+    /// # use tmf::MaterialInfo;
+    /// let materials = MaterialInfo::create(["wood".to_owned(), "metal".to_owned()], [(0, 0..12), (1, 12..16)]);
+    /// assert_eq!(materials.lookup_material(13), Some("metal"));
+    /// assert_eq!(materials.lookup_material(16), None);
+    /// ```
+    #[must_use]
+    pub fn lookup_material(&self, triangle: IndexType) -> Option<&str> {
+        self.material_groups
+            .iter()
+            .find(|(_, range)| range.contains(&triangle))
+            .and_then(|(material, _)| self.materials.get(*material as usize))
+            .map(String::as_str)
+    }
+    /// Appends materials in *other* to self, remapping material indices of triangle ranges in *other* to match materials in self.
+    pub(crate) fn append(&mut self, other: &Self) {
+        let map: Vec<IndexType> = other
+            .materials
+            .iter()
+            .map(|name| self.add_material(name))
+            .collect();
+        for (material, range) in &other.material_groups {
+            let material = map.get(*material as usize).copied().unwrap_or(*material);
+            self.add_triangle_range(material, range.clone());
+        }
+        for library in &other.libraries {
+            self.add_library(library);
         }
     }
+    pub(crate) fn extend_materials(&mut self, materials: &[String]) {
+        self.materials.extend_from_slice(materials);
+    }
+    pub(crate) fn extend_groups(&mut self, groups: &[MaterialTriangleRange]) {
+        self.material_groups.extend_from_slice(groups);
+    }
+    pub(crate) fn extend_libraries(&mut self, libraries: &[String]) {
+        self.libraries.extend_from_slice(libraries);
+    }
+}
+/// Saves a list of names, used by both material and group segments.
+pub(crate) fn save_names<W: std::io::Write>(
+    names: &[String],
+    target: &mut W,
+) -> std::io::Result<()> {
+    target.write_all(&(names.len() as u32).to_le_bytes())?;
+    for name in names {
+        crate::tmf_exporter::write_mesh_name(target, name)?;
+    }
+    Ok(())
+}
+pub(crate) fn read_names<R: std::io::Read>(src: &mut R) -> Result<Box<[String]>, TMFImportError> {
+    let count = src.read_u32()? as usize;
+    if count > MAX_SEG_SIZE {
+        return Err(TMFImportError::SegmentTooLong);
+    }
+    let mut names = Vec::with_capacity(count.min(u16::MAX as usize));
+    for _ in 0..count {
+        names.push(read_string(src)?);
+    }
+    Ok(names.into())
+}
+/// Saves ranges of triangles assigned to an index(of a material or group).
+pub(crate) fn save_triangle_ranges<W: std::io::Write>(
+    groups: &[MaterialTriangleRange],
+    target: &mut W,
+) -> std::io::Result<()> {
+    let max_material = groups.iter().map(|(material, _)| *material).max();
+    let max_triangle = groups.iter().map(|(_, range)| range.end).max();
+    let material_prec = calc_prec(max_material.unwrap_or(0) as usize);
+    let triangle_prec = calc_prec(max_triangle.unwrap_or(0) as usize);
+    target.write_all(&(groups.len() as u64).to_le_bytes())?;
+    target.write_all(&[material_prec, triangle_prec])?;
+    let material_prec = UnalignedRWMode::precision_bits(material_prec);
+    let triangle_prec = UnalignedRWMode::precision_bits(triangle_prec);
+    let mut writer = UnalignedWriter::new(target);
+    for (material, range) in groups {
+        writer.write_unaligned(material_prec, u64::from(*material))?;
+        writer.write_unaligned(triangle_prec, u64::from(range.start))?;
+        writer.write_unaligned(triangle_prec, u64::from(range.end))?;
+    }
+    writer.flush()
+}
+pub(crate) fn read_triangle_ranges<R: std::io::Read>(
+    src: &mut R,
+) -> Result<Box<[MaterialTriangleRange]>, TMFImportError> {
+    let count = src.read_u64()?;
+    if count > MAX_SEG_SIZE as u64 {
+        return Err(TMFImportError::SegmentTooLong);
+    }
+    let material_prec = src.read_u8()?;
+    let triangle_prec = src.read_u8()?;
+    for prec in [material_prec, triangle_prec] {
+        if prec >= u64::BITS as u8 {
+            return Err(TMFImportError::InvalidPrecision(prec));
+        }
+    }
+    let material_prec = UnalignedRWMode::precision_bits(material_prec);
+    let triangle_prec = UnalignedRWMode::precision_bits(triangle_prec);
+    let mut reader = UnalignedReader::new(src);
+    let mut groups = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let material = reader.read_unaligned(material_prec)? as IndexType;
+        let (start, end) = reader.read2_unaligned(triangle_prec)?;
+        groups.push((material, (start as IndexType)..(end as IndexType)));
+    }
+    Ok(groups.into())
+}
+#[test]
+fn rw_material_segments() {
+    let materials = MaterialInfo::create(
+        ["wood".to_owned(), "metal".to_owned(), "glass".to_owned()],
+        [(0, 0..12), (2, 12..13), (1, 13..200), (0, 200..201)],
+    );
+    let mut names = Vec::new();
+    save_names(materials.materials(), &mut names).unwrap();
+    let mut ranges = Vec::new();
+    save_triangle_ranges(materials.material_groups(), &mut ranges).unwrap();
+    let r_names = read_names(&mut &names[..]).unwrap();
+    let r_ranges = read_triangle_ranges(&mut &ranges[..]).unwrap();
+    assert_eq!(materials, MaterialInfo::create(r_names, r_ranges));
+}
+#[test]
+#[cfg(feature = "obj_import")]
+fn rw_multi_mtl_tmf() {
+    use crate::{TMFMesh, TMFPrecisionInfo};
+    let mut file = std::fs::File::open("testing/multi_mtl.obj").unwrap();
+    let (tmf_mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+    tmf_mesh.verify().unwrap();
+    let materials = tmf_mesh.get_materials().expect("Mesh has no materials!");
+    assert_eq!(materials.materials(), ["pz", "mz", "my", "py", "mx", "px"]);
+    assert_eq!(materials.material_groups().len(), 6);
+    let mut out = Vec::new();
+    tmf_mesh
+        .write_tmf_one(&mut out, &TMFPrecisionInfo::default(), name)
+        .unwrap();
+    let (r_mesh, _) = TMFMesh::read_tmf_one(&mut (&out as &[u8])).unwrap();
+    r_mesh.verify().unwrap();
+    assert_eq!(r_mesh.get_materials(), Some(materials));
 }
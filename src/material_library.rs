@@ -0,0 +1,499 @@
+use crate::read_extension::ReadExt;
+use crate::tmf_exporter::write_mesh_name;
+use crate::tmf_importer::read_string;
+use crate::{FloatType, TMFImportError, Vector3, MAX_SEG_SIZE};
+use std::io::{BufRead, BufReader, BufWriter, Read, Result, Write};
+/// Properties of a single material, as defined by a material library(e.g. a `.mtl` file). Properties not set by the library are [`None`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MaterialDefinition {
+    /// Name of the material, matching names used in [`crate::MaterialInfo`].
+    pub name: String,
+    /// Ambient color(`Ka`).
+    pub ambient_color: Option<Vector3>,
+    /// Diffuse color(`Kd`).
+    pub diffuse_color: Option<Vector3>,
+    /// Specular color(`Ks`).
+    pub specular_color: Option<Vector3>,
+    /// Emissive color(`Ke`).
+    pub emissive_color: Option<Vector3>,
+    /// Specular exponent(`Ns`), between 0 and 1000.
+    pub specular_exponent: Option<FloatType>,
+    /// Opacity(`d`), where 1 is fully opaque. `Tr` is converted to it.
+    pub dissolve: Option<FloatType>,
+    /// Index of refraction(`Ni`).
+    pub optical_density: Option<FloatType>,
+    /// Illumination model(`illum`), between 0 and 10.
+    pub illumination_model: Option<u8>,
+    /// Path to the ambient texture map(`map_Ka`), including any map options preceding it.
+    pub ambient_map: Option<String>,
+    /// Path to the diffuse texture map(`map_Kd`), including any map options preceding it.
+    pub diffuse_map: Option<String>,
+    /// Path to the specular texture map(`map_Ks`), including any map options preceding it.
+    pub specular_map: Option<String>,
+    /// Path to the specular exponent texture map(`map_Ns`), including any map options preceding it.
+    pub specular_exponent_map: Option<String>,
+    /// Path to the opacity texture map(`map_d`), including any map options preceding it.
+    pub dissolve_map: Option<String>,
+    /// Path to the bump map(`map_Bump` or `bump`), including any map options preceding it.
+    pub bump_map: Option<String>,
+    /// Path to the displacement map(`disp`), including any map options preceding it.
+    pub displacement_map: Option<String>,
+    /// Statements not recognised by this crate, as a keyword and the rest of the line. They are preserved, and written back as they were.
+    pub other: Vec<(String, String)>,
+}
+impl MaterialDefinition {
+    /// Creates a new [`MaterialDefinition`] named *name*, with no properties set.
+    /// # Example
+    /// ```
+    /// # use tmf::MaterialDefinition;
+    /// let mut wood = MaterialDefinition::new("wood");
+    /// wood.diffuse_color = Some((0.5, 0.3, 0.1));
+    /// wood.diffuse_map = Some("wood.png".to_owned());
+    /// ```
+    #[must_use]
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            ..Default::default()
+        }
+    }
+    /// Returns texture map properties of this material, paired with their MTL keywords.
+    fn maps(&self) -> [(&'static str, Option<&str>); 7] {
+        [
+            ("map_Ka", self.ambient_map.as_deref()),
+            ("map_Kd", self.diffuse_map.as_deref()),
+            ("map_Ks", self.specular_map.as_deref()),
+            ("map_Ns", self.specular_exponent_map.as_deref()),
+            ("map_d", self.dissolve_map.as_deref()),
+            ("map_Bump", self.bump_map.as_deref()),
+            ("disp", self.displacement_map.as_deref()),
+        ]
+    }
+    fn maps_mut(&mut self) -> [(&'static str, &mut Option<String>); 7] {
+        [
+            ("map_Ka", &mut self.ambient_map),
+            ("map_Kd", &mut self.diffuse_map),
+            ("map_Ks", &mut self.specular_map),
+            ("map_Ns", &mut self.specular_exponent_map),
+            ("map_d", &mut self.dissolve_map),
+            ("map_Bump", &mut self.bump_map),
+            ("disp", &mut self.displacement_map),
+        ]
+    }
+    /// Returns color properties of this material, paired with their MTL keywords.
+    fn colors(&self) -> [(&'static str, Option<Vector3>); 4] {
+        [
+            ("Ka", self.ambient_color),
+            ("Kd", self.diffuse_color),
+            ("Ks", self.specular_color),
+            ("Ke", self.emissive_color),
+        ]
+    }
+    fn colors_mut(&mut self) -> [(&'static str, &mut Option<Vector3>); 4] {
+        [
+            ("Ka", &mut self.ambient_color),
+            ("Kd", &mut self.diffuse_color),
+            ("Ks", &mut self.specular_color),
+            ("Ke", &mut self.emissive_color),
+        ]
+    }
+    /// Returns scalar properties of this material, paired with their MTL keywords.
+    fn scalars(&self) -> [(&'static str, Option<FloatType>); 3] {
+        [
+            ("Ns", self.specular_exponent),
+            ("Ni", self.optical_density),
+            ("d", self.dissolve),
+        ]
+    }
+    fn scalars_mut(&mut self) -> [(&'static str, &mut Option<FloatType>); 3] {
+        [
+            ("Ns", &mut self.specular_exponent),
+            ("Ni", &mut self.optical_density),
+            ("d", &mut self.dissolve),
+        ]
+    }
+    /// Applies MTL statement *keyword* with arguments *rest*. Statements which can't be parsed are kept in [`Self::other`].
+    fn parse_statement(&mut self, keyword: &str, rest: &str) {
+        let keyword = match keyword {
+            "bump" | "map_bump" => "map_Bump",
+            keyword => keyword,
+        };
+        if !self.apply_statement(keyword, rest) {
+            self.other.push((keyword.to_owned(), rest.to_owned()));
+        }
+    }
+    /// Sets the property described by MTL statement *keyword*, and returns if it was recognised and parsed.
+    fn apply_statement(&mut self, keyword: &str, rest: &str) -> bool {
+        if let Some((_, map)) = self.maps_mut().into_iter().find(|(key, _)| *key == keyword) {
+            *map = Some(rest.to_owned());
+            return true;
+        }
+        if let Some((_, color)) = self
+            .colors_mut()
+            .into_iter()
+            .find(|(key, _)| *key == keyword)
+        {
+            let values: Option<Vec<FloatType>> = rest
+                .split_whitespace()
+                .map(|value| value.parse().ok())
+                .collect();
+            // Colors may also be given as a single value, or as spectral curves and CIE XYZ values, which are not supported.
+            match values.as_deref() {
+                Some([r, g, b]) => *color = Some((*r, *g, *b)),
+                Some([value]) => *color = Some((*value, *value, *value)),
+                _ => return false,
+            }
+            return true;
+        }
+        if let Some((_, scalar)) = self
+            .scalars_mut()
+            .into_iter()
+            .find(|(key, _)| *key == keyword)
+        {
+            return rest.parse().map(|value| *scalar = Some(value)).is_ok();
+        }
+        match keyword {
+            "Tr" => rest
+                .parse::<FloatType>()
+                .map(|transparency| self.dissolve = Some(1.0 - transparency))
+                .is_ok(),
+            "illum" => rest
+                .parse()
+                .map(|model| self.illumination_model = Some(model))
+                .is_ok(),
+            _ => false,
+        }
+    }
+    fn write_mtl<W: Write>(&self, w: &mut W) -> Result<()> {
+        writeln!(w, "newmtl {}", self.name)?;
+        for (keyword, color) in self.colors() {
+            if let Some((r, g, b)) = color {
+                writeln!(w, "{keyword} {r} {g} {b}")?;
+            }
+        }
+        for (keyword, scalar) in self.scalars() {
+            if let Some(value) = scalar {
+                writeln!(w, "{keyword} {value}")?;
+            }
+        }
+        if let Some(model) = self.illumination_model {
+            writeln!(w, "illum {model}")?;
+        }
+        for (keyword, map) in self.maps() {
+            if let Some(map) = map {
+                writeln!(w, "{keyword} {map}")?;
+            }
+        }
+        for (keyword, rest) in &self.other {
+            writeln!(w, "{keyword} {rest}")?;
+        }
+        Ok(())
+    }
+    /// Saves this material in the binary layout used by `MaterialDefinitions` segments. Each property is saved as a tag, byte length and its data, so readers can skip unknown properties.
+    fn write_tmf<W: Write>(&self, w: &mut W) -> Result<()> {
+        write_mesh_name(w, &self.name)?;
+        let mut properties: Vec<(u8, Vec<u8>)> = Vec::new();
+        let float_bytes = |values: &[FloatType]| -> Vec<u8> {
+            values
+                .iter()
+                .flat_map(|value| (*value as f64).to_le_bytes())
+                .collect()
+        };
+        for (tag, (_, color)) in (COLOR_TAG..).zip(self.colors()) {
+            if let Some((r, g, b)) = color {
+                properties.push((tag, float_bytes(&[r, g, b])));
+            }
+        }
+        for (tag, (_, scalar)) in (SCALAR_TAG..).zip(self.scalars()) {
+            if let Some(value) = scalar {
+                properties.push((tag, float_bytes(&[value])));
+            }
+        }
+        if let Some(model) = self.illumination_model {
+            properties.push((ILLUMINATION_TAG, vec![model]));
+        }
+        for (tag, (_, map)) in (MAP_TAG..).zip(self.maps()) {
+            if let Some(map) = map {
+                properties.push((tag, map.as_bytes().to_vec()));
+            }
+        }
+        for (keyword, rest) in &self.other {
+            let mut data = Vec::new();
+            write_mesh_name(&mut data, keyword)?;
+            write_mesh_name(&mut data, rest)?;
+            properties.push((OTHER_TAG, data));
+        }
+        w.write_all(&(properties.len() as u16).to_le_bytes())?;
+        for (tag, data) in properties {
+            w.write_all(&[tag])?;
+            w.write_all(&(data.len() as u16).to_le_bytes())?;
+            w.write_all(&data)?;
+        }
+        Ok(())
+    }
+    fn read_tmf<R: Read>(src: &mut R) -> std::result::Result<Self, TMFImportError> {
+        let mut material = Self::new(&read_string(src)?);
+        let property_count = src.read_u16()?;
+        for _ in 0..property_count {
+            let tag = src.read_u8()?;
+            let mut data = vec![0; src.read_u16()? as usize];
+            src.read_exact(&mut data)?;
+            let mut data = &data[..];
+            match tag {
+                COLOR_TAG..SCALAR_TAG => {
+                    let color = (
+                        data.read_f64()? as FloatType,
+                        data.read_f64()? as FloatType,
+                        data.read_f64()? as FloatType,
+                    );
+                    *material.colors_mut()[(tag - COLOR_TAG) as usize].1 = Some(color);
+                }
+                SCALAR_TAG..ILLUMINATION_TAG => {
+                    let value = data.read_f64()? as FloatType;
+                    *material.scalars_mut()[(tag - SCALAR_TAG) as usize].1 = Some(value);
+                }
+                ILLUMINATION_TAG => material.illumination_model = Some(data.read_u8()?),
+                MAP_TAG..MAP_END_TAG => {
+                    let map = String::from_utf8(data.to_vec())
+                        .map_err(|_| std::io::Error::other("Texture map path not valid UTF-8"))?;
+                    *material.maps_mut()[(tag - MAP_TAG) as usize].1 = Some(map);
+                }
+                OTHER_TAG => {
+                    let keyword = read_string(&mut data)?;
+                    let rest = read_string(&mut data)?;
+                    material.other.push((keyword, rest));
+                }
+                // Properties added by newer versions of the format are skipped.
+                _ => (),
+            }
+        }
+        Ok(material)
+    }
+}
+// Tags of properties saved in `MaterialDefinitions` segments.
+const COLOR_TAG: u8 = 0;
+const SCALAR_TAG: u8 = 4;
+const ILLUMINATION_TAG: u8 = 7;
+const MAP_TAG: u8 = 8;
+const MAP_END_TAG: u8 = 15;
+const OTHER_TAG: u8 = 255;
+/// A library of material definitions, such as ones read from a `.mtl` file. Can be stored in a TMF file alongside a mesh, using [`crate::TMFMesh::set_material_library`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MaterialLibrary {
+    materials: Vec<MaterialDefinition>,
+}
+impl MaterialLibrary {
+    /// Creates a new, empty [`MaterialLibrary`].
+    /// # Example
+    /// ```
+    /// # use tmf::MaterialLibrary;
+    /// let library = MaterialLibrary::new();
+    /// assert!(library.materials().is_empty());
+    /// ```
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds *material* to this library. If a material with the same name is already present, it is replaced, and the old definition is returned.
+    /// # Example
+    /// ```
+    /// # use tmf::{MaterialDefinition, MaterialLibrary};
+    /// let mut library = MaterialLibrary::new();
+    /// assert!(library.add_material(MaterialDefinition::new("wood")).is_none());
+    /// assert!(library.add_material(MaterialDefinition::new("wood")).is_some());
+    /// assert_eq!(library.materials().len(), 1);
+    /// ```
+    pub fn add_material(&mut self, material: MaterialDefinition) -> Option<MaterialDefinition> {
+        match self
+            .materials
+            .iter_mut()
+            .find(|curr| curr.name == material.name)
+        {
+            Some(curr) => Some(std::mem::replace(curr, material)),
+            None => {
+                self.materials.push(material);
+                None
+            }
+        }
+    }
+    /// Returns all material definitions in this library.
+    #[must_use]
+    pub fn materials(&self) -> &[MaterialDefinition] {
+        &self.materials
+    }
+    /// Returns the definition of material *name*, if present.
+    /// # Example
+    /// ```
+    /// # use tmf::{MaterialDefinition, MaterialLibrary};
+    /// let mut library = MaterialLibrary::new();
+    /// library.add_material(MaterialDefinition::new("wood"));
+    /// assert!(library.lookup_material("wood").is_some());
+    /// assert!(library.lookup_material("metal").is_none());
+    /// ```
+    #[must_use]
+    pub fn lookup_material(&self, name: &str) -> Option<&MaterialDefinition> {
+        self.materials.iter().find(|material| material.name == name)
+    }
+    /// Reads a material library from `.mtl` file in *reader*. Statements before the first `newmtl` are ignored.
+    /// # Example
+    /// ```
+    /// # use tmf::MaterialLibrary;
+    /// let mtl = "newmtl wood\nKd 0.5 0.3 0.1\nmap_Kd wood.png\nillum 2\n";
+    /// let library = MaterialLibrary::read_mtl(&mut mtl.as_bytes()).unwrap();
+    /// let wood = library.lookup_material("wood").unwrap();
+    /// assert_eq!(wood.diffuse_color, Some((0.5, 0.3, 0.1)));
+    /// assert_eq!(wood.diffuse_map.as_deref(), Some("wood.png"));
+    /// assert_eq!(wood.illumination_model, Some(2));
+    /// ```
+    pub fn read_mtl<R: Read>(reader: &mut R) -> Result<Self> {
+        let reader = BufReader::new(reader);
+        let mut library = Self::new();
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            if keyword == "newmtl" {
+                library.add_material(MaterialDefinition::new(rest));
+            } else if let Some(material) = library.materials.last_mut() {
+                material.parse_statement(keyword, rest);
+            }
+        }
+        Ok(library)
+    }
+    /// Writes this material library to *w* as a `.mtl` file.
+    /// # Example
+    /// ```
+    /// # use tmf::{MaterialDefinition, MaterialLibrary};
+    /// let mut library = MaterialLibrary::new();
+    /// library.add_material(MaterialDefinition::new("wood"));
+    /// let mut out = Vec::new();
+    /// library.write_mtl(&mut out).unwrap();
+    /// assert_eq!(MaterialLibrary::read_mtl(&mut &out[..]).unwrap(), library);
+    /// ```
+    pub fn write_mtl<W: Write>(&self, w: &mut W) -> Result<()> {
+        let mut w = BufWriter::new(w);
+        for material in &self.materials {
+            material.write_mtl(&mut w)?;
+            writeln!(w)?;
+        }
+        w.flush()
+    }
+    pub(crate) fn extend_materials(&mut self, materials: &[MaterialDefinition]) {
+        for material in materials {
+            self.add_material(material.clone());
+        }
+    }
+}
+/// Saves a list of material definitions.
+pub(crate) fn save_definitions<W: Write>(
+    materials: &[MaterialDefinition],
+    target: &mut W,
+) -> Result<()> {
+    target.write_all(&(materials.len() as u32).to_le_bytes())?;
+    for material in materials {
+        material.write_tmf(target)?;
+    }
+    Ok(())
+}
+pub(crate) fn read_definitions<R: Read>(
+    src: &mut R,
+) -> std::result::Result<Box<[MaterialDefinition]>, TMFImportError> {
+    let count = src.read_u32()? as usize;
+    if count > MAX_SEG_SIZE {
+        return Err(TMFImportError::SegmentTooLong);
+    }
+    let mut materials = Vec::with_capacity(count.min(u16::MAX as usize));
+    for _ in 0..count {
+        materials.push(MaterialDefinition::read_tmf(src)?);
+    }
+    Ok(materials.into())
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn read_multi_mtl() {
+        let mut file = std::fs::File::open("testing/multi_mtl.mtl").unwrap();
+        let library = MaterialLibrary::read_mtl(&mut file).unwrap();
+        let names: Vec<&str> = library
+            .materials()
+            .iter()
+            .map(|material| material.name.as_str())
+            .collect();
+        assert_eq!(names, ["mx", "my", "mz", "px", "py", "pz"]);
+        let mx = library.lookup_material("mx").unwrap();
+        assert_eq!(mx.specular_exponent, Some(360.0));
+        assert_eq!(mx.ambient_color, Some((1.0, 1.0, 1.0)));
+        assert_eq!(mx.diffuse_color, Some((0.8, 0.8, 0.8)));
+        assert_eq!(mx.specular_color, Some((0.5, 0.5, 0.5)));
+        assert_eq!(mx.illumination_model, Some(2));
+        assert!(mx.other.is_empty());
+    }
+    #[test]
+    fn rw_mtl_statements() {
+        let mtl = "# comment
+Kd 1 1 1
+newmtl brick
+Ka 0.2
+Kd 0.6 0.3 0.2
+Tr 0.25
+map_Kd -s 2 2 1 textures/brick.png
+bump brick_bump.png
+Pr 0.5
+Kd spectral brick.rfl
+";
+        let library = MaterialLibrary::read_mtl(&mut mtl.as_bytes()).unwrap();
+        assert_eq!(library.materials().len(), 1);
+        let brick = library.lookup_material("brick").unwrap();
+        assert_eq!(brick.ambient_color, Some((0.2, 0.2, 0.2)));
+        assert_eq!(brick.diffuse_color, Some((0.6, 0.3, 0.2)));
+        assert_eq!(brick.dissolve, Some(0.75));
+        assert_eq!(
+            brick.diffuse_map.as_deref(),
+            Some("-s 2 2 1 textures/brick.png")
+        );
+        assert_eq!(brick.bump_map.as_deref(), Some("brick_bump.png"));
+        assert_eq!(
+            brick.other,
+            [
+                ("Pr".to_owned(), "0.5".to_owned()),
+                ("Kd".to_owned(), "spectral brick.rfl".to_owned())
+            ]
+        );
+        let mut out = Vec::new();
+        library.write_mtl(&mut out).unwrap();
+        assert_eq!(MaterialLibrary::read_mtl(&mut &out[..]).unwrap(), library);
+    }
+    #[test]
+    fn rw_definitions_tmf() {
+        let mut file = std::fs::File::open("testing/multi_mtl.mtl").unwrap();
+        let mut library = MaterialLibrary::read_mtl(&mut file).unwrap();
+        let mut brick = MaterialDefinition::new("brick");
+        brick.diffuse_map = Some("brick.png".to_owned());
+        brick.other.push(("Pr".to_owned(), "0.5".to_owned()));
+        library.add_material(brick);
+        let mut data = Vec::new();
+        save_definitions(library.materials(), &mut data).unwrap();
+        let materials = read_definitions(&mut &data[..]).unwrap();
+        assert_eq!(&materials[..], library.materials());
+    }
+    #[test]
+    #[cfg(feature = "obj_import")]
+    fn rw_library_in_tmf() {
+        use crate::{TMFMesh, TMFPrecisionInfo};
+        let mut file = std::fs::File::open("testing/multi_mtl.obj").unwrap();
+        let (mut mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+        let mut file = std::fs::File::open("testing/multi_mtl.mtl").unwrap();
+        let library = MaterialLibrary::read_mtl(&mut file).unwrap();
+        mesh.set_material_library(library.clone());
+        let mut out = Vec::new();
+        mesh.write_tmf_one(&mut out, &TMFPrecisionInfo::default(), name)
+            .unwrap();
+        let (r_mesh, _) = TMFMesh::read_tmf_one(&mut &out[..]).unwrap();
+        assert_eq!(r_mesh.get_material_library(), Some(&library));
+    }
+}
@@ -1,11 +1,9 @@
-#[cfg(not(any(feature = "obj_import")))]
+// Materials, groups and multiple meshes are only used by the .obj importer.
+#![cfg_attr(not(feature = "obj_import"), allow(dead_code))]
+#[cfg(not(any(feature = "obj_import", feature = "ply")))]
 compile_error!("Feature \"model_importer\" is only useful when another feature using it is enabled(e.g. obj importer) and is otherwise useless dead code.");
-#[cfg(feature = "triangulation")]
-use crate::obj::SMALL_VEC_CAP;
-use crate::{IndexType, TMFMesh, Vector2, Vector3};
-//const SMALL_VEC_CAP: usize = 8;
-#[cfg(feature = "triangulation")]
-include!("triangulation.rs");
+use crate::{GroupInfo, IndexType, MaterialInfo, SmoothingGroupRange, TMFMesh, Vector2, Vector3};
+pub(crate) const SMALL_VEC_CAP: usize = 8;
 pub(crate) struct ModelImporter {
     vertices: Vec<Vector3>,
     normals: Vec<Vector3>,
@@ -13,6 +11,17 @@ pub(crate) struct ModelImporter {
     vertex_triangles: Vec<IndexType>,
     normal_triangles: Vec<IndexType>,
     uv_triangles: Vec<IndexType>,
+    materials: MaterialInfo,
+    // Material currently in use, and the first triangle using it.
+    current_material: Option<(IndexType, IndexType)>,
+    groups: GroupInfo,
+    // Groups currently in use, and the first triangle belonging to them.
+    current_groups: Vec<(IndexType, IndexType)>,
+    // Libraries defining materials, shared by all meshes.
+    material_libraries: Vec<String>,
+    smoothing_groups: Vec<SmoothingGroupRange>,
+    // Smoothing group currently in use, and the first triangle belonging to it.
+    current_smoothing: Option<(IndexType, IndexType)>,
     name: String,
 }
 impl ModelImporter {
@@ -24,6 +33,13 @@ impl ModelImporter {
             vertex_triangles: Vec::new(),
             normal_triangles: Vec::new(),
             uv_triangles: Vec::new(),
+            materials: MaterialInfo::new(),
+            current_material: None,
+            groups: GroupInfo::new(),
+            current_groups: Vec::new(),
+            material_libraries: Vec::new(),
+            smoothing_groups: Vec::new(),
+            current_smoothing: None,
             name: "".to_owned(),
         }
     }
@@ -33,24 +49,7 @@ impl ModelImporter {
         if self.vertex_triangles.is_empty() {
             return None;
         }
-        let mut mesh = TMFMesh::empty();
-        let (mut vertices, mut vertex_triangles) =
-            (self.vertices.clone(), self.vertex_triangles.clone());
-        let (mut normals, mut normal_triangles) =
-            (self.normals.clone(), self.normal_triangles.clone());
-        let (mut uvs, mut uv_triangles) = (self.uvs.clone(), self.uv_triangles.clone());
-
-        crate::utilis::fast_prune(&mut vertices, &mut vertex_triangles);
-        crate::utilis::fast_prune(&mut normals, &mut normal_triangles);
-        crate::utilis::fast_prune(&mut uvs, &mut uv_triangles);
-
-        mesh.set_vertices(vertices);
-        mesh.set_normals(normals);
-        mesh.set_uvs(uvs);
-
-        mesh.set_vertex_triangles(vertex_triangles);
-        mesh.set_normal_triangles(normal_triangles);
-        mesh.set_uv_triangles(uv_triangles);
+        let mesh = self.build_mesh();
 
         self.vertex_triangles.clear();
         self.normal_triangles.clear();
@@ -58,7 +57,12 @@ impl ModelImporter {
 
         Some((mesh, name))
     }
-    pub(crate) fn finish(self) -> std::io::Result<(TMFMesh, String)> {
+    pub(crate) fn finish(mut self) -> std::io::Result<(TMFMesh, String)> {
+        let mesh = self.build_mesh();
+        Ok((mesh, self.name))
+    }
+    /// Creates a mesh from data pushed so far. Arrays with no data are left unset, so a mesh with no faces becomes a point cloud.
+    fn build_mesh(&mut self) -> TMFMesh {
         let mut mesh = TMFMesh::empty();
 
         let (mut vertices, mut vertex_triangles) =
@@ -72,14 +76,148 @@ impl ModelImporter {
         crate::utilis::fast_prune(&mut uvs, &mut uv_triangles);
 
         mesh.set_vertices(vertices);
-        mesh.set_normals(normals);
-        mesh.set_uvs(uvs);
-
-        mesh.set_vertex_triangles(vertex_triangles);
-        mesh.set_normal_triangles(normal_triangles);
-        mesh.set_uv_triangles(uv_triangles);
-
-        Ok((mesh, self.name))
+        if !normals.is_empty() {
+            mesh.set_normals(normals);
+        }
+        if !uvs.is_empty() {
+            mesh.set_uvs(uvs);
+        }
+        if !vertex_triangles.is_empty() {
+            mesh.set_vertex_triangles(vertex_triangles);
+            if mesh.get_normals().is_some() {
+                mesh.set_normal_triangles(normal_triangles);
+            }
+            if mesh.get_uvs().is_some() {
+                mesh.set_uv_triangles(uv_triangles);
+            }
+        }
+        if let Some(materials) = self.take_materials() {
+            mesh.set_materials(materials);
+        }
+        if let Some(groups) = self.take_groups() {
+            mesh.set_groups(groups);
+        }
+        if let Some(smoothing_groups) = self.take_smoothing_groups() {
+            mesh.set_smoothing_groups(smoothing_groups);
+        }
+        mesh
+    }
+    /// Closes the triangle range of the material currently in use.
+    fn end_material_range(&mut self) {
+        if let Some((material, start)) = self.current_material {
+            let end = (self.vertex_triangles.len() / 3) as IndexType;
+            if end > start {
+                self.materials.add_triangle_range(material, start..end);
+            }
+        }
+    }
+    /// Makes all following faces use material *name*. An empty *name* makes them use no material.
+    pub(crate) fn use_material(&mut self, name: &str) {
+        self.end_material_range();
+        if name.is_empty() {
+            self.current_material = None;
+            return;
+        }
+        let material = self.materials.add_material(name);
+        self.current_material = Some((material, (self.vertex_triangles.len() / 3) as IndexType));
+    }
+    /// Adds libraries *names* to libraries defining materials of all meshes.
+    pub(crate) fn use_material_libraries<'a, I: Iterator<Item = &'a str>>(&mut self, names: I) {
+        for name in names {
+            if !self.material_libraries.iter().any(|curr| curr == name) {
+                self.material_libraries.push(name.to_owned());
+            }
+        }
+    }
+    /// Takes material info of the current mesh. The material in use stays in use for the next mesh.
+    fn take_materials(&mut self) -> Option<MaterialInfo> {
+        self.end_material_range();
+        let mut materials = std::mem::take(&mut self.materials);
+        if let Some((material, _)) = self.current_material {
+            let name = &materials.materials()[material as usize];
+            self.current_material = Some((self.materials.add_material(name), 0));
+        }
+        materials.extend_libraries(&self.material_libraries);
+        if materials.material_groups().is_empty() && materials.libraries().is_empty() {
+            None
+        } else {
+            Some(materials)
+        }
+    }
+    /// Closes the triangle ranges of all groups currently in use.
+    fn end_group_ranges(&mut self) {
+        let end = (self.vertex_triangles.len() / 3) as IndexType;
+        for (group, start) in std::mem::take(&mut self.current_groups) {
+            if end > start {
+                self.groups.add_triangle_range(group, start..end);
+            }
+        }
+    }
+    /// Makes all following faces belong to groups *names*(and only to them).
+    pub(crate) fn use_groups<'a, I: Iterator<Item = &'a str>>(&mut self, names: I) {
+        self.end_group_ranges();
+        let start = (self.vertex_triangles.len() / 3) as IndexType;
+        for name in names {
+            let group = self.groups.add_group(name);
+            if !self.current_groups.iter().any(|(curr, _)| *curr == group) {
+                self.current_groups.push((group, start));
+            }
+        }
+    }
+    /// Takes group info of the current mesh. The groups in use stay in use for the next mesh.
+    fn take_groups(&mut self) -> Option<GroupInfo> {
+        let current: Vec<IndexType> = self
+            .current_groups
+            .iter()
+            .map(|(group, _)| *group)
+            .collect();
+        self.end_group_ranges();
+        let groups = std::mem::take(&mut self.groups);
+        self.use_groups(
+            current
+                .iter()
+                .map(|group| groups.groups()[*group as usize].as_str()),
+        );
+        // Triangles of the next mesh are counted from 0.
+        self.current_groups
+            .iter_mut()
+            .for_each(|(_, start)| *start = 0);
+        if groups.group_ranges().is_empty() {
+            None
+        } else {
+            Some(groups)
+        }
+    }
+    /// Closes the triangle range of the smoothing group currently in use.
+    fn end_smoothing_range(&mut self) {
+        if let Some((group, start)) = self.current_smoothing {
+            let end = (self.vertex_triangles.len() / 3) as IndexType;
+            if end > start {
+                self.smoothing_groups.push((group, start..end));
+            }
+        }
+    }
+    /// Makes all following faces belong to smoothing group *group*. Group 0 turns smoothing off.
+    pub(crate) fn use_smoothing_group(&mut self, group: IndexType) {
+        if self.current_smoothing.map(|(curr, _)| curr) == Some(group) {
+            return;
+        }
+        self.end_smoothing_range();
+        self.current_smoothing =
+            (group != 0).then_some((group, (self.vertex_triangles.len() / 3) as IndexType));
+    }
+    /// Takes smoothing groups of the current mesh. The smoothing group in use stays in use for the next mesh.
+    fn take_smoothing_groups(&mut self) -> Option<Vec<SmoothingGroupRange>> {
+        self.end_smoothing_range();
+        if let Some((group, _)) = self.current_smoothing {
+            self.current_smoothing = Some((group, 0));
+        }
+        let smoothing_groups = std::mem::take(&mut self.smoothing_groups);
+        if smoothing_groups.is_empty() {
+            None
+        } else {
+            Some(smoothing_groups)
+        }
     }
     pub(crate) fn push_vertex(&mut self, vertex: Vector3) {
         self.vertices.push(vertex);
@@ -133,14 +271,28 @@ impl ModelImporter {
             _ => {
                 #[cfg(not(feature = "triangulation"))]
                 {
-                    Err("Encountered a face that needed triangulation but experimental triangulation feature is disabled".to_owned())
+                    Err("Encountered a face that needed triangulation but triangulation feature is disabled".to_owned())
                 }
                 #[cfg(feature = "triangulation")]
                 {
-                    let vertex_indices = SmallVec::from_slice(vertex_indices);
-                    let normal_indices = SmallVec::from_slice(normal_indices);
-                    let uv_indices = SmallVec::from_slice(uv_indices);
-                    triangulate(self, vertex_indices, normal_indices, uv_indices);
+                    let mut polygon: smallvec::SmallVec<[Vector3; SMALL_VEC_CAP]> =
+                        smallvec::SmallVec::with_capacity(vertex_indices.len());
+                    for index in vertex_indices {
+                        match self.vertices.get(*index as usize) {
+                            Some(vertex) => polygon.push(*vertex),
+                            None => {
+                                return Err(format!(
+                                    "Face uses vertex {index}, but there are only {} vertices",
+                                    self.vertices.len()
+                                ))
+                            }
+                        }
+                    }
+                    for corner in crate::triangulation::triangulate_polygon(&polygon, &[]) {
+                        self.push_vertex_index(vertex_indices[corner]);
+                        self.push_uv_index(uv_indices[corner]);
+                        self.push_normal_index(normal_indices[corner]);
+                    }
                     Ok(())
                 }
             }
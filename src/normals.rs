@@ -1,13 +1,14 @@
 use crate::read_extension::ReadExt;
+use crate::typed_mesh::{MeshFloat, Vector3Of};
 use crate::unaligned_rw::{UnalignedRWMode, UnalignedReader, UnalignedWriter};
-use crate::{FloatType, TMFImportError, Vector3, MAX_SEG_SIZE};
+use crate::{FloatType, TMFImportError, Vector2, Vector3, MAX_SEG_SIZE};
 #[cfg(not(feature = "double_precision"))]
 use std::f32::consts::FRAC_PI_2;
 #[cfg(feature = "double_precision")]
 use std::f64::consts::FRAC_PI_2;
 use std::io::{Read, Write};
 
-#[derive(Clone, Copy, PartialEq)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 /// Setting dictating how much can any normal in a model deviate, expressed as an angle.
 pub struct NormalPrecisionMode(u8);
 impl NormalPrecisionMode {
@@ -84,35 +85,36 @@ pub(crate) fn normal_to_encoding(
     let sz = normal.2 < 0.0;
     (asine, z, sx, sy, sz)
 }
-pub(crate) fn normal_from_encoding(
+pub(crate) fn normal_from_encoding<F: MeshFloat>(
     asine: u64,
     z: u64,
     sx: bool,
     sy: bool,
     sz: bool,
     precision: NormalPrecisionMode,
-) -> Vector3 {
+) -> Vector3Of<F> {
+    let one = F::from_f64(1.0);
     if precision.0 == 0 {
-        let x = if sx { -1.0 } else { 1.0 };
-        let y = if sy { -1.0 } else { 1.0 };
-        let z = if sz { -1.0 } else { 1.0 };
+        let x = if sx { -one } else { one };
+        let y = if sy { -one } else { one };
+        let z = if sz { -one } else { one };
         return (x, y, z);
     }
-    let divisor = ((1_u64 << precision.0) - 1) as FloatType;
+    let divisor = F::from_u64((1_u64 << precision.0) - 1);
     //Read raw asine
-    let asine = (asine as FloatType) / divisor;
+    let asine = F::from_u64(asine) / divisor;
     //Convert asine form 0-1 to 0-tau
-    let asine = asine * (PI / 2.0);
+    let asine = asine * (F::from_f64(std::f64::consts::PI) / F::from_f64(2.0));
     //Read xyz component
-    let z = (z as FloatType) / divisor;
+    let z = F::from_u64(z) / divisor;
     #[cfg(feature = "fast_trig")]
-    let x = fsin(asine as fprec) as FloatType;
+    let x = F::from_f64(fsin(asine.to_f64()));
     #[cfg(feature = "fast_trig")]
-    let y = (1.0 - x * x).sqrt();
+    let y = (one - x * x).sqrt();
     #[cfg(not(feature = "fast_trig"))]
     let (x, y) = asine.sin_cos();
     // Calculate XY magnitude
-    let xy_mag = (1.0 - z * z).sqrt();
+    let xy_mag = (one - z * z).sqrt();
     // Adjust x an y
     let y = y * xy_mag;
     let x = x * xy_mag;
@@ -123,6 +125,29 @@ pub(crate) fn normal_from_encoding(
     (x, y, z)
 }
 const PI: FloatType = std::f64::consts::PI as FloatType;
+/// Saves a 2D unit vector, using the same angle encoding as normals, but with no z component.
+#[inline(always)]
+pub(crate) fn save_unit2<W: Write>(
+    unit: Vector2,
+    precision: NormalPrecisionMode,
+    writer: &mut UnalignedWriter<W>,
+) -> std::io::Result<()> {
+    let (asine, _, sx, sy, _) = normal_to_encoding((unit.0, unit.1, 0.0), &precision);
+    writer.write_bit(sx)?;
+    writer.write_bit(sy)?;
+    writer.write_unaligned(UnalignedRWMode::precision_bits(precision.0), asine)
+}
+#[inline(always)]
+pub(crate) fn read_unit2<R: Read>(
+    precision: NormalPrecisionMode,
+    reader: &mut UnalignedReader<R>,
+) -> std::io::Result<Vector2> {
+    let sx = reader.read_bit()?;
+    let sy = reader.read_bit()?;
+    let asine = reader.read_unaligned(UnalignedRWMode::precision_bits(precision.0))?;
+    let (x, y, _) = normal_from_encoding(asine, 0, sx, sy, false, precision);
+    Ok((x, y))
+}
 #[inline(always)]
 fn save_normal<W: Write>(
     normal: Vector3,
@@ -141,10 +166,10 @@ fn save_normal<W: Write>(
     Ok(())
 }
 #[inline(always)]
-fn read_normal<R: Read>(
+fn read_normal<F: MeshFloat, R: Read>(
     precision: NormalPrecisionMode,
     reader: &mut UnalignedReader<R>,
-) -> std::io::Result<Vector3> {
+) -> std::io::Result<Vector3Of<F>> {
     let main_prec = UnalignedRWMode::precision_bits(precision.0);
     // Get signs of x y z component
     let sx = reader.read_bit()?;
@@ -169,6 +194,21 @@ pub(crate) fn save_normal_array<W: Write>(
     Ok(())
 }
 pub(crate) fn read_normal_array<R: Read>(reader: &mut R) -> Result<Box<[Vector3]>, TMFImportError> {
+    Ok(read_normal_array_prec(reader)?.0)
+}
+/// Reads an array of normals, returning it alongside the precision it was saved with.
+pub(crate) fn read_normal_array_prec<R: Read>(
+    reader: &mut R,
+) -> Result<(Box<[Vector3]>, NormalPrecisionMode), TMFImportError> {
+    let mut normals = Vec::new();
+    let precision = read_normal_array_into(reader, &mut normals)?;
+    Ok((normals.into(), precision))
+}
+/// Reads an array of normals, appending it to *normals*, and returns the precision it was saved with.
+pub(crate) fn read_normal_array_into<F: MeshFloat, R: Read>(
+    reader: &mut R,
+    normals: &mut Vec<Vector3Of<F>>,
+) -> Result<NormalPrecisionMode, TMFImportError> {
     let count = reader.read_u64()? as usize;
     if count > MAX_SEG_SIZE {
         return Err(TMFImportError::SegmentTooLong);
@@ -179,12 +219,12 @@ pub(crate) fn read_normal_array<R: Read>(reader: &mut R) -> Result<Box<[Vector3]
     }
     let precision = NormalPrecisionMode(precision);
     let mut reader = UnalignedReader::new(reader);
-    let mut normals = Vec::with_capacity(count);
+    normals.reserve(count);
     for _ in 0..count {
         let normal = read_normal(precision, &mut reader)?;
         normals.push(normal);
     }
-    Ok(normals.into())
+    Ok(precision)
 }
 #[cfg(test)]
 mod test_normal {
@@ -1,7 +1,6 @@
-use crate::model_importer::ModelImporter;
+use crate::model_importer::{ModelImporter, SMALL_VEC_CAP};
 use crate::{FloatType, IndexType, TMFMesh, Vector2, Vector3};
-use std::io::{BufReader, BufWriter, Error, ErrorKind, Read, Result, Write};
-pub(crate) const SMALL_VEC_CAP: usize = 8;
+use std::io::{BufReader, BufWriter, Error, Read, Result, Write};
 fn parse_line(line: Result<String>, oi: &mut ModelImporter) -> Result<Option<(TMFMesh, String)>> {
     let line = line?;
     // Split the line by white spaces and '/' sign used in triangles
@@ -10,9 +9,13 @@ fn parse_line(line: Result<String>, oi: &mut ModelImporter) -> Result<Option<(TM
     let beg = match_split(split.next())?;
     match beg {
         "#" => (), //Ignoring comments
-        "mtllib" => (),
-        "usemtl" => (),
-        "s" => (), //Ignore smoothness info
+        "mtllib" => oi.use_material_libraries(line.split_whitespace().skip(1)),
+        "usemtl" => oi.use_material(line["usemtl".len()..].trim()),
+        "g" => oi.use_groups(line.split_whitespace().skip(1)),
+        "s" => oi.use_smoothing_group(match line.split_whitespace().nth(1) {
+            Some("off") | None => 0,
+            Some(group) => group.parse().unwrap_or(0),
+        }),
         "v" => oi.push_vertex(load_vec3(&mut split)?),
         "vn" => oi.push_normal(load_vec3(&mut split)?),
         "vt" => oi.push_uv(load_vec2(&mut split)?),
@@ -42,10 +45,7 @@ pub fn read_from_obj<R: Read>(reader: &mut R) -> Result<Vec<(TMFMesh, String)>>
 fn parse_float_type(float: &str) -> Result<FloatType> {
     match float.parse::<FloatType>() {
         Ok(float) => Ok(float),
-        Err(err) => Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            err.to_string(),
-        )),
+        Err(err) => Err(std::io::Error::other(err.to_string())),
     }
 }
 fn parse_index(uint: &str) -> IndexType {
@@ -54,10 +54,7 @@ fn parse_index(uint: &str) -> IndexType {
 fn match_split(split: Option<&str>) -> Result<&str> {
     match split {
         Some(beg) => Ok(beg),
-        None => Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Invalid .obj line",
-        )),
+        None => Err(std::io::Error::other("Invalid .obj line")),
     }
 }
 use smallvec::SmallVec;
@@ -70,10 +67,7 @@ fn load_indices(split: &mut Split<&[char; 2]>) -> Result<(IndexType, IndexType,
     );
     let (i0, i1, i2) = (parse_index(i0), parse_index(i1), parse_index(i2));
     if i0 < 1 || i1 < 1 || i2 < 1 {
-        Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Invalid index!",
-        ))
+        Err(std::io::Error::other("Invalid index!"))
     } else {
         Ok((i0 - 1, i1 - 1, i2 - 1))
     }
@@ -90,7 +84,7 @@ fn load_face(split: &mut Split<&[char; 2]>, oi: &mut ModelImporter) -> Result<()
     }
     match oi.push_face(&vertex_indices, &uv_indices, &normal_indices) {
         Ok(()) => Ok(()),
-        Err(msg) => Err(Error::new(ErrorKind::Other, msg)),
+        Err(msg) => Err(Error::other(msg)),
     }
 }
 pub fn load_vec3(split: &mut Split<&[char; 2]>) -> Result<Vector3> {
@@ -109,11 +103,101 @@ pub fn load_vec2(split: &mut Split<&[char; 2]>) -> Result<Vector2> {
     let (x, y) = (match_split(split.next())?, match_split(split.next())?);
     Ok((parse_float_type(x)?, parse_float_type(y)?))
 }
+/// Material, groups and smoothing group of faces written last. Like in the reader, they carry over to the next mesh.
+#[derive(Default)]
+struct FaceState {
+    material: Option<String>,
+    groups: Vec<String>,
+    smoothing_group: IndexType,
+}
+impl FaceState {
+    /// Returns the state of triangle *triangle* of *mesh*.
+    fn of_triangle(mesh: &TMFMesh, triangle: IndexType) -> Self {
+        let material = mesh.get_materials().and_then(|materials| {
+            materials
+                .material_groups()
+                .iter()
+                .find(|(_, range)| range.contains(&triangle))
+                .map(|(material, _)| materials.materials()[*material as usize].clone())
+        });
+        let mut groups: Vec<String> = Vec::new();
+        if let Some(group_info) = mesh.get_groups() {
+            for (group, range) in group_info.group_ranges() {
+                let name = &group_info.groups()[*group as usize];
+                if range.contains(&triangle) && !groups.contains(name) {
+                    groups.push(name.clone());
+                }
+            }
+        }
+        let smoothing_group = mesh
+            .get_smoothing_groups()
+            .and_then(|ranges| ranges.iter().find(|(_, range)| range.contains(&triangle)))
+            .map_or(0, |(group, _)| *group);
+        Self {
+            material,
+            groups,
+            smoothing_group,
+        }
+    }
+    /// Writes lines changing the state from *self* to *next*.
+    fn write_change<W: Write>(&mut self, w: &mut W, next: Self) -> Result<()> {
+        // A bare `g` or `usemtl` makes the following faces belong to no group or use no material.
+        if next.groups != self.groups {
+            match next.groups.is_empty() {
+                true => writeln!(w, "g")?,
+                false => writeln!(w, "g {}", next.groups.join(" "))?,
+            }
+        }
+        if next.material != self.material {
+            match &next.material {
+                Some(material) => writeln!(w, "usemtl {material}")?,
+                None => writeln!(w, "usemtl")?,
+            }
+        }
+        if next.smoothing_group != self.smoothing_group {
+            match next.smoothing_group {
+                0 => writeln!(w, "s off")?,
+                group => writeln!(w, "s {group}")?,
+            }
+        }
+        *self = next;
+        Ok(())
+    }
+}
+/// Returns sorted triangles at which any material, group or smoothing group range begins or ends.
+fn range_boundaries(mesh: &TMFMesh) -> Vec<IndexType> {
+    let mut boundaries = vec![0];
+    let mut push_range = |range: &std::ops::Range<IndexType>| {
+        boundaries.push(range.start);
+        boundaries.push(range.end);
+    };
+    if let Some(materials) = mesh.get_materials() {
+        materials
+            .material_groups()
+            .iter()
+            .for_each(|(_, range)| push_range(range));
+    }
+    if let Some(groups) = mesh.get_groups() {
+        groups
+            .group_ranges()
+            .iter()
+            .for_each(|(_, range)| push_range(range));
+    }
+    if let Some(smoothing_groups) = mesh.get_smoothing_groups() {
+        smoothing_groups
+            .iter()
+            .for_each(|(_, range)| push_range(range));
+    }
+    boundaries.sort_unstable();
+    boundaries.dedup();
+    boundaries
+}
 fn save_obj<W: Write>(
     w: &mut W,
     mesh: &TMFMesh,
     index_offset: (IndexType, IndexType, IndexType),
     name: &str,
+    state: &mut FaceState,
 ) -> Result<(IndexType, IndexType, IndexType)> {
     writeln!(w, "o {name}")?;
     let vertex_count = match mesh.get_vertices() {
@@ -148,13 +232,10 @@ fn save_obj<W: Write>(
         // If no vertex triangle array, then object is a point cloud, so should not have any other triangle array.
         None => {
             if mesh.get_normal_triangles().is_some() {
-                return Err(Error::new(ErrorKind::Other, "If vertex triangles data is not present, normal triangle data must not be present either!"));
+                return Err(Error::other("If vertex triangles data is not present, normal triangle data must not be present either!"));
             }
             if mesh.get_uv_triangles().is_some() {
-                return Err(Error::new(
-                    ErrorKind::Other,
-                    "If vertex triangles data is not present, uv triangle data must not be present either!",
-                ));
+                return Err(Error::other("If vertex triangles data is not present, uv triangle data must not be present either!"));
             }
             return Ok((0, 0, 0));
         }
@@ -162,8 +243,7 @@ fn save_obj<W: Write>(
     // Ensure normal triangle array, if present, has the right length.
     if let Some(normal_triangles) = mesh.get_normal_triangles() {
         if normal_triangles.len() != vert_triangle_len {
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Number of triangles in the vertex triangle and normal triangle array differs.",
             ));
         }
@@ -171,15 +251,19 @@ fn save_obj<W: Write>(
     // Ensure uv triangle array, if present, has the right length.
     if let Some(uv_triangles) = mesh.get_uv_triangles() {
         if uv_triangles.len() != vert_triangle_len {
-            return Err(Error::new(
-                ErrorKind::Other,
+            return Err(Error::other(
                 "Number of triangles in the vertex triangle and uv triangle array differs.",
             ));
         }
     }
+    let mut boundaries = range_boundaries(mesh).into_iter().peekable();
     // TODO: this part can be rewritten to be more efficient by checking if arrays are present beforehand.
     for i in 0..vert_triangle_len {
         if i % 3 == 0 {
+            let triangle = (i / 3) as IndexType;
+            if boundaries.next_if_eq(&triangle).is_some() {
+                state.write_change(w, FaceState::of_triangle(mesh, triangle))?;
+            }
             write!(w, "f ")?
         };
         // Why unwrap? Vertex triangle array MUST be present at this point in time, because if it was not, this function would have already returned.
@@ -224,10 +308,23 @@ pub fn write_obj<W: Write, S: std::borrow::Borrow<str>>(
     w: &mut W,
 ) -> Result<()> {
     let mut w = BufWriter::new(w);
-    writeln!(w, "s 1")?;
+    let mut libraries: Vec<&str> = Vec::new();
+    for library in meshes
+        .iter()
+        .filter_map(|(mesh, _)| mesh.get_materials())
+        .flat_map(|materials| materials.libraries())
+    {
+        if !libraries.contains(&library.as_str()) {
+            libraries.push(library);
+        }
+    }
+    if !libraries.is_empty() {
+        writeln!(w, "mtllib {}", libraries.join(" "))?;
+    }
+    let mut state = FaceState::default();
     let mut index_offsets = (1, 1, 1);
     for (mesh, name) in meshes {
-        let curr_offsets = save_obj(&mut w, mesh, index_offsets, name.borrow())?;
+        let curr_offsets = save_obj(&mut w, mesh, index_offsets, name.borrow(), &mut state)?;
         index_offsets.0 += curr_offsets.0;
         index_offsets.1 += curr_offsets.1;
         index_offsets.2 += curr_offsets.2;
@@ -0,0 +1,699 @@
+use crate::model_importer::{ModelImporter, SMALL_VEC_CAP};
+use crate::{Color, CustomData, FloatType, IndexType, TMFMesh, Vector2, Vector3};
+use smallvec::SmallVec;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, BufWriter, Error, Read, Result, Write};
+/// Encoding of data in a PLY file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlyFormat {
+    /// Human-readable text, one element per line.
+    Ascii,
+    /// Binary data, with little endian byte order.
+    BinaryLittleEndian,
+    /// Binary data, with big endian byte order.
+    BinaryBigEndian,
+}
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ScalarType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+impl ScalarType {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => return Err(Error::other(format!("Unknown PLY property type '{name}'"))),
+        })
+    }
+    fn is_float(self) -> bool {
+        matches!(self, Self::F32 | Self::F64)
+    }
+    /// Value corresponding to full intensity of a color channel stored with this type.
+    fn color_max(self) -> f64 {
+        match self {
+            Self::I8 => i8::MAX as f64,
+            Self::U8 => u8::MAX as f64,
+            Self::I16 => i16::MAX as f64,
+            Self::U16 => u16::MAX as f64,
+            Self::I32 => i32::MAX as f64,
+            Self::U32 => u32::MAX as f64,
+            Self::F32 | Self::F64 => 1.0,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug)]
+enum PropertyKind {
+    Scalar(ScalarType),
+    // Type of the item count, and of the items
+    List(ScalarType, ScalarType),
+}
+#[derive(Debug)]
+struct Property {
+    name: String,
+    kind: PropertyKind,
+}
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+impl Element {
+    fn property(&self, names: &[&str]) -> Option<usize> {
+        self.properties
+            .iter()
+            .position(|property| names.contains(&property.name.as_str()))
+    }
+}
+fn read_header<R: BufRead>(src: &mut R) -> Result<(PlyFormat, Vec<Element>)> {
+    let mut line = String::new();
+    src.read_line(&mut line)?;
+    if line.trim_end() != "ply" {
+        return Err(Error::other("Not a PLY file"));
+    }
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    loop {
+        line.clear();
+        if src.read_line(&mut line)? == 0 {
+            return Err(Error::other("Unexpected end of PLY header"));
+        }
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("format") => {
+                format = Some(match words.next() {
+                    Some("ascii") => PlyFormat::Ascii,
+                    Some("binary_little_endian") => PlyFormat::BinaryLittleEndian,
+                    Some("binary_big_endian") => PlyFormat::BinaryBigEndian,
+                    _ => return Err(Error::other("Unknown PLY format")),
+                });
+            }
+            Some("element") => {
+                let (Some(name), Some(count)) = (words.next(), words.next()) else {
+                    return Err(Error::other("Invalid PLY element declaration"));
+                };
+                let count = count.parse().map_err(Error::other)?;
+                elements.push(Element {
+                    name: name.to_owned(),
+                    count,
+                    properties: Vec::new(),
+                });
+            }
+            Some("property") => {
+                let element = elements
+                    .last_mut()
+                    .ok_or_else(|| Error::other("PLY property declared outside of an element"))?;
+                let kind = match words.next() {
+                    Some("list") => {
+                        let (Some(count), Some(item)) = (words.next(), words.next()) else {
+                            return Err(Error::other("Invalid PLY list property"));
+                        };
+                        PropertyKind::List(ScalarType::parse(count)?, ScalarType::parse(item)?)
+                    }
+                    Some(scalar) => PropertyKind::Scalar(ScalarType::parse(scalar)?),
+                    None => return Err(Error::other("Invalid PLY property declaration")),
+                };
+                let name = words
+                    .next()
+                    .ok_or_else(|| Error::other("PLY property has no name"))?;
+                element.properties.push(Property {
+                    name: name.to_owned(),
+                    kind,
+                });
+            }
+            Some("end_header") => break,
+            // Comments, obj_info and empty lines
+            _ => (),
+        }
+    }
+    let format = format.ok_or_else(|| Error::other("PLY header has no format"))?;
+    Ok((format, elements))
+}
+/// Reads values of element properties, in any of the PLY formats.
+struct ValueReader<R: BufRead> {
+    src: R,
+    format: PlyFormat,
+    // Current line of an ascii file, and the position of the next token in it.
+    line: String,
+    pos: usize,
+}
+impl<R: BufRead> ValueReader<R> {
+    fn next_token(&mut self) -> Result<&str> {
+        loop {
+            let rest = &self.line[self.pos..];
+            let trimmed = rest.trim_start();
+            if !trimmed.is_empty() {
+                let start = self.pos + (rest.len() - trimmed.len());
+                let len = trimmed.find(char::is_whitespace).unwrap_or(trimmed.len());
+                self.pos = start + len;
+                return Ok(&self.line[start..self.pos]);
+            }
+            self.line.clear();
+            self.pos = 0;
+            if self.src.read_line(&mut self.line)? == 0 {
+                return Err(Error::other("Unexpected end of PLY data"));
+            }
+        }
+    }
+    fn read_bytes<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut bytes = [0; N];
+        self.src.read_exact(&mut bytes)?;
+        if self.format == PlyFormat::BinaryBigEndian {
+            bytes.reverse();
+        }
+        Ok(bytes)
+    }
+    fn read_scalar(&mut self, scalar: ScalarType) -> Result<f64> {
+        if self.format == PlyFormat::Ascii {
+            return self.next_token()?.parse().map_err(Error::other);
+        }
+        // Bytes are already in little endian order
+        Ok(match scalar {
+            ScalarType::I8 => i8::from_le_bytes(self.read_bytes()?) as f64,
+            ScalarType::U8 => u8::from_le_bytes(self.read_bytes()?) as f64,
+            ScalarType::I16 => i16::from_le_bytes(self.read_bytes()?) as f64,
+            ScalarType::U16 => u16::from_le_bytes(self.read_bytes()?) as f64,
+            ScalarType::I32 => i32::from_le_bytes(self.read_bytes()?) as f64,
+            ScalarType::U32 => u32::from_le_bytes(self.read_bytes()?) as f64,
+            ScalarType::F32 => f32::from_le_bytes(self.read_bytes()?) as f64,
+            ScalarType::F64 => f64::from_le_bytes(self.read_bytes()?),
+        })
+    }
+    /// Reads one element into *scalars*(one value per property) and *list*(items of the last list property).
+    fn read_element(
+        &mut self,
+        element: &Element,
+        scalars: &mut Vec<f64>,
+        list: &mut Vec<f64>,
+    ) -> Result<()> {
+        scalars.clear();
+        for property in &element.properties {
+            match property.kind {
+                PropertyKind::Scalar(scalar) => scalars.push(self.read_scalar(scalar)?),
+                PropertyKind::List(count, item) => {
+                    let count = self.read_scalar(count)? as usize;
+                    list.clear();
+                    for _ in 0..count {
+                        list.push(self.read_scalar(item)?);
+                    }
+                    scalars.push(count as f64);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+/// Where each vertex property read from a PLY file ends up.
+struct VertexLayout {
+    position: [usize; 3],
+    normal: Option<[usize; 3]>,
+    uv: Option<[usize; 2]>,
+    color: Option<([usize; 4], f64)>,
+    // Properties saved as custom data, and if they contain only non-negative integers.
+    custom: Vec<(usize, bool)>,
+}
+impl VertexLayout {
+    fn new(element: &Element) -> Result<Self> {
+        let find_all = |names: &[&[&str]]| -> Option<Vec<usize>> {
+            names.iter().map(|names| element.property(names)).collect()
+        };
+        let position = find_all(&[&["x"], &["y"], &["z"]])
+            .ok_or_else(|| Error::other("PLY vertices have no position"))?;
+        let normal = find_all(&[&["nx"], &["ny"], &["nz"]]);
+        let uv = find_all(&[
+            &["u", "s", "texture_u", "texture_s"],
+            &["v", "t", "texture_v", "texture_t"],
+        ]);
+        let color = find_all(&[
+            &["red", "diffuse_red", "r"],
+            &["green", "diffuse_green", "g"],
+            &["blue", "diffuse_blue", "b"],
+        ]);
+        let alpha = element.property(&["alpha", "diffuse_alpha", "a"]);
+        let mut used: Vec<usize> = position.clone();
+        used.extend(normal.iter().flatten());
+        used.extend(uv.iter().flatten());
+        used.extend(color.iter().flatten());
+        used.extend(alpha);
+        let color = color.map(|color| {
+            let max = match element.properties[color[0]].kind {
+                PropertyKind::Scalar(scalar) => scalar.color_max(),
+                PropertyKind::List(_, _) => 1.0,
+            };
+            // A missing alpha channel is marked with an index past all properties.
+            let alpha = alpha.unwrap_or(usize::MAX);
+            ([color[0], color[1], color[2], alpha], max)
+        });
+        let custom = element
+            .properties
+            .iter()
+            .enumerate()
+            .filter(|(index, property)| {
+                !used.contains(index)
+                    && matches!(property.kind, PropertyKind::Scalar(_))
+                    && !property.name.is_empty()
+                    && property.name.len() <= u8::MAX as usize
+            })
+            .map(|(index, property)| {
+                let is_int =
+                    matches!(property.kind, PropertyKind::Scalar(scalar) if !scalar.is_float());
+                (index, is_int)
+            })
+            .collect();
+        Ok(Self {
+            position: [position[0], position[1], position[2]],
+            normal: normal.map(|n| [n[0], n[1], n[2]]),
+            uv: uv.map(|uv| [uv[0], uv[1]]),
+            color,
+            custom,
+        })
+    }
+}
+fn custom_data(values: &[f64], is_int: bool) -> CustomData {
+    let is_uint = is_int
+        && values
+            .iter()
+            .all(|value| *value >= 0.0 && *value <= IndexType::MAX as f64);
+    if is_uint {
+        let values: Box<[IndexType]> = values.iter().map(|value| *value as IndexType).collect();
+        let max = values.iter().max().copied().unwrap_or(0) as usize;
+        CustomData::CustomIntiger(values, max)
+    } else {
+        let values: Vec<FloatType> = values.iter().map(|value| *value as FloatType).collect();
+        CustomData::from(&values[..])
+    }
+}
+pub fn read_from_ply<R: Read>(reader: &mut R) -> Result<TMFMesh> {
+    let mut src = BufReader::new(reader);
+    let (format, elements) = read_header(&mut src)?;
+    let mut values = ValueReader {
+        src,
+        format,
+        line: String::new(),
+        pos: 0,
+    };
+    let mut oi = ModelImporter::new();
+    let mut vertex_count = 0;
+    let mut colors: Vec<Color> = Vec::new();
+    let mut custom: Vec<(String, bool, Vec<f64>)> = Vec::new();
+    let (mut scalars, mut list) = (Vec::new(), Vec::new());
+    for element in &elements {
+        match element.name.as_str() {
+            "vertex" => {
+                let layout = VertexLayout::new(element)?;
+                custom = layout
+                    .custom
+                    .iter()
+                    .map(|(index, is_int)| {
+                        (
+                            element.properties[*index].name.clone(),
+                            *is_int,
+                            Vec::with_capacity(element.count),
+                        )
+                    })
+                    .collect();
+                for _ in 0..element.count {
+                    values.read_element(element, &mut scalars, &mut list)?;
+                    let get = |index: usize| scalars[index] as FloatType;
+                    let [x, y, z] = layout.position;
+                    oi.push_vertex((get(x), get(y), get(z)));
+                    if let Some([x, y, z]) = layout.normal {
+                        oi.push_normal((get(x), get(y), get(z)));
+                    }
+                    if let Some([u, v]) = layout.uv {
+                        oi.push_uv((get(u), get(v)));
+                    }
+                    if let Some(([r, g, b, a], max)) = layout.color {
+                        let channel = |index: usize| (scalars[index] / max) as FloatType;
+                        let alpha = scalars.get(a).map_or(1.0, |_| channel(a));
+                        colors.push((channel(r), channel(g), channel(b), alpha));
+                    }
+                    for ((index, _), (_, _, data)) in layout.custom.iter().zip(custom.iter_mut()) {
+                        data.push(scalars[*index]);
+                    }
+                }
+                vertex_count = element.count;
+            }
+            "face" => {
+                let indices = element
+                    .property(&["vertex_indices", "vertex_index"])
+                    .ok_or_else(|| Error::other("PLY faces have no vertex indices"))?;
+                let mut face: SmallVec<[IndexType; SMALL_VEC_CAP]> = SmallVec::new();
+                for _ in 0..element.count {
+                    // Only the vertex index list is kept, other properties of faces are skipped.
+                    list.clear();
+                    for (index, property) in element.properties.iter().enumerate() {
+                        match property.kind {
+                            PropertyKind::Scalar(scalar) => {
+                                values.read_scalar(scalar)?;
+                            }
+                            PropertyKind::List(count, item) => {
+                                let count = values.read_scalar(count)? as usize;
+                                for _ in 0..count {
+                                    let value = values.read_scalar(item)?;
+                                    if index == indices {
+                                        list.push(value);
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    face.clear();
+                    for index in &list {
+                        if *index < 0.0 || *index as usize >= vertex_count {
+                            return Err(Error::other("PLY face index out of bounds"));
+                        }
+                        face.push(*index as IndexType);
+                    }
+                    // Normals and uvs are stored per vertex, so they share vertex indices.
+                    oi.push_face(&face, &face, &face).map_err(Error::other)?;
+                }
+            }
+            // Other elements(e.g. edges) are not supported, so they are skipped.
+            _ => {
+                for _ in 0..element.count {
+                    values.read_element(element, &mut scalars, &mut list)?;
+                }
+            }
+        }
+    }
+    let (mut mesh, _) = oi.finish()?;
+    // Vertices not used by any face at the beginning of the array may have been pruned.
+    let pruned = vertex_count - mesh.get_vertices().map_or(0, <[_]>::len);
+    if !colors.is_empty() {
+        colors.drain(..pruned);
+        mesh.set_colors(colors);
+        if let Some(triangles) = mesh.get_vertex_triangles() {
+            let triangles = triangles.to_vec();
+            mesh.set_color_triangles(triangles);
+        }
+    }
+    for (name, is_int, data) in custom {
+        mesh.add_custom_data(custom_data(&data[pruned..], is_int), &name)
+            .map_err(|err| Error::other(format!("{err:?}")))?;
+    }
+    Ok(mesh)
+}
+/// A vertex of a PLY file, made from vertex, normal, uv and color indices of a triangle corner.
+type PlyVertex = [IndexType; 4];
+/// Returns the triangle array *triangles* if it has an index for each corner of the mesh.
+fn corner_indices(triangles: Option<&[IndexType]>, corner_count: usize) -> Option<&[IndexType]> {
+    triangles.filter(|triangles| triangles.len() == corner_count)
+}
+fn lookup<T: Copy>(values: &[T], index: IndexType) -> Result<T> {
+    values
+        .get(index as usize)
+        .copied()
+        .ok_or_else(|| Error::other("Triangle index out of bounds"))
+}
+/// Writes a single *value* as *scalar*, in *format*.
+fn write_scalar<W: Write>(
+    w: &mut W,
+    format: PlyFormat,
+    scalar: ScalarType,
+    value: f64,
+) -> Result<()> {
+    macro_rules! write_bytes {
+        ($value:expr) => {
+            match format {
+                PlyFormat::Ascii => write!(w, "{}", $value),
+                PlyFormat::BinaryLittleEndian => w.write_all(&$value.to_le_bytes()),
+                PlyFormat::BinaryBigEndian => w.write_all(&$value.to_be_bytes()),
+            }
+        };
+    }
+    match scalar {
+        ScalarType::I8 => write_bytes!(value as i8),
+        ScalarType::U8 => write_bytes!(value as u8),
+        ScalarType::I16 => write_bytes!(value as i16),
+        ScalarType::U16 => write_bytes!(value as u16),
+        ScalarType::I32 => write_bytes!(value as i32),
+        ScalarType::U32 => write_bytes!(value as u32),
+        ScalarType::F32 => write_bytes!(value as f32),
+        ScalarType::F64 => write_bytes!(value),
+    }
+}
+pub fn write_ply<W: Write>(mesh: &TMFMesh, w: &mut W, format: PlyFormat) -> Result<()> {
+    let vertices = mesh.get_vertices().unwrap_or(&[]);
+    // Each unique combination of indices becomes a separate PLY vertex.
+    let mut ply_vertices: Vec<PlyVertex> = Vec::new();
+    let mut faces: Vec<u32> = Vec::new();
+    let (normals, uvs, colors) = match mesh.get_vertex_triangles() {
+        Some(vertex_triangles) => {
+            let corner_count = vertex_triangles.len();
+            let normals = mesh
+                .get_normals()
+                .zip(corner_indices(mesh.get_normal_triangles(), corner_count));
+            let uvs = mesh
+                .get_uvs()
+                .zip(corner_indices(mesh.get_uv_triangles(), corner_count));
+            let colors = mesh
+                .get_colors()
+                .zip(corner_indices(mesh.get_color_triangles(), corner_count));
+            let mut unified: HashMap<PlyVertex, u32> = HashMap::new();
+            for corner in 0..corner_count {
+                let key = [
+                    vertex_triangles[corner],
+                    normals.map_or(0, |(_, tris)| tris[corner]),
+                    uvs.map_or(0, |(_, tris)| tris[corner]),
+                    colors.map_or(0, |(_, tris)| tris[corner]),
+                ];
+                let next = u32::try_from(ply_vertices.len())
+                    .map_err(|_| Error::other("Mesh has too many vertices for PLY"))?;
+                let index = *unified.entry(key).or_insert_with(|| {
+                    ply_vertices.push(key);
+                    next
+                });
+                faces.push(index);
+            }
+            (
+                normals.map(|(normals, _)| normals),
+                uvs.map(|(uvs, _)| uvs),
+                colors.map(|(colors, _)| colors),
+            )
+        }
+        None => {
+            // Point clouds store all attributes per vertex.
+            let per_vertex = |len: usize| len == vertices.len();
+            ply_vertices.extend((0..vertices.len()).map(|index| [index as IndexType; 4]));
+            (
+                mesh.get_normals()
+                    .filter(|normals| per_vertex(normals.len())),
+                mesh.get_uvs().filter(|uvs| per_vertex(uvs.len())),
+                mesh.get_colors().filter(|colors| per_vertex(colors.len())),
+            )
+        }
+    };
+    // Only scalar custom data with a value for each vertex can be saved as vertex properties.
+    let custom: Vec<(&str, ScalarType, Vec<f64>)> = mesh
+        .custom_data
+        .iter()
+        .filter_map(|seg| {
+            let name = std::str::from_utf8(&seg.name_bytes()[..seg.name_len() as usize]).ok()?;
+            let (scalar, values): (ScalarType, Vec<f64>) = match seg.custom_data() {
+                CustomData::CustomIntiger(data, _) | CustomData::CustomIndex(data, _) => (
+                    ScalarType::U32,
+                    data.iter().map(|value| *value as f64).collect(),
+                ),
+                CustomData::CustomFloat(data, _) => (
+                    ScalarType::F32,
+                    data.iter().map(|value| *value as f64).collect(),
+                ),
+                _ => return None,
+            };
+            (values.len() == vertices.len()).then_some((name, scalar, values))
+        })
+        .collect();
+    let mut w = BufWriter::new(w);
+    writeln!(w, "ply")?;
+    let format_name = match format {
+        PlyFormat::Ascii => "ascii",
+        PlyFormat::BinaryLittleEndian => "binary_little_endian",
+        PlyFormat::BinaryBigEndian => "binary_big_endian",
+    };
+    writeln!(w, "format {format_name} 1.0")?;
+    writeln!(w, "comment Exported by tmf")?;
+    writeln!(w, "element vertex {}", ply_vertices.len())?;
+    writeln!(w, "property float x\nproperty float y\nproperty float z")?;
+    if normals.is_some() {
+        writeln!(w, "property float nx\nproperty float ny\nproperty float nz")?;
+    }
+    if uvs.is_some() {
+        writeln!(w, "property float u\nproperty float v")?;
+    }
+    if colors.is_some() {
+        writeln!(
+            w,
+            "property uchar red\nproperty uchar green\nproperty uchar blue\nproperty uchar alpha"
+        )?;
+    }
+    for (name, scalar, _) in &custom {
+        let type_name = if *scalar == ScalarType::U32 {
+            "uint"
+        } else {
+            "float"
+        };
+        writeln!(w, "property {type_name} {name}")?;
+    }
+    if mesh.get_vertex_triangles().is_some() {
+        writeln!(w, "element face {}", faces.len() / 3)?;
+        writeln!(w, "property list uchar uint vertex_indices")?;
+    }
+    writeln!(w, "end_header")?;
+    let separator = |w: &mut BufWriter<&mut W>| -> Result<()> {
+        if format == PlyFormat::Ascii {
+            write!(w, " ")?;
+        }
+        Ok(())
+    };
+    let mut row: SmallVec<[(ScalarType, f64); 16]> = SmallVec::new();
+    for [vertex, normal, uv, color] in &ply_vertices {
+        row.clear();
+        let v = lookup(vertices, *vertex)?;
+        row.extend([v.0, v.1, v.2].map(|value| (ScalarType::F32, value as f64)));
+        if let Some(normals) = normals {
+            let n: Vector3 = lookup(normals, *normal)?;
+            row.extend([n.0, n.1, n.2].map(|value| (ScalarType::F32, value as f64)));
+        }
+        if let Some(uvs) = uvs {
+            let uv: Vector2 = lookup(uvs, *uv)?;
+            row.extend([uv.0, uv.1].map(|value| (ScalarType::F32, value as f64)));
+        }
+        if let Some(colors) = colors {
+            let c: Color = lookup(colors, *color)?;
+            row.extend([c.0, c.1, c.2, c.3].map(|value| {
+                let value = (value as f64).clamp(0.0, 1.0) * u8::MAX as f64;
+                (ScalarType::U8, value.round())
+            }));
+        }
+        for (_, scalar, values) in &custom {
+            row.push((*scalar, values[*vertex as usize]));
+        }
+        for (index, (scalar, value)) in row.iter().enumerate() {
+            if index > 0 {
+                separator(&mut w)?;
+            }
+            write_scalar(&mut w, format, *scalar, *value)?;
+        }
+        if format == PlyFormat::Ascii {
+            writeln!(w)?;
+        }
+    }
+    for face in faces.chunks(3) {
+        write_scalar(&mut w, format, ScalarType::U8, 3.0)?;
+        for index in face {
+            separator(&mut w)?;
+            write_scalar(&mut w, format, ScalarType::U32, *index as f64)?;
+        }
+        if format == PlyFormat::Ascii {
+            writeln!(w)?;
+        }
+    }
+    w.flush()
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    fn rw_mesh(mesh: &TMFMesh, format: PlyFormat) -> TMFMesh {
+        let mut out = Vec::new();
+        write_ply(mesh, &mut out, format).unwrap();
+        let read = read_from_ply(&mut &out[..]).unwrap();
+        read.verify().unwrap();
+        read
+    }
+    #[test]
+    #[cfg(feature = "obj_import")]
+    fn rw_susan_ply() {
+        let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+        let (tmf_mesh, _) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+        for format in [
+            PlyFormat::Ascii,
+            PlyFormat::BinaryLittleEndian,
+            PlyFormat::BinaryBigEndian,
+        ] {
+            let read = rw_mesh(&tmf_mesh, format);
+            let expected = tmf_mesh.get_vertex_buffer().unwrap();
+            let read_buffer = read.get_vertex_buffer().unwrap();
+            assert_eq!(expected.len(), read_buffer.len());
+            for (expected, read) in expected.iter().zip(read_buffer.iter()) {
+                assert!(crate::utilis::distance(*expected, *read) < 0.0001);
+            }
+            assert!(read.get_normals().is_some());
+            assert!(read.get_uvs().is_some());
+        }
+    }
+    #[test]
+    fn rw_point_cloud() {
+        let mut mesh = TMFMesh::empty();
+        mesh.set_vertices([(0.0, 0.0, 0.0), (1.0, 0.5, 0.0), (0.0, 1.0, 2.0)]);
+        mesh.set_colors([
+            (1.0, 0.0, 0.0, 1.0),
+            (0.0, 1.0, 0.0, 1.0),
+            (0.0, 0.0, 1.0, 0.0),
+        ]);
+        let intensity: [FloatType; 3] = [0.25, 0.5, 0.75];
+        mesh.add_custom_data(intensity[..].into(), "intensity")
+            .unwrap();
+        let labels: [IndexType; 3] = [3, 1, 2];
+        mesh.add_custom_data(CustomData::CustomIntiger(labels.into(), 3), "label")
+            .unwrap();
+        for format in [PlyFormat::Ascii, PlyFormat::BinaryLittleEndian] {
+            let read = rw_mesh(&mesh, format);
+            assert_eq!(read.get_vertices(), mesh.get_vertices());
+            assert!(read.get_vertex_triangles().is_none());
+            assert_eq!(read.get_colors(), mesh.get_colors());
+            let (read_intensity, _) = read
+                .lookup_custom_data("intensity")
+                .and_then(CustomData::as_float)
+                .unwrap();
+            assert_eq!(read_intensity, intensity);
+            let (read_labels, _) = read
+                .lookup_custom_data("label")
+                .and_then(CustomData::as_intiger)
+                .unwrap();
+            assert_eq!(read_labels, labels);
+        }
+    }
+    #[test]
+    fn read_ascii_quad() {
+        let ply = "ply
+format ascii 1.0
+comment A single quad
+element vertex 4
+property float x
+property float y
+property float z
+property uchar red
+property uchar green
+property uchar blue
+element face 2
+property list uchar int vertex_indices
+end_header
+0 0 0 255 0 0
+1 0 0 255 0 0
+1 1 0 255 0 0
+0 1 0 255 0 0
+3 0 1 2
+3 0 2 3
+";
+        let mesh = read_from_ply(&mut ply.as_bytes()).unwrap();
+        mesh.verify().unwrap();
+        assert_eq!(mesh.get_vertices().unwrap().len(), 4);
+        assert_eq!(mesh.get_vertex_triangles().unwrap(), [0, 1, 2, 0, 2, 3]);
+        assert_eq!(mesh.get_colors().unwrap()[3], (1.0, 0.0, 0.0, 1.0));
+        assert!(mesh.get_normals().is_none());
+    }
+}
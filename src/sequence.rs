@@ -0,0 +1,239 @@
+use crate::read_extension::ReadExt;
+use crate::typed_mesh::{index_from_u64, MeshIndex};
+use crate::unaligned_rw::{UnalignedRWMode, UnalignedReader, UnalignedWriter};
+use crate::vertices::calc_prec;
+use crate::{IndexType, TMFImportError, MAX_SEG_SIZE};
+/// Amount of bits used to store the length of a run.
+const LENGTH_BITS: u8 = 16;
+fn max_run_len(length_bits: u8) -> usize {
+    1_usize << length_bits
+}
+/// Returns the length of the run starting at *pos*, in which each index is bigger by one than the previous one.
+fn sequence_len(data: &[u64], pos: usize, max_len: usize) -> usize {
+    let mut len = 1;
+    while pos + len < data.len() && len < max_len && data[pos + len] == data[pos] + len as u64 {
+        len += 1;
+    }
+    len
+}
+/// Splits *data* into runs. Each run is either a sequence, stored as (start, length), or a literal run of raw values.
+/// Returns (is_sequence, start, length) of each run.
+fn find_runs(data: &[u64], prec: UnalignedRWMode, length_bits: u8) -> Vec<(bool, usize, usize)> {
+    let max_len = max_run_len(length_bits);
+    // A sequence run replaces `len` raw values with a start value, but may split a literal run in two.
+    let run_cost = 1 + length_bits as usize;
+    let sequence_cost = run_cost + prec.bits() as usize;
+    let mut runs = Vec::new();
+    let mut literal_start = 0;
+    let mut pos = 0;
+    let push_literal = |runs: &mut Vec<_>, start: usize, end: usize| {
+        let mut start = start;
+        while start < end {
+            let len = (end - start).min(max_len);
+            runs.push((false, start, len));
+            start += len;
+        }
+    };
+    while pos < data.len() {
+        let len = sequence_len(data, pos, max_len);
+        if len * prec.bits() as usize > sequence_cost + run_cost {
+            push_literal(&mut runs, literal_start, pos);
+            runs.push((true, pos, len));
+            literal_start = pos + len;
+        }
+        pos += len;
+    }
+    push_literal(&mut runs, literal_start, data.len());
+    runs
+}
+/// Saves *triangles* using the Sequence compression. Header is the same as for uncompressed triangles, followed by run length precision.
+pub(crate) fn save_triangles_sequence<W: std::io::Write>(
+    triangles: &[IndexType],
+    max_index: usize,
+    writer: &mut W,
+) -> std::io::Result<()> {
+    let min = *triangles.iter().min().unwrap_or(&0);
+    let precision = calc_prec(max_index);
+    writer.write_all(&precision.to_le_bytes())?;
+    writer.write_all(&(triangles.len() as u64).to_le_bytes())?;
+    writer.write_all(&u64::from(min).to_le_bytes())?;
+    writer.write_all(&[LENGTH_BITS])?;
+    let precision = UnalignedRWMode::precision_bits(precision);
+    let length_prec = UnalignedRWMode::precision_bits(LENGTH_BITS);
+    let data: Box<_> = triangles
+        .iter()
+        .map(|index| u64::from(index - min))
+        .collect();
+    let mut writer = UnalignedWriter::new(writer);
+    for (is_sequence, start, len) in find_runs(&data, precision, LENGTH_BITS) {
+        writer.write_bit(is_sequence)?;
+        writer.write_unaligned(length_prec, (len - 1) as u64)?;
+        if is_sequence {
+            writer.write_unaligned(precision, data[start])?;
+        } else {
+            for value in &data[start..(start + len)] {
+                writer.write_unaligned(precision, *value)?;
+            }
+        }
+    }
+    writer.flush()
+}
+/// Adds *offset* to a decoded *index*, failing if the result does not fit in 64 bits.
+fn offset_index(index: u64, offset: u64) -> Result<u64, TMFImportError> {
+    index.checked_add(offset).ok_or_else(|| {
+        TMFImportError::IO(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Sequence decoded index out of range.",
+        ))
+    })
+}
+pub(crate) fn read_triangles_sequence<I: MeshIndex, R: std::io::Read>(
+    mut src: R,
+    data: &mut Vec<I>,
+    ctx: &crate::tmf_importer::TMFImportContext,
+) -> Result<(), TMFImportError> {
+    let precision = src.read_u8()?;
+    let length = src.read_u64()?;
+    let min = ctx.read_traingle_min(&mut src)?;
+    if length > MAX_SEG_SIZE as u64 {
+        return Err(TMFImportError::SegmentTooLong);
+    }
+    let length_bits = src.read_u8()?;
+    for bits in [precision, length_bits] {
+        if bits >= u64::BITS as u8 {
+            return Err(TMFImportError::InvalidPrecision(bits));
+        }
+    }
+    let precision = UnalignedRWMode::precision_bits(precision);
+    let length_prec = UnalignedRWMode::precision_bits(length_bits);
+    let end = data.len() + length as usize;
+    data.reserve(length as usize);
+    let mut reader = UnalignedReader::new(src);
+    while data.len() < end {
+        let is_sequence = reader.read_bit()?;
+        let len = reader.read_unaligned(length_prec)? as usize + 1;
+        if data.len() + len > end {
+            return Err(TMFImportError::IO(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Sequence run longer than the segment.",
+            )));
+        }
+        if is_sequence {
+            let start = offset_index(reader.read_unaligned(precision)?, min)?;
+            for offset in 0..len as u64 {
+                data.push(index_from_u64(offset_index(start, offset)?)?);
+            }
+        } else {
+            for _ in 0..len {
+                data.push(index_from_u64(offset_index(
+                    reader.read_unaligned(precision)?,
+                    min,
+                )?)?);
+            }
+        }
+    }
+    Ok(())
+}
+#[cfg(test)]
+fn test_ctx() -> crate::tmf_importer::TMFImportContext {
+    crate::tmf_importer::TMFImportContext::default()
+}
+#[cfg(test)]
+fn round_trip(triangles: &[IndexType]) -> usize {
+    let max_index = *triangles.iter().max().unwrap_or(&0) as usize;
+    let mut compressed = Vec::new();
+    save_triangles_sequence(triangles, max_index, &mut compressed).unwrap();
+    // Data already present in the output must be kept.
+    let mut decoded = vec![7];
+    read_triangles_sequence(&compressed[..], &mut decoded, &test_ctx()).unwrap();
+    assert_eq!(&decoded[1..], triangles);
+    compressed.len()
+}
+#[test]
+fn compress_identity() {
+    let triangles: Vec<IndexType> = (5..60_005).collect();
+    let compressed_len = round_trip(&triangles);
+    let mut uncompressed = Vec::new();
+    crate::vertices::save_triangles(&triangles, 60_004, &mut uncompressed).unwrap();
+    assert!(compressed_len * 100 < uncompressed.len());
+}
+#[test]
+fn compress_mixed_runs() {
+    let mut triangles: Vec<IndexType> = (0..100).collect();
+    triangles.extend_from_slice(&[7, 3, 900, 2, 2, 2]);
+    triangles.extend(300..400);
+    triangles.extend_from_slice(&[1, 2]);
+    triangles.extend(1000..1002);
+    round_trip(&triangles);
+    round_trip(&[]);
+    round_trip(&[0, 0, 0]);
+    round_trip(&[3]);
+}
+#[test]
+fn reject_overlong_run() {
+    let mut data = Vec::new();
+    data.push(8);
+    data.extend_from_slice(&4_u64.to_le_bytes());
+    data.extend_from_slice(&0_u64.to_le_bytes());
+    data.push(LENGTH_BITS);
+    {
+        let mut writer = UnalignedWriter::new(&mut data);
+        writer.write_bit(true).unwrap();
+        writer
+            .write_unaligned(UnalignedRWMode::precision_bits(LENGTH_BITS), 9)
+            .unwrap();
+        writer
+            .write_unaligned(UnalignedRWMode::precision_bits(8), 0)
+            .unwrap();
+    }
+    let res = read_triangles_sequence(&data[..], &mut Vec::<IndexType>::new(), &test_ctx());
+    assert!(res.is_err());
+}
+#[test]
+fn reject_overflowing_index() {
+    let mut data = Vec::new();
+    data.push(8);
+    data.extend_from_slice(&2_u64.to_le_bytes());
+    data.extend_from_slice(&u64::MAX.to_le_bytes());
+    data.push(LENGTH_BITS);
+    {
+        let mut writer = UnalignedWriter::new(&mut data);
+        // A sequence starting at 1, above the maximal `min`.
+        writer.write_bit(true).unwrap();
+        writer
+            .write_unaligned(UnalignedRWMode::precision_bits(LENGTH_BITS), 1)
+            .unwrap();
+        writer
+            .write_unaligned(UnalignedRWMode::precision_bits(8), 1)
+            .unwrap();
+    }
+    let res = read_triangles_sequence(&data[..], &mut Vec::<u64>::new(), &test_ctx());
+    assert!(res.is_err());
+}
+#[test]
+fn rw_sequence_mesh() {
+    use crate::{CompressionType, FloatType, SectionType, TMFMesh, TMFPrecisionInfo};
+    let mut mesh = TMFMesh::empty();
+    let vertices: Vec<_> = (0..3000)
+        .map(|i| (i as FloatType, (i % 7) as FloatType, 0.0))
+        .collect();
+    mesh.set_vertices(vertices);
+    // Identity triangles, with a few shuffled ones in the middle.
+    let mut triangles: Vec<IndexType> = (0..3000).collect();
+    triangles.swap(1500, 1700);
+    triangles.swap(1501, 20);
+    mesh.set_vertex_triangles(triangles);
+    let report = mesh.inspect("mesh", &TMFPrecisionInfo::default()).unwrap();
+    let seg = report
+        .segments
+        .iter()
+        .find(|seg| seg.seg_type == SectionType::VertexTriangleSegment)
+        .unwrap();
+    assert_eq!(seg.compression_type, CompressionType::Sequence);
+    assert_eq!(seg.precision_bits.len(), 2);
+    let mut out = Vec::new();
+    mesh.write_tmf_one(&mut out, &TMFPrecisionInfo::default(), "mesh")
+        .unwrap();
+    let (read, _) = TMFMesh::read_tmf_one(&mut &out[..]).unwrap();
+    assert_eq!(read.get_vertex_triangles(), mesh.get_vertex_triangles());
+}
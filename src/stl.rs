@@ -0,0 +1,291 @@
+// Binary STL always stores f32, while `FloatType` is f64 with `double_precision`, so casts are needed only in some configurations.
+#![allow(clippy::unnecessary_cast)]
+use crate::utilis::{cross, normalize, sub_vec3};
+use crate::{FloatType, IndexType, TMFMesh, Vector3};
+use std::collections::HashMap;
+use std::io::{BufWriter, Error, Read, Result, Write};
+/// Encoding of data in a STL file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StlFormat {
+    /// Human-readable text, with each mesh saved as a separate solid.
+    Ascii,
+    /// Compact binary encoding. Can store only one solid, so all meshes are merged into it.
+    Binary,
+}
+/// Size of the header of a binary STL file, and the triangle count following it.
+const BINARY_HEADER_SIZE: usize = 80 + std::mem::size_of::<u32>();
+/// Size of a single facet of a binary STL file: normal, 3 vertices and attribute byte count.
+const BINARY_FACET_SIZE: usize = 12 * std::mem::size_of::<f32>() + std::mem::size_of::<u16>();
+/// Returns the key used to merge equal vectors. Negative zero is converted to positive zero, so both are merged.
+fn vector_key(vector: &Vector3) -> [u64; 3] {
+    [vector.0, vector.1, vector.2].map(|value| ((value + 0.0) as f64).to_bits())
+}
+/// Merges entries of *data* with equal [`vector_key`]s, returning indices of merged entries and the merged data, in order of first appearance.
+/// STL stores no indices, so unlike [`TMFMesh::unify_index_data`], which merges equal index tuples, equal values have to be found by comparing them.
+fn dedup_vectors(data: &[Vector3]) -> (Vec<IndexType>, Vec<Vector3>) {
+    let mut ids: HashMap<[u64; 3], IndexType> = HashMap::with_capacity(data.len());
+    let mut unique = Vec::new();
+    let indices = data
+        .iter()
+        .map(|vector| {
+            *ids.entry(vector_key(vector)).or_insert_with(|| {
+                unique.push(*vector);
+                (unique.len() - 1) as IndexType
+            })
+        })
+        .collect();
+    (indices, unique)
+}
+/// Returns the normal of triangle *a*, *b*, *c*, or `(0,0,0)` for degenerate triangles.
+fn facet_normal(a: Vector3, b: Vector3, c: Vector3) -> Vector3 {
+    let normal = cross(sub_vec3(b, a), sub_vec3(c, a));
+    if normal == (0.0, 0.0, 0.0) {
+        normal
+    } else {
+        normalize(normal)
+    }
+}
+/// Creates a mesh from STL facets, given as a facet normal followed by its 3 corners. Equal vertices and normals are merged.
+fn mesh_from_facets(facets: &[[Vector3; 4]]) -> TMFMesh {
+    let corners: Vec<Vector3> = facets
+        .iter()
+        .flat_map(|facet| [facet[1], facet[2], facet[3]])
+        .collect();
+    // Many exporters write zeroed normals, expecting them to be calculated from vertices.
+    let facet_normals: Vec<Vector3> = facets
+        .iter()
+        .flat_map(|[normal, a, b, c]| {
+            let normal = if *normal == (0.0, 0.0, 0.0) {
+                facet_normal(*a, *b, *c)
+            } else {
+                normalize(*normal)
+            };
+            [normal; 3]
+        })
+        .collect();
+    let (vertex_triangles, vertices) = dedup_vectors(&corners);
+    let (normal_triangles, normals) = dedup_vectors(&facet_normals);
+    let mut mesh = TMFMesh::empty();
+    mesh.set_vertices(vertices);
+    mesh.set_vertex_triangles(vertex_triangles);
+    mesh.set_normals(normals);
+    mesh.set_normal_triangles(normal_triangles);
+    mesh
+}
+fn read_binary(data: &[u8]) -> Result<Vec<(TMFMesh, String)>> {
+    // The header is free-form, but often holds the name of the solid.
+    let header = &data[..80];
+    let name = std::str::from_utf8(header)
+        .map(|header| header.trim_matches(|c: char| c == '\0' || c.is_whitespace()))
+        .unwrap_or("");
+    let name = name.strip_prefix("solid").unwrap_or(name).trim().to_owned();
+    let facets: Vec<[Vector3; 4]> = data[BINARY_HEADER_SIZE..]
+        .chunks_exact(BINARY_FACET_SIZE)
+        .map(|facet| {
+            let float = |index: usize| {
+                let bytes = &facet[(index * 4)..(index * 4 + 4)];
+                f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as FloatType
+            };
+            let vector =
+                |index: usize| (float(index * 3), float(index * 3 + 1), float(index * 3 + 2));
+            [vector(0), vector(1), vector(2), vector(3)]
+        })
+        .collect();
+    Ok(vec![(mesh_from_facets(&facets), name)])
+}
+fn parse_vector<'a, I: Iterator<Item = &'a str>>(words: &mut I) -> Result<Vector3> {
+    let mut next = || -> Result<FloatType> {
+        words
+            .next()
+            .ok_or_else(|| Error::other("Missing STL vector component"))?
+            .parse()
+            .map_err(Error::other)
+    };
+    Ok((next()?, next()?, next()?))
+}
+fn read_ascii(text: &str) -> Result<Vec<(TMFMesh, String)>> {
+    let mut meshes = Vec::new();
+    let mut name = String::new();
+    let mut facets: Vec<[Vector3; 4]> = Vec::new();
+    // Normal and corners of the facet being read.
+    let mut facet: Vec<Vector3> = Vec::with_capacity(4);
+    for line in text.lines() {
+        let mut words = line.split_whitespace();
+        match words.next() {
+            Some("solid") => {
+                name = line.trim_start()["solid".len()..].trim().to_owned();
+                facets.clear();
+            }
+            Some("facet") => {
+                if words.next() != Some("normal") {
+                    return Err(Error::other("Invalid STL facet"));
+                }
+                facet.clear();
+                facet.push(parse_vector(&mut words)?);
+            }
+            Some("vertex") => facet.push(parse_vector(&mut words)?),
+            Some("endfacet") => match facet[..] {
+                [normal, a, b, c] => facets.push([normal, a, b, c]),
+                _ => return Err(Error::other("STL facet must have exactly 3 vertices")),
+            },
+            Some("endsolid") => {
+                meshes.push((mesh_from_facets(&facets), std::mem::take(&mut name)));
+                facets.clear();
+            }
+            // "outer loop", "endloop" and empty lines
+            _ => (),
+        }
+    }
+    Ok(meshes)
+}
+pub fn read_from_stl<R: Read>(reader: &mut R) -> Result<Vec<(TMFMesh, String)>> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+    // Binary files may begin with "solid" too, so their size is checked first.
+    if data.len() >= BINARY_HEADER_SIZE {
+        let count = u32::from_le_bytes([data[80], data[81], data[82], data[83]]) as usize;
+        if count
+            .checked_mul(BINARY_FACET_SIZE)
+            .and_then(|size| size.checked_add(BINARY_HEADER_SIZE))
+            == Some(data.len())
+        {
+            return read_binary(&data);
+        }
+    }
+    match std::str::from_utf8(&data) {
+        Ok(text) if text.trim_start().starts_with("solid") => read_ascii(text),
+        _ => Err(Error::other("Not a STL file")),
+    }
+}
+/// Returns an iterator over facets of *mesh*, each as a facet normal followed by its 3 corners. Facet normals are calculated from the vertices.
+fn mesh_facets(mesh: &TMFMesh) -> Result<impl Iterator<Item = [Vector3; 4]> + '_> {
+    let vertices = mesh.get_vertices().unwrap_or(&[]);
+    let triangles = mesh.get_vertex_triangles().unwrap_or(&[]);
+    if triangles
+        .iter()
+        .any(|index| *index as usize >= vertices.len())
+    {
+        return Err(Error::other("Triangle index out of bounds"));
+    }
+    Ok(triangles.chunks_exact(3).map(|triangle| {
+        let vertex = |index: IndexType| vertices[index as usize];
+        let (a, b, c) = (
+            vertex(triangle[0]),
+            vertex(triangle[1]),
+            vertex(triangle[2]),
+        );
+        [facet_normal(a, b, c), a, b, c]
+    }))
+}
+pub fn write_stl<W: Write, S: std::borrow::Borrow<str>>(
+    meshes: &[(TMFMesh, S)],
+    w: &mut W,
+    format: StlFormat,
+) -> Result<()> {
+    let mut w = BufWriter::new(w);
+    match format {
+        StlFormat::Ascii => {
+            for (mesh, name) in meshes {
+                let name = name.borrow();
+                writeln!(w, "solid {name}")?;
+                for [n, a, b, c] in mesh_facets(mesh)? {
+                    writeln!(w, "facet normal {} {} {}", n.0, n.1, n.2)?;
+                    writeln!(w, "outer loop")?;
+                    for v in [a, b, c] {
+                        writeln!(w, "vertex {} {} {}", v.0, v.1, v.2)?;
+                    }
+                    writeln!(w, "endloop")?;
+                    writeln!(w, "endfacet")?;
+                }
+                writeln!(w, "endsolid {name}")?;
+            }
+        }
+        StlFormat::Binary => {
+            let count: usize = meshes
+                .iter()
+                .map(|(mesh, _)| mesh.get_vertex_triangles().map_or(0, <[_]>::len) / 3)
+                .sum();
+            let count = u32::try_from(count)
+                .map_err(|_| Error::other("Too many triangles for a binary STL file"))?;
+            let mut header = [0; 80];
+            let name = match meshes {
+                [(_, name)] => name.borrow(),
+                _ => "tmf",
+            };
+            let name = format!("solid {name}");
+            let len = name.len().min(header.len());
+            header[..len].copy_from_slice(&name.as_bytes()[..len]);
+            w.write_all(&header)?;
+            w.write_all(&count.to_le_bytes())?;
+            for (mesh, _) in meshes {
+                for facet in mesh_facets(mesh)? {
+                    for v in facet {
+                        for value in [v.0, v.1, v.2] {
+                            w.write_all(&(value as f32).to_le_bytes())?;
+                        }
+                    }
+                    // Attribute byte count, unused
+                    w.write_all(&0_u16.to_le_bytes())?;
+                }
+            }
+        }
+    }
+    w.flush()
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    #[cfg(feature = "obj_import")]
+    fn rw_susan_stl() {
+        let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+        let (tmf_mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+        for format in [StlFormat::Ascii, StlFormat::Binary] {
+            let mut out = Vec::new();
+            write_stl(&[(tmf_mesh.clone(), name.as_str())], &mut out, format).unwrap();
+            let (read, read_name) = read_from_stl(&mut &out[..]).unwrap().remove(0);
+            read.verify().unwrap();
+            assert_eq!(read_name, name);
+            let expected = tmf_mesh.get_vertex_buffer().unwrap();
+            let read_buffer = read.get_vertex_buffer().unwrap();
+            assert_eq!(expected.len(), read_buffer.len());
+            for (expected, read) in expected.iter().zip(read_buffer.iter()) {
+                assert!(crate::utilis::distance(*expected, *read) < 0.0001);
+            }
+            // Vertices shared by triangles are merged.
+            assert_eq!(
+                read.get_vertices().unwrap().len(),
+                tmf_mesh.get_vertices().unwrap().len()
+            );
+            assert!(read.get_normals().unwrap().len() <= read_buffer.len() / 3);
+        }
+    }
+    #[test]
+    fn read_ascii_cube_face() {
+        let stl = "solid face
+facet normal 0 0 0
+outer loop
+vertex 0 0 0
+vertex 1 0 0
+vertex 1 1 0
+endloop
+endfacet
+facet normal 0 0 1
+outer loop
+vertex 0 0 0
+vertex 1 1 0
+vertex 0 1 0
+endloop
+endfacet
+endsolid face
+";
+        let (mesh, name) = read_from_stl(&mut stl.as_bytes()).unwrap().remove(0);
+        mesh.verify().unwrap();
+        assert_eq!(name, "face");
+        assert_eq!(mesh.get_vertices().unwrap().len(), 4);
+        assert_eq!(mesh.get_vertex_triangles().unwrap(), [0, 1, 2, 0, 2, 3]);
+        // The zeroed normal is calculated, and merged with the equal normal of the second facet.
+        assert_eq!(mesh.get_normals().unwrap(), [(0.0, 0.0, 1.0)]);
+        assert_eq!(mesh.get_normal_triangles().unwrap(), [0; 6]);
+    }
+}
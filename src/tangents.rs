@@ -1,4 +1,5 @@
 use crate::read_extension::ReadExt;
+use crate::typed_mesh::{MeshFloat, TangentOf};
 use crate::unaligned_rw::{UnalignedRWMode, UnalignedReader, UnalignedWriter};
 use crate::FloatType;
 use crate::NormalPrecisionMode;
@@ -34,12 +35,8 @@ impl Default for TangentPrecisionMode {
 fn ht_to_bool(ht: HandednessType) -> bool {
     ht.is_sign_negative()
 }
-fn ht_from_bool(src: bool) -> HandednessType {
-    if src {
-        -1.0
-    } else {
-        1.0
-    }
+fn ht_from_bool<F: MeshFloat>(src: bool) -> F {
+    F::from_f64(if src { -1.0 } else { 1.0 })
 }
 /// A representation of a Tangent.
 pub type Tangent = (crate::Vector3, HandednessType);
@@ -51,7 +48,7 @@ fn tangent_to_encoding(
     let handeness = ht_to_bool(tangent.1);
     (normal.0, normal.1, normal.2, normal.3, normal.4, handeness)
 }
-fn tangent_from_encoding(
+fn tangent_from_encoding<F: MeshFloat>(
     asine: u64,
     z: u64,
     sx: bool,
@@ -59,7 +56,7 @@ fn tangent_from_encoding(
     sz: bool,
     handenes: bool,
     prec: TangentPrecisionMode,
-) -> Tangent {
+) -> TangentOf<F> {
     let normal =
         crate::normals::normal_from_encoding(asine, z, sx, sy, sz, prec.normal_precision());
     let handeness = ht_from_bool(handenes);
@@ -91,6 +88,15 @@ pub(crate) fn save_tangents<W: std::io::Write>(
 pub(crate) fn read_tangents<R: std::io::Read>(
     src: &mut R,
 ) -> Result<Box<[Tangent]>, TMFImportError> {
+    let mut tangents = Vec::new();
+    read_tangents_into(src, &mut tangents)?;
+    Ok(tangents.into())
+}
+/// Reads tangents, appending them to *tangents*.
+pub(crate) fn read_tangents_into<F: MeshFloat, R: std::io::Read>(
+    src: &mut R,
+    tangents: &mut Vec<TangentOf<F>>,
+) -> Result<(), TMFImportError> {
     let count = src.read_u64()?;
     if count > crate::MAX_SEG_SIZE as u64 {
         return Err(TMFImportError::SegmentTooLong);
@@ -102,7 +108,7 @@ pub(crate) fn read_tangents<R: std::io::Read>(
     let mut reader = UnalignedReader::new(src);
     let prec = UnalignedRWMode::precision_bits(bits_prec);
     let tan_prec = TangentPrecisionMode::from_bits(bits_prec);
-    let mut tangents = Vec::with_capacity(count as usize);
+    tangents.reserve(count as usize);
     for _ in 0..count {
         let handeness = reader.read_bit()?;
         let sx = reader.read_bit()?;
@@ -114,7 +120,7 @@ pub(crate) fn read_tangents<R: std::io::Read>(
             asine, z, sx, sy, sz, handeness, tan_prec,
         ));
     }
-    Ok(tangents.into())
+    Ok(())
 }
 #[cfg(test)]
 fn test_tangent(tangent: Tangent, prec: TangentPrecisionMode) -> FloatType {
@@ -152,11 +158,11 @@ fn tangent_rw() {
 #[test]
 fn tangents_rw() {
     let prec = TangentPrecisionMode::default();
-    let src_tangents: Vec<_> = (0..100_000).into_iter().map(|_| rand_tangent()).collect();
+    let src_tangents: Vec<_> = (0..100_000).map(|_| rand_tangent()).collect();
     let mut data = Vec::with_capacity(100_000);
     save_tangents(&src_tangents, prec, &mut data).unwrap();
     let target_tangents = read_tangents(&mut (&data as &[u8])).unwrap();
-    for tangent in target_tangents.into_iter() {
+    for tangent in target_tangents.iter() {
         let degree = test_tangent(*tangent, prec);
         assert!(degree < 5.0);
     }
@@ -171,12 +177,7 @@ fn rw_susan_tmf_tans() {
     let (mut tmf_mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
     tmf_mesh.verify().unwrap();
     let len = tmf_mesh.get_vertices().unwrap().len();
-    tmf_mesh.set_tangents(
-        (0..len)
-            .into_iter()
-            .map(|_| rand_tangent())
-            .collect::<Vec<_>>(),
-    );
+    tmf_mesh.set_tangents((0..len).map(|_| rand_tangent()).collect::<Vec<_>>());
     assert!(name == "Suzanne", "Name should be Suzanne but is {name}");
     let prec = TMFPrecisionInfo::default();
     let mut out = Vec::new();
@@ -198,11 +199,7 @@ fn rw_susan_tmf_tan_tris() {
     let (mut tmf_mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
     tmf_mesh.verify().unwrap();
     let len = tmf_mesh.get_vertex_triangles().unwrap().len();
-    tmf_mesh.set_tangent_triangles(
-        (0..(len as crate::IndexType))
-            .into_iter()
-            .collect::<Vec<_>>(),
-    );
+    tmf_mesh.set_tangent_triangles((0..(len as crate::IndexType)).collect::<Vec<_>>());
     assert!(name == "Suzanne", "Name should be Suzanne but is {name}");
     let prec = TMFPrecisionInfo::default();
     let mut out = Vec::new();
@@ -1,45 +1,82 @@
-use crate::read_extension::ReadExt;
-use crate::tmf_exporter::{opt_tris, opt_vertices, EncodeInfo};
+use crate::tmf_exporter::{encode_triangles, opt_tris, opt_vertices, EncodeInfo};
 use crate::tmf_importer::{
-    decode_custom_seg, decode_normal_seg, decode_triangle_seg, decode_uv_seg, decode_vertex_seg,
-    TMFImportContext,
+    decode_custom_seg, decode_material_seg, decode_normal_seg, decode_triangle_seg, decode_uv_seg,
+    decode_vertex_seg, read_compressed_triangles, TMFImportContext,
 };
 
+use crate::typed_mesh::MeshIndex;
 use crate::{
-    CustomDataSegment, IndexType, TMFExportError, TMFImportError, TMFMesh, TMFPrecisionInfo,
-    Tangent, Vector2, Vector3,
+    Color, CustomDataSegment, GroupTriangleRange, IndexType, MaterialDefinition,
+    MaterialTriangleRange, SmoothingGroupRange, TMFExportError, TMFImportError, TMFMesh,
+    TMFPrecisionInfo, Tangent, TriangleEncodingMode, Vector2, Vector3,
 };
 use smallvec::{smallvec, SmallVec};
+/// Type of a segment inside a TMF file. Segments of unknown types are read as [`SectionType::Invalid`], and ignored.
 #[repr(u16)]
-#[derive(Debug, PartialEq, Clone, Copy)]
-pub(crate) enum SectionType {
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum SectionType {
+    /// Unknown or invalid segment, ignored when reading.
     Invalid = 0,
+    /// Vertex positions.
     VertexSegment = 1,
+    /// Indices of vertices forming triangles.
     VertexTriangleSegment = 2,
+    /// Vertex normals.
     NormalSegment = 3,
+    /// Indices of normals forming triangles.
     NormalTriangleSegment = 4,
+    /// UV coordinates.
     UvSegment = 5,
+    /// Indices of UV coordinates forming triangles.
     UvTriangleSegment = 6,
-    //ColorSegment = 7,
-    //ColorTriangleSegment = 8,
+    /// Vertex colors.
+    ColorSegment = 7,
+    /// Indices of vertex colors forming triangles.
+    ColorTriangleSegment = 8,
+    /// Vertex tangents.
     TangentSegment = 9,
+    /// Indices of tangents forming triangles.
     TangentTriangleSegment = 10,
-    //MaterialInfo = 11,
-    //MaterialTriangleRanges = 12,
-    //GroupInfo = 13,
-    //GroupTriangleRanges = 14,
+    /// Names of materials.
+    MaterialInfo = 11,
+    /// Ranges of triangles using each material.
+    MaterialTriangleRanges = 12,
+    /// Names of triangle groups.
+    GroupInfo = 13,
+    /// Ranges of triangles belonging to each group.
+    GroupTriangleRanges = 14,
+    /// Custom named index data.
     CustomIndexSegment = 15,
+    /// Custom named float data.
     CustomFloatSegment = 16,
-    //CustomUnit2Segment = 17,
-    //CustomUnit3Segment = 18,
-    //CustomVector2Segment = 19,
-    //CustomVector3Segment = 20,
-    //CustomVector4Segment = 21,
+    /// Custom named 2D unit vector data.
+    CustomUnit2Segment = 17,
+    /// Custom named 3D unit vector data.
+    CustomUnit3Segment = 18,
+    /// Custom named 2D vector data.
+    CustomVector2Segment = 19,
+    /// Custom named 3D vector data.
+    CustomVector3Segment = 20,
+    /// Custom named 4D vector data.
+    CustomVector4Segment = 21,
+    /// Custom named RGBA color data.
     CustomColorRGBASegment = 23,
+    /// Custom named integer data.
     CustomIntigerSegment = 24,
+    /// Indices shared by multiple kinds of triangles(e.g. both vertex and normal triangles).
     SharedTriangleSegment = 25,
+    /// Names of material libraries(e.g. `.mtl` files) defining materials.
+    MaterialLibraries = 26,
+    /// Ranges of triangles in each smoothing group.
+    SmoothingGroupRanges = 27,
+    /// Definitions of materials, such as ones read from `.mtl` files.
+    MaterialDefinitions = 28,
+    /// Vertex triangles(possibly shared with other kinds of triangles) of an edge-manifold mesh, saved using the connectivity coder.
+    ConnectivityTriangleSegment = 29,
 }
 impl SectionType {
+    /// Checks if segment contains triangle indices.
+    #[must_use]
     pub fn is_triangle(&self) -> bool {
         matches!(
             self,
@@ -47,17 +84,19 @@ impl SectionType {
                 | Self::NormalTriangleSegment
                 | Self::UvTriangleSegment
                 | Self::TangentTriangleSegment
+                | Self::ColorTriangleSegment
                 | Self::SharedTriangleSegment
+                | Self::ConnectivityTriangleSegment
         )
     }
-    pub fn from_u16(input: u16) -> Self {
+    pub(crate) fn from_u16(input: u16) -> Self {
         if input > u8::MAX as u16 {
             Self::Invalid
         } else {
             Self::from_u8(input as u8)
         }
     }
-    pub fn from_u8(input: u8) -> Self {
+    pub(crate) fn from_u8(input: u8) -> Self {
         match input {
             1 => Self::VertexSegment,
             2 => Self::VertexTriangleSegment,
@@ -65,32 +104,54 @@ impl SectionType {
             4 => Self::NormalTriangleSegment,
             5 => Self::UvSegment,
             6 => Self::UvTriangleSegment,
+            7 => Self::ColorSegment,
+            8 => Self::ColorTriangleSegment,
             9 => Self::TangentSegment,
             10 => Self::TangentTriangleSegment,
+            11 => Self::MaterialInfo,
+            12 => Self::MaterialTriangleRanges,
+            13 => Self::GroupInfo,
+            14 => Self::GroupTriangleRanges,
             15 => Self::CustomIndexSegment,
             16 => Self::CustomFloatSegment,
+            17 => Self::CustomUnit2Segment,
+            18 => Self::CustomUnit3Segment,
+            19 => Self::CustomVector2Segment,
+            20 => Self::CustomVector3Segment,
+            21 => Self::CustomVector4Segment,
             23 => Self::CustomColorRGBASegment,
             24 => Self::CustomIntigerSegment,
             25 => Self::SharedTriangleSegment,
+            26 => Self::MaterialLibraries,
+            27 => Self::SmoothingGroupRanges,
+            28 => Self::MaterialDefinitions,
+            29 => Self::ConnectivityTriangleSegment,
             _ => Self::Invalid,
         }
     }
 }
+/// Compression used by a segment inside a TMF file.
 #[repr(u8)]
-#[derive(Debug, PartialEq, Clone, Copy)]
-pub(crate) enum CompressionType {
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub enum CompressionType {
+    /// Data is saved without any additional compression.
     None = 0,
+    /// Data is omitted, and reconstructed by the reader.
     Ommited = 1,
+    /// LZ77 compression working on values stored inside an UBA.
     UnalignedLZZ = 2,
-    /// Represents data that is laid out sequentially and changes by exactly one with each element.
+    /// Indices saved as variable-length differences from the biggest index of the previous triangle.
+    Delta = 3,
+    /// Index data split into runs, with runs in which each index is bigger by one than the previous one saved as just their start and length.
     Sequence = 255,
 }
 impl CompressionType {
-    pub fn from_u8(input: u8) -> Result<Self, TMFImportError> {
+    pub(crate) fn from_u8(input: u8) -> Result<Self, TMFImportError> {
         match input {
             0 => Ok(Self::None),
             1 => Ok(Self::Ommited),
             2 => Ok(Self::UnalignedLZZ),
+            3 => Ok(Self::Delta),
             255 => Ok(Self::Sequence),
             _ => Err(TMFImportError::CompressionTypeUnknown(input)),
         }
@@ -103,11 +164,22 @@ pub(crate) enum DecodedSegment {
     AppendNormal(Box<[Vector3]>),
     AppendUV(Box<[Vector2]>),
     AppendTangent(Box<[Tangent]>),
+    AppendColor(Box<[Color]>),
     AppendTriangleVertex(Box<[IndexType]>),
     AppendTriangleNormal(Box<[IndexType]>),
     AppendTriangleUV(Box<[IndexType]>),
     AppendTriangleTangent(Box<[IndexType]>),
+    AppendTriangleColor(Box<[IndexType]>),
     SharedTriangleSegment(SharedSegmentKind, Box<[IndexType]>),
+    /// Triangle segment of this type saved as `Segment Omitted`: indices are equal to their positions, and there are as many of them as elements in the array they index.
+    OmittedTriangles(SectionType),
+    AppendMaterials(Box<[String]>),
+    AppendMaterialRanges(Box<[MaterialTriangleRange]>),
+    AppendGroups(Box<[String]>),
+    AppendGroupRanges(Box<[GroupTriangleRange]>),
+    AppendMaterialLibraries(Box<[String]>),
+    AppendSmoothingGroupRanges(Box<[SmoothingGroupRange]>),
+    AppendMaterialDefinitions(Box<[MaterialDefinition]>),
     AppendCustom(CustomDataSegment),
 }
 pub(crate) struct EncodedSegment {
@@ -140,19 +212,67 @@ impl EncodedSegment {
         ctx: &TMFImportContext,
         src: &mut R,
     ) -> Result<Self, TMFImportError> {
-        let seg_type = ctx.segment_type_width().read(src)?;
-        let data_length = ctx.segment_length_width().read(src)?;
-        if data_length > crate::MAX_SEG_SIZE {
-            return Err(TMFImportError::SegmentTooLong);
-        }
-        let compresion_type = CompressionType::from_u8(src.read_u8()?)?;
+        let (seg_type, data_length, compresion_type) = ctx.read_segment_header(src)?;
+        let compresion_type = CompressionType::from_u8(compresion_type)?;
+        Self::read_data(seg_type, data_length, compresion_type, src)
+    }
+    /// Reads the data of a segment, whose header was already read.
+    pub(crate) fn read_data<R: std::io::Read>(
+        seg_type: SectionType,
+        data_length: usize,
+        compresion_type: CompressionType,
+        src: &mut R,
+    ) -> Result<Self, TMFImportError> {
         let mut data = vec![0; data_length];
         src.read_exact(&mut data)?;
-        Ok(Self {
-            data: data.into(),
+        Ok(Self::new(seg_type, compresion_type, data.into()))
+    }
+    pub(crate) fn new(
+        seg_type: SectionType,
+        compresion_type: CompressionType,
+        data: Box<[u8]>,
+    ) -> Self {
+        Self {
+            data,
             compresion_type,
             seg_type,
-        })
+        }
+    }
+    pub(crate) fn as_ref(&self) -> SegmentRef<'_> {
+        SegmentRef {
+            seg_type: self.seg_type,
+            compresion_type: self.compresion_type,
+            data: &self.data,
+        }
+    }
+}
+/// Segment whose data is borrowed(e.g. from a memory-mapped file), instead of being copied into an [`EncodedSegment`].
+#[derive(Clone, Copy)]
+pub(crate) struct SegmentRef<'a> {
+    seg_type: SectionType,
+    compresion_type: CompressionType,
+    data: &'a [u8],
+}
+impl<'a> SegmentRef<'a> {
+    pub(crate) fn new(
+        seg_type: SectionType,
+        compresion_type: CompressionType,
+        data: &'a [u8],
+    ) -> Self {
+        Self {
+            seg_type,
+            compresion_type,
+            data,
+        }
+    }
+    pub(crate) fn data(&self) -> &'a [u8] {
+        self.data
+    }
+    pub(crate) fn compresion_type(&self) -> CompressionType {
+        self.compresion_type
+    }
+    pub(crate) fn seg_type(&self) -> SectionType {
+        self.seg_type
     }
 }
 #[derive(Default, Debug, Copy, Clone)]
@@ -163,19 +283,19 @@ impl SharedSegmentKind {
     fn set_vertex(&mut self) {
         self.mask |= 0x1;
     }
-    fn get_vertex(&self) -> bool {
+    pub(crate) fn get_vertex(&self) -> bool {
         self.mask & 0x1 != 0
     }
     fn set_normal(&mut self) {
         self.mask |= 0x2;
     }
-    fn get_normal(&self) -> bool {
+    pub(crate) fn get_normal(&self) -> bool {
         self.mask & 0x2 != 0
     }
     fn set_uv(&mut self) {
         self.mask |= 0x4;
     }
-    fn get_uv(&self) -> bool {
+    pub(crate) fn get_uv(&self) -> bool {
         self.mask & 0x4 != 0
     }
     fn combine(self, other: Self) -> Self {
@@ -186,7 +306,7 @@ impl SharedSegmentKind {
     fn mask(&self) -> u8 {
         self.mask
     }
-    fn from_mask(mask: u8) -> Self {
+    pub(crate) fn from_mask(mask: u8) -> Self {
         Self { mask }
     }
 }
@@ -205,6 +325,46 @@ impl std::fmt::Display for SharedSegmentKind {
         write!(f, "}}")
     }
 }
+/// Reads indices of a `SharedTriangleSegment` or a `ConnectivityTriangleSegment` into *indices*, returning which kinds of triangles they belong to.
+pub(crate) fn read_shared_triangles<I: MeshIndex>(
+    seg: SegmentRef<'_>,
+    indices: &mut Vec<I>,
+    ctx: &TMFImportContext,
+) -> Result<SharedSegmentKind, TMFImportError> {
+    let (mask, data) = seg
+        .data()
+        .split_first()
+        .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::UnexpectedEof))?;
+    if seg.seg_type() == SectionType::ConnectivityTriangleSegment {
+        if seg.compresion_type() != CompressionType::None {
+            return Err(TMFImportError::UnsuportedCompressionType(
+                seg.compresion_type() as u8,
+            ));
+        }
+        crate::connectivity::read_triangles_connectivity(data, indices)?;
+    } else {
+        read_compressed_triangles(data, indices, ctx, seg.compresion_type())?;
+    }
+    Ok(SharedSegmentKind::from_mask(*mask))
+}
+/// Saves vertex triangles(possibly shared with triangles of other *kind*s) using the connectivity coder. Returns `None` if *prec* does not allow it,
+/// *triangles* are not edge-manifold, or the result would not be smaller than *standard_len* bytes.
+fn encode_connectivity(
+    kind: SharedSegmentKind,
+    triangles: &[IndexType],
+    prec: &TMFPrecisionInfo,
+    standard_len: usize,
+) -> std::io::Result<Option<Vec<u8>>> {
+    if prec.triangle_encoding != TriangleEncodingMode::Connectivity
+        || !kind.get_vertex()
+        || !crate::connectivity::is_edge_manifold(triangles)
+    {
+        return Ok(None);
+    }
+    let mut data = vec![kind.mask()];
+    crate::connectivity::save_triangles_connectivity(triangles, &mut data)?;
+    Ok((data.len() < standard_len).then_some(data))
+}
 impl DecodedSegment {
     pub(crate) fn merge(&mut self, other: &mut Self) {
         if other.as_triangles().is_none() {
@@ -283,6 +443,59 @@ impl DecodedSegment {
             _ => todo!("{:?}", self),
         }
     }
+    /// Replaces a triangle segment with identity indices, which can be recreated from the length of the array they index in *mesh*, with [`Self::OmittedTriangles`].
+    pub(crate) fn omit_identity(self, mesh: &TMFMesh) -> Self {
+        let (triangles, seg_type, indexed_len) = match &self {
+            Self::AppendTriangleVertex(triangles) => (
+                triangles,
+                SectionType::VertexTriangleSegment,
+                mesh.get_vertices().map(<[_]>::len),
+            ),
+            Self::AppendTriangleNormal(triangles) => (
+                triangles,
+                SectionType::NormalTriangleSegment,
+                mesh.get_normals().map(<[_]>::len),
+            ),
+            Self::AppendTriangleUV(triangles) => (
+                triangles,
+                SectionType::UvTriangleSegment,
+                mesh.get_uvs().map(<[_]>::len),
+            ),
+            Self::AppendTriangleTangent(triangles) => (
+                triangles,
+                SectionType::TangentTriangleSegment,
+                mesh.get_tangents().map(<[_]>::len),
+            ),
+            Self::AppendTriangleColor(triangles) => (
+                triangles,
+                SectionType::ColorTriangleSegment,
+                mesh.get_colors().map(<[_]>::len),
+            ),
+            _ => return self,
+        };
+        let is_identity = triangles
+            .iter()
+            .enumerate()
+            .all(|(position, index)| *index as usize == position);
+        if !triangles.is_empty() && is_identity && indexed_len == Some(triangles.len()) {
+            Self::OmittedTriangles(seg_type)
+        } else {
+            self
+        }
+    }
+    /// Applies all *segments* to *mesh*. Omitted triangles are applied last, since they depend on the length of arrays set by other segments.
+    pub(crate) fn apply_all<'a, I: IntoIterator<Item = &'a Self>>(segments: I, mesh: &mut TMFMesh) {
+        let mut omitted = Vec::new();
+        for seg in segments {
+            match seg {
+                Self::OmittedTriangles(_) => omitted.push(seg),
+                _ => seg.apply(mesh),
+            }
+        }
+        for seg in omitted {
+            seg.apply(mesh);
+        }
+    }
     pub(crate) fn is_something(&self) -> bool {
         !matches!(self, Self::Nothing)
     }
@@ -321,6 +534,14 @@ impl DecodedSegment {
                 }
                 res
             }
+            Self::AppendTriangleColor(triangles) => {
+                let optimised = opt_tris(&triangles);
+                let mut res = SmallVec::new();
+                for seg in optimised {
+                    res.push(Self::AppendTriangleColor(seg.into()));
+                }
+                res
+            }
             Self::AppendVertex(vertices) => {
                 let optimised = opt_vertices(&vertices);
                 let mut res = SmallVec::new();
@@ -329,11 +550,11 @@ impl DecodedSegment {
                 }
                 res
             }
-            Self::SharedTriangleSegment(kind,triangles) => {
+            Self::SharedTriangleSegment(kind, triangles) => {
                 let optimised = opt_tris(&triangles);
                 let mut res = SmallVec::new();
                 for seg in optimised {
-                    res.push(Self::SharedTriangleSegment(kind,seg.into()));
+                    res.push(Self::SharedTriangleSegment(kind, seg.into()));
                 }
                 res
             }
@@ -346,6 +567,7 @@ impl DecodedSegment {
         ei: &EncodeInfo,
     ) -> Result<EncodedSegment, TMFExportError> {
         let mut data = vec![];
+        let mut compresion_type = CompressionType::None;
         let seg_type = match self {
             Self::AppendVertex(vertices) => {
                 crate::vertices::save_tmf_vertices(
@@ -368,89 +590,215 @@ impl DecodedSegment {
                 crate::uv::save_uvs(&uvs, &mut data, prec.uv_prec)?;
                 SectionType::UvSegment
             }
+            Self::AppendColor(colors) => {
+                crate::color::save_colors(&colors, &mut data, prec.color_prec)?;
+                SectionType::ColorSegment
+            }
             Self::AppendTriangleVertex(triangles) => {
                 let max_index = triangles.iter().max().unwrap_or(&0);
-                crate::vertices::save_triangles(&triangles, (*max_index) as usize, &mut data)?;
-                SectionType::VertexTriangleSegment
+                compresion_type = encode_triangles(
+                    &triangles,
+                    (*max_index) as usize,
+                    prec.triangle_encoding,
+                    &mut data,
+                )?;
+                let mut kind = SharedSegmentKind::default();
+                kind.set_vertex();
+                match encode_connectivity(kind, &triangles, prec, data.len())? {
+                    Some(connectivity) => {
+                        data = connectivity;
+                        compresion_type = CompressionType::None;
+                        SectionType::ConnectivityTriangleSegment
+                    }
+                    None => SectionType::VertexTriangleSegment,
+                }
             }
             Self::AppendTriangleNormal(triangles) => {
                 let max_index = triangles.iter().max().unwrap_or(&0);
-                crate::vertices::save_triangles(&triangles, (*max_index) as usize, &mut data)?;
+                compresion_type = encode_triangles(
+                    &triangles,
+                    (*max_index) as usize,
+                    prec.triangle_encoding,
+                    &mut data,
+                )?;
                 SectionType::NormalTriangleSegment
             }
             Self::AppendTriangleUV(triangles) => {
                 let max_index = triangles.iter().max().unwrap_or(&0);
-                crate::vertices::save_triangles(&triangles, (*max_index) as usize, &mut data)?;
+                compresion_type = encode_triangles(
+                    &triangles,
+                    (*max_index) as usize,
+                    prec.triangle_encoding,
+                    &mut data,
+                )?;
                 SectionType::UvTriangleSegment
             }
             Self::AppendTriangleTangent(triangles) => {
                 let max_index = triangles.iter().max().unwrap_or(&0);
-                crate::vertices::save_triangles(&triangles, (*max_index) as usize, &mut data)?;
+                compresion_type = encode_triangles(
+                    &triangles,
+                    (*max_index) as usize,
+                    prec.triangle_encoding,
+                    &mut data,
+                )?;
                 SectionType::TangentTriangleSegment
             }
-            Self::AppendCustom(custom_data) => custom_data.encode(&mut data)?,
+            Self::AppendTriangleColor(triangles) => {
+                let max_index = triangles.iter().max().unwrap_or(&0);
+                compresion_type = encode_triangles(
+                    &triangles,
+                    (*max_index) as usize,
+                    prec.triangle_encoding,
+                    &mut data,
+                )?;
+                SectionType::ColorTriangleSegment
+            }
+            Self::AppendMaterials(materials) => {
+                crate::material::save_names(&materials, &mut data)?;
+                SectionType::MaterialInfo
+            }
+            Self::AppendMaterialRanges(ranges) => {
+                crate::material::save_triangle_ranges(&ranges, &mut data)?;
+                SectionType::MaterialTriangleRanges
+            }
+            Self::AppendGroups(groups) => {
+                crate::material::save_names(&groups, &mut data)?;
+                SectionType::GroupInfo
+            }
+            Self::AppendGroupRanges(ranges) => {
+                crate::material::save_triangle_ranges(&ranges, &mut data)?;
+                SectionType::GroupTriangleRanges
+            }
+            Self::AppendMaterialLibraries(libraries) => {
+                crate::material::save_names(&libraries, &mut data)?;
+                SectionType::MaterialLibraries
+            }
+            Self::AppendSmoothingGroupRanges(ranges) => {
+                crate::material::save_triangle_ranges(&ranges, &mut data)?;
+                SectionType::SmoothingGroupRanges
+            }
+            Self::AppendMaterialDefinitions(materials) => {
+                crate::material_library::save_definitions(&materials, &mut data)?;
+                SectionType::MaterialDefinitions
+            }
+            Self::AppendCustom(custom_data) => {
+                let (seg_type, custom_compression) = custom_data.encode(&mut data)?;
+                compresion_type = custom_compression;
+                seg_type
+            }
             Self::SharedTriangleSegment(kind, triangles) => {
                 let max_index = triangles.iter().max().unwrap_or(&0);
                 data.push(kind.mask());
-                crate::vertices::save_triangles(&triangles, (*max_index) as usize, &mut data)?;
-                SectionType::SharedTriangleSegment
+                compresion_type = encode_triangles(
+                    &triangles,
+                    (*max_index) as usize,
+                    prec.triangle_encoding,
+                    &mut data,
+                )?;
+                match encode_connectivity(kind, &triangles, prec, data.len())? {
+                    Some(connectivity) => {
+                        data = connectivity;
+                        compresion_type = CompressionType::None;
+                        SectionType::ConnectivityTriangleSegment
+                    }
+                    None => SectionType::SharedTriangleSegment,
+                }
+            }
+            Self::OmittedTriangles(seg_type) => {
+                compresion_type = CompressionType::Ommited;
+                seg_type
             }
             Self::Nothing => SectionType::Invalid,
         };
         Ok(EncodedSegment {
             seg_type,
             data: data.into(),
-            compresion_type: CompressionType::None,
+            compresion_type,
         })
     }
     pub(crate) async fn decode(
         seg: EncodedSegment,
         ctx: &crate::tmf_importer::TMFImportContext,
+    ) -> Result<Self, TMFImportError> {
+        Self::decode_ref(seg.as_ref(), ctx).await
+    }
+    /// Decodes a segment, whose data is borrowed.
+    pub(crate) async fn decode_ref(
+        seg: SegmentRef<'_>,
+        ctx: &crate::tmf_importer::TMFImportContext,
     ) -> Result<Self, TMFImportError> {
         //println!("Decoding segment of type:{:?}!",seg.seg_type());
-        match seg.seg_type {
+        match seg.seg_type() {
             SectionType::Invalid => Ok(Self::Nothing),
             SectionType::VertexSegment => decode_vertex_seg(seg).await,
             SectionType::NormalSegment => decode_normal_seg(seg).await,
             SectionType::UvSegment => decode_uv_seg(seg).await,
             SectionType::TangentSegment => {
                 async {
-                    let tans = crate::tangents::read_tangents(&mut &seg.data[..])?;
+                    let tans = crate::tangents::read_tangents(&mut seg.data())?;
                     Ok(DecodedSegment::AppendTangent(tans))
                 }
                 .await
             }
+            SectionType::ColorSegment => {
+                async {
+                    let colors = crate::color::read_colors(&mut seg.data())?;
+                    Ok(DecodedSegment::AppendColor(colors))
+                }
+                .await
+            }
             SectionType::VertexTriangleSegment
             | SectionType::NormalTriangleSegment
             | SectionType::UvTriangleSegment
-            | SectionType::TangentTriangleSegment => decode_triangle_seg(seg, ctx).await,
+            | SectionType::TangentTriangleSegment
+            | SectionType::ColorTriangleSegment => decode_triangle_seg(seg, ctx).await,
+            SectionType::MaterialInfo
+            | SectionType::MaterialTriangleRanges
+            | SectionType::GroupInfo
+            | SectionType::GroupTriangleRanges
+            | SectionType::MaterialLibraries
+            | SectionType::SmoothingGroupRanges
+            | SectionType::MaterialDefinitions => decode_material_seg(seg).await,
             SectionType::CustomIndexSegment
             | SectionType::CustomIntigerSegment
             | SectionType::CustomFloatSegment
-            | SectionType::CustomColorRGBASegment => decode_custom_seg(seg, ctx).await,
-            SectionType::SharedTriangleSegment => {
-                if seg.data.len() < 1 {
-                    return Err(TMFImportError::IO(std::io::Error::from(
-                        std::io::ErrorKind::UnexpectedEof,
-                    )));
-                }
-                let kind = SharedSegmentKind::from_mask(seg.data[0]);
-                let data: &[u8] = &seg.data()[1..];
+            | SectionType::CustomColorRGBASegment
+            | SectionType::CustomUnit2Segment
+            | SectionType::CustomUnit3Segment
+            | SectionType::CustomVector2Segment
+            | SectionType::CustomVector3Segment
+            | SectionType::CustomVector4Segment => decode_custom_seg(seg, ctx).await,
+            SectionType::SharedTriangleSegment | SectionType::ConnectivityTriangleSegment => {
                 let mut indices = Vec::new();
-                match seg.compresion_type() {
-                    CompressionType::None => {
-                        crate::tmf_importer::read_default_triangles(data, &mut indices, ctx)?
-                    }
-                    _ => {
-                        return Err(TMFImportError::UnsuportedCompressionType(
-                            seg.compresion_type() as u8,
-                        ))
-                    }
-                }
+                let kind = read_shared_triangles(seg, &mut indices, ctx)?;
                 Ok(Self::SharedTriangleSegment(kind, indices.into()))
             }
         }
     }
+    /// Returns the amount of elements(vertices, indices, names, ranges, etc.) stored in this segment, or `None` if it stores nothing.
+    pub(crate) fn element_count(&self) -> Option<usize> {
+        Some(match self {
+            Self::Nothing | Self::OmittedTriangles(_) => return None,
+            Self::AppendVertex(data) | Self::AppendNormal(data) => data.len(),
+            Self::AppendUV(data) => data.len(),
+            Self::AppendTangent(data) => data.len(),
+            Self::AppendColor(data) => data.len(),
+            Self::AppendTriangleVertex(data)
+            | Self::AppendTriangleNormal(data)
+            | Self::AppendTriangleUV(data)
+            | Self::AppendTriangleTangent(data)
+            | Self::AppendTriangleColor(data)
+            | Self::SharedTriangleSegment(_, data) => data.len(),
+            Self::AppendMaterials(names)
+            | Self::AppendGroups(names)
+            | Self::AppendMaterialLibraries(names) => names.len(),
+            Self::AppendMaterialRanges(ranges)
+            | Self::AppendGroupRanges(ranges)
+            | Self::AppendSmoothingGroupRanges(ranges) => ranges.len(),
+            Self::AppendMaterialDefinitions(materials) => materials.len(),
+            Self::AppendCustom(custom) => custom.custom_data().len(),
+        })
+    }
     pub(crate) fn apply(&self, mesh: &mut TMFMesh) {
         match self {
             DecodedSegment::AppendVertex(verts) => mesh.append_vertices(verts),
@@ -472,6 +820,66 @@ impl DecodedSegment {
             DecodedSegment::AppendTriangleTangent(tan_triangles) => {
                 mesh.append_tangent_triangles(tan_triangles)
             }
+            DecodedSegment::AppendColor(colors) => mesh.append_colors(colors),
+            DecodedSegment::AppendTriangleColor(color_triangles) => {
+                mesh.append_color_triangles(color_triangles)
+            }
+            DecodedSegment::AppendMaterials(materials) => mesh
+                .materials
+                .get_or_insert_with(Default::default)
+                .extend_materials(materials),
+            DecodedSegment::AppendMaterialRanges(ranges) => mesh
+                .materials
+                .get_or_insert_with(Default::default)
+                .extend_groups(ranges),
+            DecodedSegment::AppendGroups(groups) => mesh
+                .groups
+                .get_or_insert_with(Default::default)
+                .extend_groups(groups),
+            DecodedSegment::AppendGroupRanges(ranges) => mesh
+                .groups
+                .get_or_insert_with(Default::default)
+                .extend_ranges(ranges),
+            DecodedSegment::AppendMaterialLibraries(libraries) => mesh
+                .materials
+                .get_or_insert_with(Default::default)
+                .extend_libraries(libraries),
+            DecodedSegment::AppendSmoothingGroupRanges(ranges) => mesh
+                .smoothing_groups
+                .get_or_insert_with(Default::default)
+                .extend_from_slice(ranges),
+            DecodedSegment::AppendMaterialDefinitions(materials) => mesh
+                .material_library
+                .get_or_insert_with(Default::default)
+                .extend_materials(materials),
+            DecodedSegment::OmittedTriangles(seg_type) => {
+                let (len, append): (_, fn(&mut TMFMesh, &[IndexType])) = match seg_type {
+                    SectionType::VertexTriangleSegment => (
+                        mesh.get_vertices().map(<[_]>::len),
+                        TMFMesh::append_vertex_triangles,
+                    ),
+                    SectionType::NormalTriangleSegment => (
+                        mesh.get_normals().map(<[_]>::len),
+                        TMFMesh::append_normal_triangles,
+                    ),
+                    SectionType::UvTriangleSegment => {
+                        (mesh.get_uvs().map(<[_]>::len), TMFMesh::append_uv_triangles)
+                    }
+                    SectionType::TangentTriangleSegment => (
+                        mesh.get_tangents().map(<[_]>::len),
+                        TMFMesh::append_tangent_triangles,
+                    ),
+                    SectionType::ColorTriangleSegment => (
+                        mesh.get_colors().map(<[_]>::len),
+                        TMFMesh::append_color_triangles,
+                    ),
+                    _ => return,
+                };
+                let triangles: Box<[IndexType]> = (0..len.unwrap_or(0))
+                    .map(|index| index as IndexType)
+                    .collect();
+                append(mesh, &triangles);
+            }
             DecodedSegment::Nothing => (),
             DecodedSegment::SharedTriangleSegment(kind, indices) => {
                 //println!("kind:{kind}");
@@ -1,12 +1,14 @@
-use crate::tmf::DecodedSegment;
+use crate::executor::TMFExecutor;
+use crate::tmf::{CompressionType, DecodedSegment, EncodedSegment};
 
 use smallvec::{smallvec, SmallVec};
 
 use crate::{
-    FloatType, IndexType, TMFExportError, TMFMesh, TMFPrecisionInfo, Vector3, MIN_TMF_MAJOR,
-    MIN_TMF_MINOR, TMF_MAJOR, TMF_MINOR,
+    FloatType, IndexType, TMFExportError, TMFMesh, TMFPrecisionInfo, TriangleEncodingMode, Vector3,
+    MIN_TMF_MAJOR, MIN_TMF_MINOR, TMF_MAJOR, TMF_MINOR,
 };
 
+#[derive(Clone, Copy)]
 pub(crate) struct EncodeInfo {
     shortest_edge: FloatType,
 }
@@ -20,6 +22,29 @@ impl EncodeInfo {
         self.shortest_edge
     }
 }
+/// Calculates the distance between the two closest, not overlapping, points of a point cloud. Points are sorted along the x axis, so only points closer on this axis than the shortest distance found so far need to be compared.
+fn shortest_point_distance(points: &[Vector3]) -> FloatType {
+    let mut sorted: Vec<Vector3> = points.to_vec();
+    sorted.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+    let mut shortest = FloatType::INFINITY;
+    for (index, point) in sorted.iter().enumerate() {
+        for other in &sorted[(index + 1)..] {
+            if other.0 - point.0 >= shortest {
+                break;
+            }
+            let distance = crate::utilis::distance(*point, *other);
+            if distance > 0.0 {
+                shortest = shortest.min(distance);
+            }
+        }
+    }
+    if shortest.is_finite() {
+        shortest
+    } else {
+        // Less than 2 distinct points
+        0.1
+    }
+}
 fn calc_shortest_edge(
     vertex_triangles: Option<&[IndexType]>,
     vertices: Option<&[Vector3]>,
@@ -27,8 +52,8 @@ fn calc_shortest_edge(
     let shortest_edge = match vertex_triangles {
         Some(vertex_triangles) => {
             if vertex_triangles.is_empty() {
-                //TODO: handle 0 faced mesh as mesh with no faces!
-                return 0.1;
+                // A mesh with no faces is a point cloud.
+                return vertices.map_or(0.1, shortest_point_distance);
             }
             use crate::utilis::distance;
             let vertices = match vertices {
@@ -53,8 +78,7 @@ fn calc_shortest_edge(
             }
             shortest_edge
         }
-        // TODO: Calculate distance between closest points for point cloud
-        None => 0.1,
+        None => vertices.map_or(0.1, shortest_point_distance),
     };
     assert!(
         shortest_edge.is_finite(),
@@ -63,6 +87,41 @@ fn calc_shortest_edge(
     shortest_edge
 }
 
+/// Saves *triangles* using the compression type allowed by *mode* resulting in the smallest segment, and returns the chosen compression type.
+pub(crate) fn encode_triangles<W: std::io::Write>(
+    triangles: &[IndexType],
+    max_index: usize,
+    mode: TriangleEncodingMode,
+    target: &mut W,
+) -> std::io::Result<CompressionType> {
+    let mut uncompressed = Vec::new();
+    crate::vertices::save_triangles(triangles, max_index, &mut uncompressed)?;
+    let mut lzz = Vec::new();
+    crate::lz77::save_triangles_lzz(triangles, max_index, &mut lzz)?;
+    let mut sequence = Vec::new();
+    crate::sequence::save_triangles_sequence(triangles, max_index, &mut sequence)?;
+    let mut candidates = vec![
+        (lzz, CompressionType::UnalignedLZZ),
+        (sequence, CompressionType::Sequence),
+    ];
+    if mode != TriangleEncodingMode::Standard {
+        let mut delta = Vec::new();
+        crate::delta::save_triangles_delta(triangles, max_index, &mut delta)?;
+        candidates.push((delta, CompressionType::Delta));
+    }
+    let (data, compression_type) =
+        candidates
+            .into_iter()
+            .fold((uncompressed, CompressionType::None), |best, candidate| {
+                if candidate.0.len() < best.0.len() {
+                    candidate
+                } else {
+                    best
+                }
+            });
+    target.write_all(&data)?;
+    Ok(compression_type)
+}
 pub(crate) fn write_mesh_name<W: std::io::Write>(w: &mut W, s: &str) -> std::io::Result<()> {
     let bytes = s.as_bytes();
     w.write_all(&(bytes.len() as u16).to_le_bytes())?;
@@ -81,17 +140,103 @@ fn merge_segments(segments: &[DecodedSegment]) -> Box<[DecodedSegment]> {
     }
     new_segments.into()
 }
-async fn write_mesh<W: std::io::Write>(
+/// Splits vertex and triangle segments longer than *max_length* into segments, which can be encoded independently.
+fn split_segment(seg: DecodedSegment, max_length: usize) -> SmallVec<[DecodedSegment; 1]> {
+    // Triangles are never split in the middle.
+    let max_tris_length = (max_length / 3).max(1) * 3;
+    let max_length = max_length.max(1);
+    match seg {
+        DecodedSegment::AppendVertex(vertices) if vertices.len() > max_length => vertices
+            .chunks(max_length)
+            .map(|chunk| DecodedSegment::AppendVertex(chunk.into()))
+            .collect(),
+        DecodedSegment::AppendTriangleVertex(tris) if tris.len() > max_tris_length => tris
+            .chunks(max_tris_length)
+            .map(|chunk| DecodedSegment::AppendTriangleVertex(chunk.into()))
+            .collect(),
+        DecodedSegment::AppendTriangleNormal(tris) if tris.len() > max_tris_length => tris
+            .chunks(max_tris_length)
+            .map(|chunk| DecodedSegment::AppendTriangleNormal(chunk.into()))
+            .collect(),
+        DecodedSegment::AppendTriangleUV(tris) if tris.len() > max_tris_length => tris
+            .chunks(max_tris_length)
+            .map(|chunk| DecodedSegment::AppendTriangleUV(chunk.into()))
+            .collect(),
+        DecodedSegment::AppendTriangleTangent(tris) if tris.len() > max_tris_length => tris
+            .chunks(max_tris_length)
+            .map(|chunk| DecodedSegment::AppendTriangleTangent(chunk.into()))
+            .collect(),
+        DecodedSegment::AppendTriangleColor(tris) if tris.len() > max_tris_length => tris
+            .chunks(max_tris_length)
+            .map(|chunk| DecodedSegment::AppendTriangleColor(chunk.into()))
+            .collect(),
+        DecodedSegment::SharedTriangleSegment(kind, tris) if tris.len() > max_tris_length => tris
+            .chunks(max_tris_length)
+            .map(|chunk| DecodedSegment::SharedTriangleSegment(kind, chunk.into()))
+            .collect(),
+        seg => smallvec![seg],
+    }
+}
+/// Optimizes and encodes *segments* on *executor*. Large segments are split first, so that their parts are encoded in parallel.
+async fn encode_segments_on(
+    segments: Box<[DecodedSegment]>,
+    p_info: TMFPrecisionInfo,
+    ei: EncodeInfo,
+    executor: &dyn TMFExecutor,
+) -> Vec<Result<EncodedSegment, TMFExportError>> {
+    let max_length = executor.max_segment_length();
+    let tasks: Vec<_> = segments
+        .into_vec()
+        .into_iter()
+        .flat_map(|seg| split_segment(seg, max_length))
+        .map(|seg| {
+            crate::executor::run_on(executor, move || {
+                futures::executor::block_on(async move {
+                    let mut encoded = Vec::new();
+                    for seg in seg.optimize().await {
+                        encoded.push(seg.encode(&p_info, &ei).await);
+                    }
+                    encoded
+                })
+            })
+        })
+        .collect();
+    futures::future::join_all(tasks)
+        .await
+        .into_iter()
+        .flatten()
+        .collect()
+}
+pub(crate) async fn write_mesh<W: std::io::Write>(
+    mesh: &TMFMesh,
+    name: &str,
+    target: &mut W,
+    p_info: &TMFPrecisionInfo,
+) -> Result<(), TMFExportError> {
+    let executor = crate::executor::current();
+    write_mesh_on(mesh, name, target, p_info, executor.as_deref()).await
+}
+/// Writes *mesh*, encoding its segments on *executor* if present.
+pub(crate) async fn write_mesh_on<W: std::io::Write>(
     mesh: &TMFMesh,
     name: &str,
     target: &mut W,
     p_info: &TMFPrecisionInfo,
+    executor: Option<&dyn TMFExecutor>,
 ) -> Result<(), TMFExportError> {
     write_mesh_name(target, name)?;
     let ei = EncodeInfo {
         shortest_edge: calc_shortest_edge(mesh.get_vertex_triangles(), mesh.get_vertices()),
     };
-    let tmf_segs = merge_segments(&MeshSegIter::tmf_segs(mesh).collect::<Box<[_]>>());
+    let tmf_segs = merge_segments(
+        &MeshSegIter::tmf_segs(mesh)
+            .map(|seg| seg.omit_identity(mesh))
+            .collect::<Box<[_]>>(),
+    );
+    if let Some(executor) = executor {
+        let encoded = encode_segments_on(tmf_segs, *p_info, ei, executor).await;
+        return write_encoded_segments(encoded, target);
+    }
     let mut new_segs = Vec::with_capacity(32);
     for seg in tmf_segs.iter() {
         let c_segs = seg.clone().optimize().await;
@@ -105,6 +250,12 @@ async fn write_mesh<W: std::io::Write>(
         encoded.push(seg.encode(p_info, &ei));
     }
     let encoded = futures::future::join_all(encoded).await;
+    write_encoded_segments(encoded, target)
+}
+fn write_encoded_segments<W: std::io::Write>(
+    encoded: Vec<Result<EncodedSegment, TMFExportError>>,
+    target: &mut W,
+) -> Result<(), TMFExportError> {
     target.write_all(&(encoded.len() as u16).to_le_bytes())?;
     for seg in encoded {
         seg?.write(target)?;
@@ -123,6 +274,26 @@ pub(crate) async fn write_tmf<W: std::io::Write, S: std::borrow::Borrow<str>>(
     }
     Ok(())
 }
+/// Writes meshes to asynchronous *target*. Each mesh is encoded into a buffer, and written as soon as it is ready.
+pub(crate) async fn write_tmf_async<
+    W: futures::io::AsyncWrite + Unpin,
+    S: std::borrow::Borrow<str>,
+>(
+    meshes_names: &[(TMFMesh, S)],
+    target: &mut W,
+    p_info: &TMFPrecisionInfo,
+) -> Result<(), TMFExportError> {
+    use futures::io::AsyncWriteExt;
+    let mut buffer = Vec::new();
+    write_tmf_header(&mut buffer, meshes_names.len() as u32)?;
+    target.write_all(&buffer).await?;
+    for (mesh, name) in meshes_names {
+        buffer.clear();
+        write_mesh(mesh, name.borrow(), &mut buffer, p_info).await?;
+        target.write_all(&buffer).await?;
+    }
+    Ok(target.flush().await?)
+}
 pub(crate) fn write_tmf_header<W: std::io::Write>(
     w: &mut W,
     mesh_count: u32,
@@ -185,8 +356,54 @@ impl<'a> std::iter::Iterator for MeshSegIter<'a> {
                 Some(tans) => Some(DecodedSegment::AppendTriangleTangent(tans.into())),
                 None => self.next(),
             },
-            9..=usize::MAX => {
-                let index = self.item - 9;
+            9 => match self.mesh.get_colors() {
+                Some(colors) => Some(DecodedSegment::AppendColor(colors.into())),
+                None => self.next(),
+            },
+            10 => match self.mesh.get_color_triangles() {
+                Some(tris) => Some(DecodedSegment::AppendTriangleColor(tris.into())),
+                None => self.next(),
+            },
+            11 => match self.mesh.get_materials() {
+                Some(materials) => Some(DecodedSegment::AppendMaterials(
+                    materials.materials().into(),
+                )),
+                None => self.next(),
+            },
+            12 => match self.mesh.get_materials() {
+                Some(materials) => Some(DecodedSegment::AppendMaterialRanges(
+                    materials.material_groups().into(),
+                )),
+                None => self.next(),
+            },
+            13 => match self.mesh.get_groups() {
+                Some(groups) => Some(DecodedSegment::AppendGroups(groups.groups().into())),
+                None => self.next(),
+            },
+            14 => match self.mesh.get_groups() {
+                Some(groups) => Some(DecodedSegment::AppendGroupRanges(
+                    groups.group_ranges().into(),
+                )),
+                None => self.next(),
+            },
+            15 => match self.mesh.get_materials() {
+                Some(materials) if !materials.libraries().is_empty() => Some(
+                    DecodedSegment::AppendMaterialLibraries(materials.libraries().into()),
+                ),
+                _ => self.next(),
+            },
+            16 => match self.mesh.get_smoothing_groups() {
+                Some(ranges) => Some(DecodedSegment::AppendSmoothingGroupRanges(ranges.into())),
+                None => self.next(),
+            },
+            17 => match self.mesh.get_material_library() {
+                Some(library) if !library.materials().is_empty() => Some(
+                    DecodedSegment::AppendMaterialDefinitions(library.materials().into()),
+                ),
+                _ => self.next(),
+            },
+            18..=usize::MAX => {
+                let index = self.item - 18;
                 let seg = self.mesh.custom_data.get(index)?;
                 Some(DecodedSegment::AppendCustom(seg.clone()))
             }
@@ -1,14 +1,21 @@
+use crate::executor::TMFExecutor;
 use crate::read_extension::ReadExt;
 use crate::tmf::CompressionType;
-use crate::tmf::{DecodedSegment, EncodedSegment, SectionType};
+use crate::tmf::{DecodedSegment, EncodedSegment, SectionType, SegmentRef};
+use crate::typed_mesh::{index_from_u64, MeshIndex};
 use crate::unaligned_rw::UnalignedRWMode;
 use crate::unaligned_rw::UnalignedReader;
 use crate::CustomDataSegment;
-use crate::IndexType;
 use crate::MAX_SEG_SIZE;
 use crate::{TMFImportError, TMFMesh, TMF_MAJOR, TMF_MINOR};
-use futures::future::join_all;
+use futures::future::{join_all, select, BoxFuture, Either, FutureExt};
+use futures::io::{AsyncRead, AsyncReadExt};
+use futures::stream::{FuturesUnordered, StreamExt};
+use std::future::Future;
 use std::io::Read;
+use std::sync::Arc;
+/// Segment being decoded, together with its position in the mesh.
+type PendingSegment = BoxFuture<'static, (usize, Result<DecodedSegment, TMFImportError>)>;
 #[derive(Clone, Copy)]
 pub(crate) enum SegLenWidth {
     U32,
@@ -54,9 +61,11 @@ pub(crate) struct TMFImportContext {
     segment_length_width: SegLenWidth,
     segment_type_width: SegTypeWidth,
     should_read_min_index: bool,
+    header: TMFHeader,
+    // Executor segments are decoded on, if any.
+    executor: Option<Arc<dyn TMFExecutor>>,
 }
-// While some of those fileds are not read yet, they may be relevant in the future.
-#[allow(dead_code)]
+#[derive(Clone, Copy)]
 struct TMFHeader {
     major: u16,
     minor: u16,
@@ -69,12 +78,25 @@ pub(crate) fn read_string<R: Read>(src: &mut R) -> std::io::Result<String> {
     src.read_exact(&mut bytes)?;
     match std::str::from_utf8(&bytes) {
         Ok(string) => Ok(string.to_owned()),
-        Err(_) => Err(std::io::Error::new(
-            std::io::ErrorKind::Other,
-            "Mesh name not valid UTF-8",
-        )),
+        Err(_) => Err(std::io::Error::other("Mesh name not valid UTF-8")),
     }
 }
+/// Size of the file header, followed by the mesh count.
+const HEADER_SIZE: usize = 3 + 4 * std::mem::size_of::<u16>() + std::mem::size_of::<u32>();
+/// Reads exactly *length* bytes from *src*.
+async fn read_bytes_async<R: AsyncRead + Unpin>(
+    src: &mut R,
+    length: usize,
+) -> std::io::Result<Vec<u8>> {
+    let mut bytes = vec![0; length];
+    src.read_exact(&mut bytes).await?;
+    Ok(bytes)
+}
+async fn read_u16_async<R: AsyncRead + Unpin>(src: &mut R) -> std::io::Result<u16> {
+    let mut bytes = [0; std::mem::size_of::<u16>()];
+    src.read_exact(&mut bytes).await?;
+    Ok(u16::from_le_bytes(bytes))
+}
 async fn read_tmf_header<R: Read>(src: &mut R) -> Result<TMFHeader, TMFImportError> {
     let mut magic = [0; 3];
     src.read_exact(&mut magic)?;
@@ -98,13 +120,18 @@ async fn read_tmf_header<R: Read>(src: &mut R) -> Result<TMFHeader, TMFImportErr
         })
     }
 }
-impl TMFImportContext {
-    pub(crate) fn segment_type_width(&self) -> &SegTypeWidth {
-        &self.segment_type_width
-    }
-    pub(crate) fn segment_length_width(&self) -> &SegLenWidth {
-        &self.segment_length_width
+impl Default for TMFImportContext {
+    /// Context for reading data saved by this version of the exporter.
+    fn default() -> Self {
+        Self::init_header(TMFHeader {
+            major: TMF_MAJOR,
+            minor: TMF_MINOR,
+            min_major: crate::MIN_TMF_MAJOR,
+            min_minor: crate::MIN_TMF_MINOR,
+        })
     }
+}
+impl TMFImportContext {
     pub(crate) fn read_traingle_min<R: Read>(&self, src: &mut R) -> std::io::Result<u64> {
         if self.should_read_min_index {
             src.read_u64()
@@ -117,7 +144,100 @@ impl TMFImportContext {
             segment_length_width: SegLenWidth::from_header(&hdr),
             segment_type_width: SegTypeWidth::from_header(&hdr),
             should_read_min_index: (hdr.min_minor > 1),
+            header: hdr,
+            executor: crate::executor::current(),
+        }
+    }
+    /// Returns the version of format used to save the file, as (major, minor).
+    pub(crate) fn version(&self) -> (u16, u16) {
+        (self.header.major, self.header.minor)
+    }
+    /// Returns the minimal version of reader required to read the file, as (major, minor).
+    pub(crate) fn min_version(&self) -> (u16, u16) {
+        (self.header.min_major, self.header.min_minor)
+    }
+    /// Reads the header of a segment, returning its type, length of its data, and raw compression type.
+    pub(crate) fn read_segment_header<R: Read>(
+        &self,
+        src: &mut R,
+    ) -> Result<(SectionType, usize, u8), TMFImportError> {
+        let seg_type = self.segment_type_width.read(src)?;
+        let data_length = self.segment_length_width.read(src)?;
+        if data_length > MAX_SEG_SIZE {
+            return Err(TMFImportError::SegmentTooLong);
         }
+        let compression_type = src.read_u8()?;
+        Ok((seg_type, data_length, compression_type))
+    }
+    /// Size of a segment header: its type, length of its data and compression type.
+    fn segment_header_size(&self) -> usize {
+        let type_size = match self.segment_type_width {
+            SegTypeWidth::U8 => std::mem::size_of::<u8>(),
+            SegTypeWidth::U16 => std::mem::size_of::<u16>(),
+        };
+        let length_size = match self.segment_length_width {
+            SegLenWidth::U32 => std::mem::size_of::<u32>(),
+            SegLenWidth::U64 => std::mem::size_of::<u64>(),
+        };
+        type_size + length_size + std::mem::size_of::<u8>()
+    }
+    /// Starts decoding *encoded*. With an executor or the tokio runtime, decoding begins right away on a separate task, otherwise when the returned future is first polled.
+    fn start_decode(
+        encoded: EncodedSegment,
+        ctx: &Self,
+    ) -> BoxFuture<'static, Result<DecodedSegment, TMFImportError>> {
+        let ctx = ctx.clone();
+        if let Some(executor) = ctx.executor.clone() {
+            return crate::executor::run_on(&*executor, move || {
+                futures::executor::block_on(DecodedSegment::decode(encoded, &ctx))
+            })
+            .boxed();
+        }
+        let decoded = async move { DecodedSegment::decode(encoded, &ctx).await };
+        #[cfg(feature = "tokio_runtime")]
+        let decoded = {
+            // Outside of a tokio runtime, decode on the one used for blocking reads.
+            let handle = tokio::runtime::Handle::try_current()
+                .unwrap_or_else(|_| crate::TOKIO_RUNTIME.handle().clone());
+            let task = handle.spawn(decoded);
+            async move { task.await.unwrap() }
+        };
+        decoded.boxed()
+    }
+    /// Awaits *read*, decoding segments from *pending* while it waits for data. Decoded segments are added to *decoded*, with their position in the mesh.
+    async fn read_while_decoding<T, F: Future<Output = std::io::Result<T>>>(
+        read: F,
+        pending: &mut FuturesUnordered<PendingSegment>,
+        decoded: &mut Vec<(usize, DecodedSegment)>,
+    ) -> Result<T, TMFImportError> {
+        let mut read = std::pin::pin!(read);
+        while !pending.is_empty() {
+            match select(read.as_mut(), pending.next()).await {
+                Either::Left((res, _)) => return Ok(res?),
+                Either::Right((Some((index, segment)), _)) => decoded.push((index, segment?)),
+                Either::Right((None, _)) => break,
+            }
+        }
+        Ok(read.await?)
+    }
+    /// Waits for all segments of a mesh to be decoded, and creates the mesh from them.
+    async fn finish_mesh<F: Future<Output = Result<DecodedSegment, TMFImportError>>>(
+        decoded_segs: Vec<F>,
+    ) -> Result<TMFMesh, TMFImportError> {
+        let mut res = TMFMesh::empty();
+        let decoded = join_all(decoded_segs)
+            .await
+            .into_iter()
+            .collect::<Result<Vec<_>, _>>()?;
+        DecodedSegment::apply_all(&decoded, &mut res);
+        Ok(res)
+    }
+    /// Reads the file header and mesh count, and creates a context for reading meshes that follow.
+    pub(crate) async fn read_header<R: Read>(src: &mut R) -> Result<(Self, u32), TMFImportError> {
+        let header = read_tmf_header(src).await?;
+        let ctx = Self::init_header(header);
+        let mesh_count = src.read_u32()?;
+        Ok((ctx, mesh_count))
     }
     async fn import_mesh<R: Read>(
         &self,
@@ -125,77 +245,94 @@ impl TMFImportContext {
         ctx: &Self,
     ) -> Result<(TMFMesh, String), TMFImportError> {
         let name = read_string(&mut src)?;
+        let mesh = self.import_mesh_segments(src, ctx).await?;
+        Ok((mesh, name))
+    }
+    /// Reads and decodes segments of a mesh, whose name was already read.
+    pub(crate) async fn import_mesh_segments<R: Read>(
+        &self,
+        mut src: R,
+        ctx: &Self,
+    ) -> Result<TMFMesh, TMFImportError> {
         let segment_count = src.read_u16()?;
         //self.segment_length_width.read(&mut src)?;
         let mut decoded_segs = Vec::with_capacity(segment_count as usize);
         for _ in 0..segment_count {
             let encoded = EncodedSegment::read(self, &mut src)?;
-            //println!("encoded_type:{:?}",encoded.seg_type());
-            let ctx = ctx.clone();
-            let decoded = async move { DecodedSegment::decode(encoded, &ctx).await };
-
-            #[cfg(feature = "tokio_runtime")]
-            let decoded = { tokio::task::spawn(decoded) };
-
-            decoded_segs.push(decoded);
+            decoded_segs.push(Self::start_decode(encoded, ctx));
         }
-        let mut res = TMFMesh::empty();
-        let joined = join_all(decoded_segs).await;
-        #[cfg(feature = "tokio_runtime")]
-        let joined = { joined.into_iter().collect::<Result<Vec<_>, _>>().unwrap() };
-        joined
-            .into_iter()
-            .collect::<Result<Vec<_>, _>>()?
-            .iter()
-            .for_each(|seg| {
-                seg.apply(&mut res);
-            });
-        Ok((res, name))
-    }
-    async fn analize_mesh<R: Read>(&self, mut src: R, _ctx: &Self) -> Result<(), TMFImportError> {
-        let name = read_string(&mut src)?;
-        println!("############################\nMesh {name}:");
+        Self::finish_mesh(decoded_segs).await
+    }
+    /// Reads a mesh from asynchronous *src*. Segments already read are decoded while waiting for the data of the following ones.
+    async fn import_mesh_async<R: AsyncRead + Unpin>(
+        &self,
+        src: &mut R,
+    ) -> Result<(TMFMesh, String), TMFImportError> {
+        let name_length = read_u16_async(src).await?;
+        let name = read_bytes_async(src, name_length as usize).await?;
+        let name = String::from_utf8(name)
+            .map_err(|_| std::io::Error::other("Mesh name not valid UTF-8"))?;
+        let segment_count = read_u16_async(src).await?;
+        let mut pending = FuturesUnordered::new();
+        let mut decoded = Vec::with_capacity(segment_count as usize);
+        for index in 0..(segment_count as usize) {
+            let header = read_bytes_async(src, self.segment_header_size());
+            let header = Self::read_while_decoding(header, &mut pending, &mut decoded).await?;
+            let (seg_type, data_length, compression_type) =
+                self.read_segment_header(&mut &header[..])?;
+            let compression_type = CompressionType::from_u8(compression_type)?;
+            let data = read_bytes_async(src, data_length);
+            let data = Self::read_while_decoding(data, &mut pending, &mut decoded).await?;
+            let encoded = EncodedSegment::new(seg_type, compression_type, data.into());
+            let segment = Self::start_decode(encoded, self);
+            pending.push(async move { (index, segment.await) }.boxed());
+        }
+        while let Some((index, segment)) = pending.next().await {
+            decoded.push((index, segment?));
+        }
+        // Segments must be applied in the order they were saved in.
+        decoded.sort_unstable_by_key(|(index, _)| *index);
+        let decoded: Vec<_> = decoded.into_iter().map(|(_, segment)| segment).collect();
+        let mut mesh = TMFMesh::empty();
+        DecodedSegment::apply_all(&decoded, &mut mesh);
+        Ok((mesh, name))
+    }
+    pub(crate) async fn import_async<R: AsyncRead + Unpin>(
+        src: &mut R,
+    ) -> Result<Vec<(TMFMesh, String)>, TMFImportError> {
+        let header = read_bytes_async(src, HEADER_SIZE).await?;
+        let (ctx, mesh_count) = Self::read_header(&mut &header[..]).await?;
+        let mut meshes = Vec::with_capacity((u16::MAX as usize).min(mesh_count as usize));
+        for _ in 0..mesh_count {
+            meshes.push(ctx.import_mesh_async(src).await?);
+        }
+        Ok(meshes)
+    }
+    /// Skips over segments of a mesh, whose name was already read, without decoding them.
+    pub(crate) fn skip_mesh_segments<R: Read>(&self, mut src: R) -> Result<(), TMFImportError> {
         let segment_count = src.read_u16()?;
-        let mut results = [0; 256];
         for _ in 0..segment_count {
-            let encoded = EncodedSegment::read(self, &mut src)?;
-            results[encoded.seg_type() as u8 as usize] += encoded.data().len();
-        }
-        let mut res = Vec::new();
-        let mut total = 0;
-        for (index, result) in results.iter().enumerate() {
-            let byte_len = *result;
-            total += byte_len;
-            if byte_len != 0 {
-                res.push((format!("{:?}", SectionType::from_u8(index as u8)), byte_len));
+            let (_, data_length, _) = self.read_segment_header(&mut src)?;
+            let skipped = std::io::copy(
+                &mut (&mut src).take(data_length as u64),
+                &mut std::io::sink(),
+            )?;
+            if skipped != data_length as u64 {
+                return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
             }
         }
-        res.sort_by(|a, b| a.1.cmp(&b.1));
-        println!("res:{res:?}, total_len:{total}");
         Ok(())
     }
     pub(crate) async fn import<R: Read>(
         mut src: R,
     ) -> Result<Vec<(TMFMesh, String)>, TMFImportError> {
-        let header = read_tmf_header(&mut src).await?;
-        let res = Self::init_header(header);
-        let mesh_count = src.read_u32()?;
+        let (res, mesh_count) = Self::read_header(&mut src).await?;
         let mut meshes = Vec::with_capacity((u16::MAX as usize).min(mesh_count as usize));
         for _ in 0..mesh_count {
             meshes.push(res.import_mesh(&mut src, &res).await?);
         }
         Ok(meshes)
     }
-    #[allow(dead_code)]
-    pub(crate) async fn analize<R: Read>(mut src: R) -> Result<(), TMFImportError> {
-        let header = read_tmf_header(&mut src).await?;
-        let res = Self::init_header(header);
-        let mesh_count = src.read_u32()?;
-        for _ in 0..mesh_count {
-            res.analize_mesh(&mut src, &res).await?;
-        }
-        Ok(())
-    }
 }
 pub(crate) fn import_sync<R: std::io::Read>(
     src: R,
@@ -203,7 +340,7 @@ pub(crate) fn import_sync<R: std::io::Read>(
     runtime_agnostic_block_on!(TMFImportContext::import(src))
 }
 pub(crate) async fn decode_vertex_seg(
-    seg: EncodedSegment,
+    seg: SegmentRef<'_>,
 ) -> Result<DecodedSegment, TMFImportError> {
     if SectionType::VertexSegment == seg.seg_type() {
         let mut data: &[u8] = seg.data();
@@ -214,7 +351,7 @@ pub(crate) async fn decode_vertex_seg(
         panic!("Unreachable condition reached!");
     }
 }
-pub(crate) async fn decode_uv_seg(seg: EncodedSegment) -> Result<DecodedSegment, TMFImportError> {
+pub(crate) async fn decode_uv_seg(seg: SegmentRef<'_>) -> Result<DecodedSegment, TMFImportError> {
     if SectionType::UvSegment == seg.seg_type() {
         let mut data: &[u8] = seg.data();
         Ok(DecodedSegment::AppendUV(crate::uv::read_uvs(&mut data)?))
@@ -223,7 +360,7 @@ pub(crate) async fn decode_uv_seg(seg: EncodedSegment) -> Result<DecodedSegment,
     }
 }
 pub(crate) async fn decode_normal_seg(
-    seg: EncodedSegment,
+    seg: SegmentRef<'_>,
 ) -> Result<DecodedSegment, TMFImportError> {
     if SectionType::NormalSegment == seg.seg_type() {
         let mut data: &[u8] = seg.data();
@@ -234,8 +371,37 @@ pub(crate) async fn decode_normal_seg(
         panic!("Unreachable condition reached!");
     }
 }
+pub(crate) async fn decode_material_seg(
+    seg: SegmentRef<'_>,
+) -> Result<DecodedSegment, TMFImportError> {
+    let mut data: &[u8] = seg.data();
+    match seg.seg_type() {
+        SectionType::MaterialInfo => Ok(DecodedSegment::AppendMaterials(
+            crate::material::read_names(&mut data)?,
+        )),
+        SectionType::MaterialTriangleRanges => Ok(DecodedSegment::AppendMaterialRanges(
+            crate::material::read_triangle_ranges(&mut data)?,
+        )),
+        SectionType::GroupInfo => Ok(DecodedSegment::AppendGroups(crate::material::read_names(
+            &mut data,
+        )?)),
+        SectionType::GroupTriangleRanges => Ok(DecodedSegment::AppendGroupRanges(
+            crate::material::read_triangle_ranges(&mut data)?,
+        )),
+        SectionType::MaterialLibraries => Ok(DecodedSegment::AppendMaterialLibraries(
+            crate::material::read_names(&mut data)?,
+        )),
+        SectionType::SmoothingGroupRanges => Ok(DecodedSegment::AppendSmoothingGroupRanges(
+            crate::material::read_triangle_ranges(&mut data)?,
+        )),
+        SectionType::MaterialDefinitions => Ok(DecodedSegment::AppendMaterialDefinitions(
+            crate::material_library::read_definitions(&mut data)?,
+        )),
+        _ => panic!("Unreachable condition reached!"),
+    }
+}
 pub(crate) async fn decode_custom_seg(
-    seg: EncodedSegment,
+    seg: SegmentRef<'_>,
     ctx: &crate::tmf_importer::TMFImportContext,
 ) -> Result<DecodedSegment, TMFImportError> {
     if matches!(
@@ -244,20 +410,26 @@ pub(crate) async fn decode_custom_seg(
             | SectionType::CustomFloatSegment
             | SectionType::CustomIntigerSegment
             | SectionType::CustomColorRGBASegment
+            | SectionType::CustomUnit2Segment
+            | SectionType::CustomUnit3Segment
+            | SectionType::CustomVector2Segment
+            | SectionType::CustomVector3Segment
+            | SectionType::CustomVector4Segment
     ) {
         let mut data: &[u8] = seg.data();
         Ok(DecodedSegment::AppendCustom(CustomDataSegment::read(
             &mut data,
             seg.seg_type(),
+            seg.compresion_type(),
             ctx,
         )?))
     } else {
         panic!("Unreachable condition reached!");
     }
 }
-pub(crate) fn read_default_triangles<R: std::io::Read>(
+pub(crate) fn read_default_triangles<I: MeshIndex, R: std::io::Read>(
     mut src: R,
-    data: &mut Vec<IndexType>,
+    data: &mut Vec<I>,
     ctx: &crate::tmf_importer::TMFImportContext,
 ) -> Result<(), TMFImportError> {
     let precision_bits = src.read_u8()?;
@@ -272,53 +444,49 @@ pub(crate) fn read_default_triangles<R: std::io::Read>(
     let mut reader = UnalignedReader::new(src);
     if precision_bits == 0 {
         use std::mem::MaybeUninit;
-        buf.fill(MaybeUninit::new(0));
+        buf.fill(MaybeUninit::new(index_from_u64(0)?));
     } else {
         for index in 0..(length as usize) / 2 {
             let (i1, i2) = reader.read2_unaligned(precision)?;
-            buf[index * 2].write((i1 + min) as IndexType);
-            buf[index * 2 + 1].write((i2 + min) as IndexType);
+            buf[index * 2].write(index_from_u64(i1 + min)?);
+            buf[index * 2 + 1].write(index_from_u64(i2 + min)?);
         }
         if length % 2 != 0 {
             let i = reader.read_unaligned(precision)?;
-            buf[(length - 1) as usize].write((i + min) as IndexType);
+            buf[(length - 1) as usize].write(index_from_u64(i + min)?);
         }
     }
-    unsafe { data.set_len(length as usize) }
+    unsafe { data.set_len(data.len() + length as usize) }
     Ok(())
 }
-#[allow(dead_code)]
-fn read_triangle_sequence<R: std::io::Read>(
-    _src: R,
-    _data: &mut Vec<IndexType>,
+/// Reads indices saved with any of the compression types supported for index arrays.
+pub(crate) fn read_compressed_triangles<I: MeshIndex, R: std::io::Read>(
+    src: R,
+    data: &mut Vec<I>,
+    ctx: &crate::tmf_importer::TMFImportContext,
+    compression_type: CompressionType,
 ) -> Result<(), TMFImportError> {
-    todo!();
+    match compression_type {
+        CompressionType::None => read_default_triangles(src, data, ctx),
+        CompressionType::UnalignedLZZ => crate::lz77::read_triangles_lzz(src, data, ctx),
+        CompressionType::Delta => crate::delta::read_triangles_delta(src, data, ctx),
+        CompressionType::Sequence => crate::sequence::read_triangles_sequence(src, data, ctx),
+        CompressionType::Ommited => Err(TMFImportError::UnsuportedCompressionType(
+            compression_type as u8,
+        )),
+    }
 }
 pub(crate) async fn decode_triangle_seg(
-    seg: EncodedSegment,
+    seg: SegmentRef<'_>,
     ctx: &crate::tmf_importer::TMFImportContext,
 ) -> Result<DecodedSegment, TMFImportError> {
     if seg.seg_type().is_triangle() {
+        if seg.compresion_type() == CompressionType::Ommited {
+            return Ok(DecodedSegment::OmittedTriangles(seg.seg_type()));
+        }
         let data: &[u8] = seg.data();
         let mut indices = Vec::new();
-        match seg.compresion_type() {
-            CompressionType::None => read_default_triangles(data, &mut indices, ctx)?,
-            CompressionType::Sequence => {
-                return Err(TMFImportError::UnsuportedCompressionType(
-                    CompressionType::Ommited as u8,
-                ))
-            } //read_triangle_sequence(data, &mut indices)?,
-            CompressionType::Ommited => {
-                return Err(TMFImportError::UnsuportedCompressionType(
-                    CompressionType::Ommited as u8,
-                ))
-            }
-            CompressionType::UnalignedLZZ => {
-                return Err(TMFImportError::UnsuportedCompressionType(
-                    CompressionType::UnalignedLZZ as u8,
-                ))
-            }
-        };
+        read_compressed_triangles(data, &mut indices, ctx, seg.compresion_type())?;
         Ok(match seg.seg_type() {
             SectionType::VertexTriangleSegment => {
                 DecodedSegment::AppendTriangleVertex(indices.into())
@@ -330,6 +498,9 @@ pub(crate) async fn decode_triangle_seg(
             SectionType::TangentTriangleSegment => {
                 DecodedSegment::AppendTriangleTangent(indices.into())
             }
+            SectionType::ColorTriangleSegment => {
+                DecodedSegment::AppendTriangleColor(indices.into())
+            }
             _ => panic!("Unsupported section type {:?}", seg.seg_type()),
         })
     } else {
@@ -358,6 +529,205 @@ fn test() {
 }
 #[cfg(test)]
 #[test]
+#[cfg(feature = "obj_import")]
+fn rw_async() {
+    use crate::TMFPrecisionInfo;
+    init_test_env();
+    let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+    let (tmf_mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+    let prec = TMFPrecisionInfo::default();
+    let meshes = [(tmf_mesh.clone(), name.clone()), (tmf_mesh, name)];
+    let mut out = Vec::new();
+    runtime_agnostic_block_on!(TMFMesh::write_tmf_async(&meshes, &mut out, &prec)).unwrap();
+    let mut sync_out = Vec::new();
+    TMFMesh::write_tmf(&meshes, &mut sync_out, &prec).unwrap();
+    assert_eq!(out, sync_out);
+    let imported =
+        runtime_agnostic_block_on!(TMFMesh::read_tmf_from_async_reader(&mut &out[..])).unwrap();
+    let expected = TMFMesh::read_tmf(&mut &out[..]).unwrap();
+    assert_eq!(imported.len(), 2);
+    for ((mesh, name), (expected_mesh, expected_name)) in imported.iter().zip(expected.iter()) {
+        assert_eq!(name, expected_name);
+        assert_eq!(mesh.get_vertices(), expected_mesh.get_vertices());
+        assert_eq!(
+            mesh.get_vertex_triangles(),
+            expected_mesh.get_vertex_triangles()
+        );
+        assert_eq!(mesh.get_normals(), expected_mesh.get_normals());
+        assert_eq!(mesh.get_uvs(), expected_mesh.get_uvs());
+    }
+    // Truncated input must be reported, not decoded.
+    assert!(
+        runtime_agnostic_block_on!(TMFMesh::read_tmf_from_async_reader(
+            &mut &out[..out.len() / 2]
+        ))
+        .is_err()
+    );
+}
+#[cfg(test)]
+#[test]
+#[cfg(feature = "obj_import")]
+fn read_async_trickle() {
+    use crate::TMFPrecisionInfo;
+    use std::pin::Pin;
+    use std::task::{Context, Poll};
+    // Returns a few bytes at a time, and is not ready on every other poll, so segments are decoded while waiting for data.
+    struct Trickle<'a> {
+        data: &'a [u8],
+        ready: bool,
+    }
+    impl AsyncRead for Trickle<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            cx: &mut Context<'_>,
+            buf: &mut [u8],
+        ) -> Poll<std::io::Result<usize>> {
+            self.ready = !self.ready;
+            if !self.ready {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            let len = buf.len().min(self.data.len()).min(61);
+            buf[..len].copy_from_slice(&self.data[..len]);
+            self.data = &self.data[len..];
+            Poll::Ready(Ok(len))
+        }
+    }
+    let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+    let (tmf_mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+    let mut out = Vec::new();
+    tmf_mesh
+        .write_tmf_one(&mut out, &TMFPrecisionInfo::default(), name)
+        .unwrap();
+    let mut src = Trickle {
+        data: &out,
+        ready: false,
+    };
+    // Not inside of a tokio runtime, even with the `tokio_runtime` feature.
+    let (mesh, _) =
+        futures::executor::block_on(TMFMesh::read_tmf_one_from_async_reader(&mut src)).unwrap();
+    let (expected, _) = TMFMesh::read_tmf_one(&mut &out[..]).unwrap();
+    assert_eq!(mesh.get_vertices(), expected.get_vertices());
+    assert_eq!(mesh.get_normals(), expected.get_normals());
+    assert_eq!(mesh.get_uvs(), expected.get_uvs());
+    assert_eq!(mesh.get_vertex_triangles(), tmf_mesh.get_vertex_triangles());
+    assert_eq!(mesh.get_normal_triangles(), tmf_mesh.get_normal_triangles());
+    assert_eq!(mesh.get_uv_triangles(), tmf_mesh.get_uv_triangles());
+}
+#[cfg(test)]
+#[test]
+#[cfg(feature = "obj_import")]
+fn rw_executor() {
+    use crate::executor::{TMFExecutor, TMFTask};
+    use crate::TMFPrecisionInfo;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    struct ThreadExecutor(AtomicUsize);
+    impl TMFExecutor for ThreadExecutor {
+        fn spawn(&self, task: TMFTask) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            std::thread::spawn(task);
+        }
+        fn max_segment_length(&self) -> usize {
+            300
+        }
+    }
+    init_test_env();
+    let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+    let (tmf_mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+    let executor = Arc::new(ThreadExecutor(AtomicUsize::new(0)));
+    let prec = TMFPrecisionInfo::default();
+    let mut out = Vec::new();
+    crate::tmf_exporter::write_tmf_header(&mut out, 1).unwrap();
+    runtime_agnostic_block_on!(crate::tmf_exporter::write_mesh_on(
+        &tmf_mesh,
+        &name,
+        &mut out,
+        &prec,
+        Some(&*executor)
+    ))
+    .unwrap();
+    let encode_tasks = executor.0.load(Ordering::Relaxed);
+    // Vertices and triangles are split into many segments.
+    assert!(encode_tasks > 10, "Only {encode_tasks} encoding tasks run");
+    let mut src = &out[..];
+    let (mut ctx, mesh_count) =
+        runtime_agnostic_block_on!(TMFImportContext::read_header(&mut src)).unwrap();
+    assert_eq!(mesh_count, 1);
+    ctx.executor = Some(executor.clone());
+    let (mesh, read_name) = runtime_agnostic_block_on!(ctx.import_mesh(&mut src, &ctx)).unwrap();
+    assert!(executor.0.load(Ordering::Relaxed) > encode_tasks);
+    let (expected, _) = TMFMesh::read_tmf_one(&mut &out[..]).unwrap();
+    assert_eq!(read_name, name);
+    assert_eq!(mesh.get_vertices(), expected.get_vertices());
+    assert_eq!(mesh.get_vertex_triangles(), tmf_mesh.get_vertex_triangles());
+    assert_eq!(mesh.get_normal_triangles(), tmf_mesh.get_normal_triangles());
+    assert_eq!(mesh.get_uv_triangles(), tmf_mesh.get_uv_triangles());
+    assert_eq!(
+        mesh.get_vertices().unwrap().len(),
+        tmf_mesh.get_vertices().unwrap().len()
+    );
+}
+#[cfg(test)]
+#[test]
+fn rw_omitted() {
+    use crate::{TMFPrecisionInfo, TMFSliceReader};
+    let mut mesh = TMFMesh::empty();
+    mesh.set_vertices([
+        (0.0, 0.0, 0.0),
+        (1.0, 0.0, 0.0),
+        (1.0, 1.0, 0.0),
+        (0.0, 1.0, 0.0),
+    ]);
+    mesh.set_vertex_triangles([0, 1, 2, 0, 2, 3]);
+    mesh.set_normals([(0.0, 0.0, 1.0); 6]);
+    mesh.set_normal_triangles([0, 1, 2, 3, 4, 5]);
+    mesh.set_uvs([
+        (0.0, 0.0),
+        (1.0, 0.0),
+        (1.0, 1.0),
+        (0.0, 0.0),
+        (1.0, 1.0),
+        (0.0, 1.0),
+    ]);
+    mesh.set_uv_triangles([0, 1, 2, 3, 4, 5]);
+    mesh.set_tangents([((1.0, 0.0, 0.0), 1.0); 6]);
+    mesh.set_tangent_triangles([0, 1, 2, 3, 4, 5]);
+    let prec = TMFPrecisionInfo::default();
+    let report = mesh.inspect("quad", &prec).unwrap();
+    let omitted: Vec<_> = report
+        .segments
+        .iter()
+        .filter(|seg| seg.compression_type == CompressionType::Ommited)
+        .map(|seg| {
+            assert_eq!(seg.byte_len, 0);
+            seg.seg_type
+        })
+        .collect();
+    assert_eq!(omitted.len(), 3);
+    for seg_type in [
+        SectionType::NormalTriangleSegment,
+        SectionType::UvTriangleSegment,
+        SectionType::TangentTriangleSegment,
+    ] {
+        assert!(omitted.contains(&seg_type), "{seg_type:?} not omitted");
+    }
+    let mut out = Vec::new();
+    mesh.write_tmf_one(&mut out, &prec, "quad").unwrap();
+    let (read, _) = TMFMesh::read_tmf_one(&mut &out[..]).unwrap();
+    let (sliced, _) = TMFSliceReader::new(&out)
+        .unwrap()
+        .read_next()
+        .unwrap()
+        .unwrap();
+    for read in [read, sliced] {
+        assert_eq!(read.get_vertex_triangles(), mesh.get_vertex_triangles());
+        assert_eq!(read.get_normal_triangles(), mesh.get_normal_triangles());
+        assert_eq!(read.get_uv_triangles(), mesh.get_uv_triangles());
+        assert_eq!(read.get_tangent_triangles(), mesh.get_tangent_triangles());
+    }
+}
+#[cfg(test)]
+#[test]
 fn test_triangles_opt() {
     use crate::tmf_exporter::EncodeInfo;
     use crate::TMFPrecisionInfo;
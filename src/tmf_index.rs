@@ -0,0 +1,291 @@
+use crate::read_extension::ReadExt;
+use crate::tmf::{CompressionType, DecodedSegment, EncodedSegment, SectionType};
+use crate::tmf_exporter::write_mesh_name;
+use crate::tmf_importer::{read_string, TMFImportContext};
+use crate::{TMFImportError, TMFMesh};
+use std::io::{Read, Seek, SeekFrom, Write};
+/// Magic bytes marking the end of a mesh index footer.
+const INDEX_MAGIC: &[u8; 4] = b"TMFI";
+/// Size of the part of the footer at the very end of the file: offset of the index, followed by magic bytes.
+const TRAILER_SIZE: u64 = (std::mem::size_of::<u64>() + INDEX_MAGIC.len()) as u64;
+/// Writes a mesh index footer, containing names of meshes and their offsets from the beginning of the file header at *header_pos*.
+pub(crate) fn write_index<W: Write + Seek>(
+    target: &mut W,
+    meshes: &[(String, u64)],
+    header_pos: u64,
+) -> std::io::Result<()> {
+    let index_start = target.stream_position()? - header_pos;
+    target.write_all(&(meshes.len() as u32).to_le_bytes())?;
+    for (name, offset) in meshes {
+        write_mesh_name(target, name)?;
+        target.write_all(&offset.to_le_bytes())?;
+    }
+    target.write_all(&index_start.to_le_bytes())?;
+    target.write_all(INDEX_MAGIC)
+}
+/// Names and byte offsets of all meshes inside a TMF file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TMFIndex {
+    meshes: Vec<(String, u64)>,
+    stored: bool,
+}
+impl TMFIndex {
+    /// Returns the names of all meshes, in the order they are stored in.
+    pub fn mesh_names(&self) -> impl Iterator<Item = &str> {
+        self.meshes.iter().map(|(name, _)| name.as_str())
+    }
+    /// Returns the amount of meshes in the file.
+    #[must_use]
+    pub fn mesh_count(&self) -> usize {
+        self.meshes.len()
+    }
+    /// Returns the offset of the first mesh named *name*, counted in bytes from the beginning of the file header.
+    #[must_use]
+    pub fn mesh_offset(&self, name: &str) -> Option<u64> {
+        self.meshes
+            .iter()
+            .find(|(mesh_name, _)| mesh_name == name)
+            .map(|(_, offset)| *offset)
+    }
+    /// Checks if this index was read from an index footer stored in the file. If `false`, the file had no index footer and the index was built by scanning the whole file.
+    #[must_use]
+    pub fn is_stored(&self) -> bool {
+        self.stored
+    }
+}
+/// A reader of TMF files allowing random access to meshes and their segments.
+/// If the file contains a mesh index footer(written by [`crate::TMFWriter::new_indexed`]) it is used to find meshes instantly. Otherwise, the index is built by skipping over all segments once, without decoding them.
+/// # Example
+/// ```
+/// # use tmf::{TMFMesh,TMFPrecisionInfo,TMFSeekReader,TMFWriter};
+/// # let mut writer = TMFWriter::new_indexed(std::io::Cursor::new(Vec::new())).unwrap();
+/// # writer.write_mesh(&TMFMesh::empty(), "a", &TMFPrecisionInfo::default()).unwrap();
+/// # writer.write_mesh(&TMFMesh::empty(), "b", &TMFPrecisionInfo::default()).unwrap();
+/// # let mut file = writer.finish().unwrap();
+/// # file.set_position(0);
+/// let mut reader = TMFSeekReader::new(file).expect("Could not read TMF file!");
+/// assert!(reader.index().is_stored());
+/// let mesh = reader.read_mesh("b").expect("Could not read mesh!").expect("No mesh named b!");
+/// ```
+pub struct TMFSeekReader<R: Read + Seek> {
+    src: R,
+    ctx: TMFImportContext,
+    header_pos: u64,
+    index: TMFIndex,
+}
+impl<R: Read + Seek> TMFSeekReader<R> {
+    /// Creates a new [`TMFSeekReader`], reading a TMF file starting at the current position of *src*.
+    /// # Errors
+    /// Returns: an IO error if it occurs, `NotTMFFile` if not tmf file, `NewerVersionRequired` if a newer importer is required for importing the file, and other errors for malformed tmf files.
+    pub fn new(mut src: R) -> Result<Self, TMFImportError> {
+        let header_pos = src.stream_position()?;
+        let (ctx, mesh_count) =
+            runtime_agnostic_block_on!(TMFImportContext::read_header(&mut src))?;
+        let data_start = src.stream_position()? - header_pos;
+        let mut res = Self {
+            src,
+            ctx,
+            header_pos,
+            index: TMFIndex::default(),
+        };
+        res.index = match res.read_stored_index(mesh_count, data_start) {
+            Ok(Some(index)) => index,
+            _ => res.scan_index(mesh_count, data_start)?,
+        };
+        Ok(res)
+    }
+    /// Tries to read the index footer. Returns `None` if the file has no valid index footer.
+    fn read_stored_index(
+        &mut self,
+        mesh_count: u32,
+        data_start: u64,
+    ) -> Result<Option<TMFIndex>, TMFImportError> {
+        let end = self.src.seek(SeekFrom::End(0))?;
+        if end < self.header_pos + data_start + TRAILER_SIZE {
+            return Ok(None);
+        }
+        self.src.seek(SeekFrom::End(-(TRAILER_SIZE as i64)))?;
+        let index_start = self.src.read_u64()?;
+        let mut magic = [0; INDEX_MAGIC.len()];
+        self.src.read_exact(&mut magic)?;
+        if magic != *INDEX_MAGIC
+            || index_start < data_start
+            || index_start > end - TRAILER_SIZE - self.header_pos
+        {
+            return Ok(None);
+        }
+        self.src
+            .seek(SeekFrom::Start(self.header_pos + index_start))?;
+        if self.src.read_u32()? != mesh_count {
+            return Ok(None);
+        }
+        let mut meshes = Vec::with_capacity((u16::MAX as usize).min(mesh_count as usize));
+        for _ in 0..mesh_count {
+            let name = read_string(&mut self.src)?;
+            let offset = self.src.read_u64()?;
+            if offset < data_start || offset >= index_start {
+                return Ok(None);
+            }
+            meshes.push((name, offset));
+        }
+        Ok(Some(TMFIndex {
+            meshes,
+            stored: true,
+        }))
+    }
+    /// Builds an index by skipping over all segments in the file.
+    fn scan_index(&mut self, mesh_count: u32, data_start: u64) -> Result<TMFIndex, TMFImportError> {
+        self.src
+            .seek(SeekFrom::Start(self.header_pos + data_start))?;
+        let mut meshes = Vec::with_capacity((u16::MAX as usize).min(mesh_count as usize));
+        for _ in 0..mesh_count {
+            let offset = self.src.stream_position()? - self.header_pos;
+            let name = read_string(&mut self.src)?;
+            let segment_count = self.src.read_u16()?;
+            for _ in 0..segment_count {
+                let (_, data_length, _) = self.ctx.read_segment_header(&mut self.src)?;
+                self.src.seek(SeekFrom::Current(data_length as i64))?;
+            }
+            meshes.push((name, offset));
+        }
+        Ok(TMFIndex {
+            meshes,
+            stored: false,
+        })
+    }
+    /// Returns the index of meshes in this file.
+    #[must_use]
+    pub fn index(&self) -> &TMFIndex {
+        &self.index
+    }
+    /// Seeks to the mesh named *name*, skipping its name. Returns `false` if no such mesh exists.
+    fn seek_mesh(&mut self, name: &str) -> Result<bool, TMFImportError> {
+        let offset = match self.index.mesh_offset(name) {
+            Some(offset) => offset,
+            None => return Ok(false),
+        };
+        self.src.seek(SeekFrom::Start(self.header_pos + offset))?;
+        read_string(&mut self.src)?;
+        Ok(true)
+    }
+    /// Reads the mesh named *name*. Returns `None` if there is no such mesh.
+    pub fn read_mesh(&mut self, name: &str) -> Result<Option<TMFMesh>, TMFImportError> {
+        if !self.seek_mesh(name)? {
+            return Ok(None);
+        }
+        let mesh =
+            runtime_agnostic_block_on!(self.ctx.import_mesh_segments(&mut self.src, &self.ctx))?;
+        Ok(Some(mesh))
+    }
+    /// Reads only segments of types *seg_types* of mesh named *name*, skipping all other segments without decoding them. Returns `None` if there is no such mesh.
+    /// Triangle indices may be stored in a [`SectionType::SharedTriangleSegment`] or [`SectionType::ConnectivityTriangleSegment`], so those should be requested alongside any triangle segments.
+    /// # Example
+    /// ```
+    /// # use tmf::{TMFMesh,TMFPrecisionInfo,TMFSeekReader,SectionType};
+    /// # let mut file = Vec::new();
+    /// # TMFMesh::write_tmf(&[(TMFMesh::empty(),"a")],&mut file,&TMFPrecisionInfo::default()).unwrap();
+    /// # let file = std::io::Cursor::new(file);
+    /// let mut reader = TMFSeekReader::new(file).unwrap();
+    /// // Read only vertex positions
+    /// let mesh = reader.read_mesh_segments("a",&[SectionType::VertexSegment]).unwrap().unwrap();
+    /// assert!(mesh.get_normals().is_none());
+    /// ```
+    pub fn read_mesh_segments(
+        &mut self,
+        name: &str,
+        seg_types: &[SectionType],
+    ) -> Result<Option<TMFMesh>, TMFImportError> {
+        if !self.seek_mesh(name)? {
+            return Ok(None);
+        }
+        let segment_count = self.src.read_u16()?;
+        let mut decoded_segs = Vec::new();
+        for _ in 0..segment_count {
+            let (seg_type, data_length, compression_type) =
+                self.ctx.read_segment_header(&mut self.src)?;
+            if !seg_types.contains(&seg_type) {
+                self.src.seek(SeekFrom::Current(data_length as i64))?;
+                continue;
+            }
+            let compression_type = CompressionType::from_u8(compression_type)?;
+            let encoded =
+                EncodedSegment::read_data(seg_type, data_length, compression_type, &mut self.src)?;
+            let decoded = runtime_agnostic_block_on!(DecodedSegment::decode(encoded, &self.ctx))?;
+            decoded_segs.push(decoded);
+        }
+        let mut mesh = TMFMesh::empty();
+        DecodedSegment::apply_all(&decoded_segs, &mut mesh);
+        Ok(Some(mesh))
+    }
+    /// Consumes the reader, returning the underlying source.
+    pub fn into_inner(self) -> R {
+        self.src
+    }
+}
+#[cfg(all(test, feature = "obj_import"))]
+mod test {
+    use super::*;
+    use crate::{TMFPrecisionInfo, TMFWriter};
+    use std::io::Cursor;
+    fn susan() -> TMFMesh {
+        let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+        TMFMesh::read_from_obj_one(&mut file).unwrap().0
+    }
+    fn write_susans(indexed: bool) -> Vec<u8> {
+        let tmf_mesh = susan();
+        let target = Cursor::new(Vec::new());
+        let mut writer = if indexed {
+            TMFWriter::new_indexed(target).unwrap()
+        } else {
+            TMFWriter::new(target).unwrap()
+        };
+        for index in 0..4 {
+            writer
+                .write_mesh(
+                    &tmf_mesh,
+                    &format!("Suzanne{index}"),
+                    &TMFPrecisionInfo::default(),
+                )
+                .unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+    fn check_reader(out: Vec<u8>, stored: bool) {
+        let tmf_mesh = susan();
+        let mut reader = TMFSeekReader::new(Cursor::new(out)).unwrap();
+        assert_eq!(reader.index().is_stored(), stored);
+        assert_eq!(reader.index().mesh_count(), 4);
+        let mesh = reader.read_mesh("Suzanne2").unwrap().unwrap();
+        mesh.verify().unwrap();
+        assert_eq!(mesh.get_vertex_triangles(), tmf_mesh.get_vertex_triangles());
+        let mesh = reader
+            .read_mesh_segments("Suzanne1", &[SectionType::NormalSegment])
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            mesh.get_normals().map(|normals| normals.len()),
+            tmf_mesh.get_normals().map(|normals| normals.len())
+        );
+        assert!(mesh.get_vertices().is_none());
+        assert!(reader.read_mesh("Suzanne4").unwrap().is_none());
+    }
+    #[test]
+    fn read_indexed() {
+        let out = write_susans(true);
+        // Readers unaware of the index must ignore it.
+        assert_eq!(TMFMesh::read_tmf(&mut &out[..]).unwrap().len(), 4);
+        check_reader(out, true);
+    }
+    #[test]
+    fn read_unindexed() {
+        check_reader(write_susans(false), false);
+    }
+    #[test]
+    fn read_corrupted_index() {
+        let mut out = write_susans(true);
+        let len = out.len();
+        // Point the index outside of the file
+        out[len - 12..len - 4].copy_from_slice(&u64::MAX.to_le_bytes());
+        check_reader(out, false);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::tmf_importer::{read_string, TMFImportContext};
+use crate::{TMFImportError, TMFMesh};
+use std::io::Read;
+/// A lazy reader of TMF files, which parses the file header once, and then reads meshes one at a time.
+/// Meshes can be skipped by name, without decoding their segments.
+/// # Example
+/// ```
+/// # use tmf::{TMFMesh,TMFPrecisionInfo,TMFReader};
+/// # let meshes = [(TMFMesh::empty(),"a".to_owned()),(TMFMesh::empty(),"b".to_owned())];
+/// # let mut file = Vec::new();
+/// # TMFMesh::write_tmf(&meshes,&mut file,&TMFPrecisionInfo::default()).unwrap();
+/// # let file = &file[..];
+/// let reader = TMFReader::new(file).expect("Could not read TMF header!");
+/// for mesh in reader{
+///     let (mesh, name) = mesh.expect("Could not read mesh!");
+///     // Do something with each mesh and name(eg. use in game)
+/// }
+/// ```
+pub struct TMFReader<R: Read> {
+    src: R,
+    ctx: TMFImportContext,
+    mesh_count: u32,
+    meshes_read: u32,
+    next_name: Option<String>,
+}
+impl<R: Read> TMFReader<R> {
+    /// Creates a new [`TMFReader`] reading from *src*. Reads the file header, but no meshes.
+    /// # Errors
+    /// Returns: an IO error if it occurs, `NotTMFFile` if not tmf file, and `NewerVersionRequired` if a newer importer is required for importing the file.
+    pub fn new(mut src: R) -> Result<Self, TMFImportError> {
+        let (ctx, mesh_count) =
+            runtime_agnostic_block_on!(TMFImportContext::read_header(&mut src))?;
+        Ok(Self {
+            src,
+            ctx,
+            mesh_count,
+            meshes_read: 0,
+            next_name: None,
+        })
+    }
+    /// Returns the total amount of meshes in the file.
+    #[must_use]
+    pub fn mesh_count(&self) -> u32 {
+        self.mesh_count
+    }
+    /// Returns the amount of meshes which were not yet read or skipped.
+    #[must_use]
+    pub fn remaining(&self) -> u32 {
+        self.mesh_count - self.meshes_read
+    }
+    /// Stops any further reads after an error, since the position in the file is no longer known.
+    fn fail<T>(&mut self, err: TMFImportError) -> Result<T, TMFImportError> {
+        self.meshes_read = self.mesh_count;
+        self.next_name = None;
+        Err(err)
+    }
+    /// Returns the name of the next mesh, without reading the mesh itself. Returns `None` if there are no more meshes.
+    /// # Example
+    /// ```
+    /// # use tmf::{TMFMesh,TMFPrecisionInfo,TMFReader};
+    /// # let meshes = [(TMFMesh::empty(),"a".to_owned()),(TMFMesh::empty(),"b".to_owned())];
+    /// # let mut file = Vec::new();
+    /// # TMFMesh::write_tmf(&meshes,&mut file,&TMFPrecisionInfo::default()).unwrap();
+    /// # let file = &file[..];
+    /// let mut reader = TMFReader::new(file).unwrap();
+    /// assert_eq!(reader.peek_name().unwrap(), Some("a"));
+    /// ```
+    pub fn peek_name(&mut self) -> Result<Option<&str>, TMFImportError> {
+        if self.next_name.is_none() && self.remaining() > 0 {
+            match read_string(&mut self.src) {
+                Ok(name) => self.next_name = Some(name),
+                Err(err) => return self.fail(err.into()),
+            }
+        }
+        Ok(self.next_name.as_deref())
+    }
+    /// Reads and decodes the next mesh. Returns `None` if there are no more meshes.
+    pub fn read_next(&mut self) -> Result<Option<(TMFMesh, String)>, TMFImportError> {
+        if self.peek_name()?.is_none() {
+            return Ok(None);
+        }
+        let name = self.next_name.take().unwrap_or_default();
+        let mesh =
+            runtime_agnostic_block_on!(self.ctx.import_mesh_segments(&mut self.src, &self.ctx));
+        match mesh {
+            Ok(mesh) => {
+                self.meshes_read += 1;
+                Ok(Some((mesh, name)))
+            }
+            Err(err) => self.fail(err),
+        }
+    }
+    /// Skips the next mesh without decoding it, and returns its name. Returns `None` if there are no more meshes.
+    /// # Example
+    /// ```
+    /// # use tmf::{TMFMesh,TMFPrecisionInfo,TMFReader};
+    /// # let meshes = [(TMFMesh::empty(),"a".to_owned()),(TMFMesh::empty(),"b".to_owned())];
+    /// # let mut file = Vec::new();
+    /// # TMFMesh::write_tmf(&meshes,&mut file,&TMFPrecisionInfo::default()).unwrap();
+    /// # let file = &file[..];
+    /// let mut reader = TMFReader::new(file).unwrap();
+    /// assert_eq!(reader.skip_next().unwrap().as_deref(), Some("a"));
+    /// let (mesh, name) = reader.read_next().unwrap().unwrap();
+    /// assert_eq!(name, "b");
+    /// ```
+    pub fn skip_next(&mut self) -> Result<Option<String>, TMFImportError> {
+        if self.peek_name()?.is_none() {
+            return Ok(None);
+        }
+        let name = self.next_name.take().unwrap_or_default();
+        match self.ctx.skip_mesh_segments(&mut self.src) {
+            Ok(()) => {
+                self.meshes_read += 1;
+                Ok(Some(name))
+            }
+            Err(err) => self.fail(err),
+        }
+    }
+    /// Skips meshes until a mesh named *name* is found, and then reads it. Meshes before it are **not** decoded. Returns `None` if no mesh named *name* remains in the file.
+    /// # Example
+    /// ```
+    /// # use tmf::{TMFMesh,TMFPrecisionInfo,TMFReader};
+    /// # let meshes = [(TMFMesh::empty(),"a".to_owned()),(TMFMesh::empty(),"b".to_owned())];
+    /// # let mut file = Vec::new();
+    /// # TMFMesh::write_tmf(&meshes,&mut file,&TMFPrecisionInfo::default()).unwrap();
+    /// # let file = &file[..];
+    /// let mut reader = TMFReader::new(file).unwrap();
+    /// let mesh = reader.find_mesh("b").unwrap().expect("No mesh named b!");
+    /// assert_eq!(reader.remaining(), 0);
+    /// ```
+    pub fn find_mesh(&mut self, name: &str) -> Result<Option<TMFMesh>, TMFImportError> {
+        while let Some(next_name) = self.peek_name()? {
+            if next_name == name {
+                return Ok(self.read_next()?.map(|(mesh, _)| mesh));
+            }
+            self.skip_next()?;
+        }
+        Ok(None)
+    }
+    /// Consumes the reader, returning the underlying source.
+    pub fn into_inner(self) -> R {
+        self.src
+    }
+}
+impl<R: Read> Iterator for TMFReader<R> {
+    type Item = Result<(TMFMesh, String), TMFImportError>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next().transpose()
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.remaining() as usize))
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::TMFPrecisionInfo;
+    #[cfg(feature = "obj_import")]
+    fn susan_copies(count: usize) -> (Vec<u8>, TMFMesh) {
+        let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+        let (tmf_mesh, _) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+        let meshes: Vec<_> = (0..count)
+            .map(|index| (tmf_mesh.clone(), format!("Suzanne{index}")))
+            .collect();
+        let mut out = Vec::new();
+        TMFMesh::write_tmf(&meshes, &mut out, &TMFPrecisionInfo::default()).unwrap();
+        (out, tmf_mesh)
+    }
+    #[test]
+    #[cfg(feature = "obj_import")]
+    fn read_lazy() {
+        let (out, tmf_mesh) = susan_copies(4);
+        let reader = TMFReader::new(&out[..]).unwrap();
+        assert_eq!(reader.mesh_count(), 4);
+        let meshes: Vec<_> = reader.collect::<Result<_, _>>().unwrap();
+        assert_eq!(meshes.len(), 4);
+        for (index, (mesh, name)) in meshes.iter().enumerate() {
+            assert_eq!(*name, format!("Suzanne{index}"));
+            mesh.verify().unwrap();
+            assert_eq!(mesh.get_vertex_triangles(), tmf_mesh.get_vertex_triangles());
+        }
+    }
+    #[test]
+    #[cfg(feature = "obj_import")]
+    fn skip_by_name() {
+        let (out, tmf_mesh) = susan_copies(5);
+        let mut reader = TMFReader::new(&out[..]).unwrap();
+        let mesh = reader.find_mesh("Suzanne3").unwrap().unwrap();
+        mesh.verify().unwrap();
+        assert_eq!(mesh.get_vertex_triangles(), tmf_mesh.get_vertex_triangles());
+        assert_eq!(reader.remaining(), 1);
+        assert!(reader.find_mesh("Suzanne0").unwrap().is_none());
+        assert!(reader.next().is_none());
+    }
+    #[test]
+    fn truncated_file() {
+        let meshes = [(TMFMesh::empty(), "a".to_owned())];
+        let mut out = Vec::new();
+        TMFMesh::write_tmf(&meshes, &mut out, &TMFPrecisionInfo::default()).unwrap();
+        out.truncate(out.len() - 1);
+        let mut reader = TMFReader::new(&out[..]).unwrap();
+        assert!(reader.next().unwrap().is_err());
+        assert!(reader.next().is_none());
+    }
+}
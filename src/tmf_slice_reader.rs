@@ -0,0 +1,322 @@
+use crate::read_extension::ReadExt;
+use crate::tmf::{read_shared_triangles, CompressionType, DecodedSegment, SectionType, SegmentRef};
+use crate::tmf_importer::{read_compressed_triangles, TMFImportContext};
+use crate::{IndexType, TMFImportError, TMFMesh, Vector2, Vector3};
+/// Buffers a [`TMFSliceReader`] decodes meshes into. Reusing the same buffers for many meshes avoids allocating new arrays for each of them.
+#[derive(Default)]
+pub struct TMFMeshBuffers {
+    /// Positions of vertices.
+    pub vertices: Vec<Vector3>,
+    /// Normals.
+    pub normals: Vec<Vector3>,
+    /// Uv coordinates.
+    pub uvs: Vec<Vector2>,
+    /// Triangles, as indices into `vertices`.
+    pub vertex_triangles: Vec<IndexType>,
+    /// Triangles, as indices into `normals`.
+    pub normal_triangles: Vec<IndexType>,
+    /// Triangles, as indices into `uvs`.
+    pub uv_triangles: Vec<IndexType>,
+    /// All other data of the mesh(tangents, colors, materials, groups and custom data).
+    pub other: TMFMesh,
+}
+impl TMFMeshBuffers {
+    /// Creates empty buffers.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Removes all data from the buffers, keeping their allocated capacity.
+    pub fn clear(&mut self) {
+        self.vertices.clear();
+        self.normals.clear();
+        self.uvs.clear();
+        self.vertex_triangles.clear();
+        self.normal_triangles.clear();
+        self.uv_triangles.clear();
+        self.other = TMFMesh::empty();
+    }
+    /// Creates a [`TMFMesh`] from data in the buffers. Empty arrays are left unset.
+    #[must_use]
+    pub fn into_mesh(self) -> TMFMesh {
+        let mut mesh = self.other;
+        if !self.vertices.is_empty() {
+            mesh.set_vertices(self.vertices);
+        }
+        if !self.normals.is_empty() {
+            mesh.set_normals(self.normals);
+        }
+        if !self.uvs.is_empty() {
+            mesh.set_uvs(self.uvs);
+        }
+        if !self.vertex_triangles.is_empty() {
+            mesh.set_vertex_triangles(self.vertex_triangles);
+        }
+        if !self.normal_triangles.is_empty() {
+            mesh.set_normal_triangles(self.normal_triangles);
+        }
+        if !self.uv_triangles.is_empty() {
+            mesh.set_uv_triangles(self.uv_triangles);
+        }
+        mesh
+    }
+    /// Recreates identity indices of an omitted triangle segment of type *seg_type*. Must be called after all other segments are decoded.
+    fn fill_omitted(&mut self, seg_type: SectionType) {
+        let identity = |len: usize| (0..len).map(|index| index as IndexType);
+        match seg_type {
+            SectionType::VertexTriangleSegment => {
+                self.vertex_triangles.extend(identity(self.vertices.len()));
+            }
+            SectionType::NormalTriangleSegment => {
+                self.normal_triangles.extend(identity(self.normals.len()));
+            }
+            SectionType::UvTriangleSegment => self.uv_triangles.extend(identity(self.uvs.len())),
+            _ => DecodedSegment::OmittedTriangles(seg_type).apply(&mut self.other),
+        }
+    }
+    /// Decodes *seg*, appending its data to the buffers.
+    fn decode(
+        &mut self,
+        seg: SegmentRef<'_>,
+        ctx: &TMFImportContext,
+    ) -> Result<(), TMFImportError> {
+        let mut data = seg.data();
+        let compression_type = seg.compresion_type();
+        match seg.seg_type() {
+            SectionType::VertexSegment => {
+                crate::vertices::read_tmf_vertices_into(&mut data, &mut self.vertices)
+            }
+            SectionType::NormalSegment => {
+                crate::normals::read_normal_array_into(&mut data, &mut self.normals).map(|_| ())
+            }
+            SectionType::UvSegment => crate::uv::read_uvs_into(&mut data, &mut self.uvs),
+            SectionType::VertexTriangleSegment => {
+                read_compressed_triangles(data, &mut self.vertex_triangles, ctx, compression_type)
+            }
+            SectionType::NormalTriangleSegment => {
+                read_compressed_triangles(data, &mut self.normal_triangles, ctx, compression_type)
+            }
+            SectionType::UvTriangleSegment => {
+                read_compressed_triangles(data, &mut self.uv_triangles, ctx, compression_type)
+            }
+            SectionType::SharedTriangleSegment | SectionType::ConnectivityTriangleSegment => {
+                let mut indices = Vec::new();
+                let kind = read_shared_triangles(seg, &mut indices, ctx)?;
+                if kind.get_vertex() {
+                    self.vertex_triangles.extend_from_slice(&indices);
+                }
+                if kind.get_normal() {
+                    self.normal_triangles.extend_from_slice(&indices);
+                }
+                if kind.get_uv() {
+                    self.uv_triangles.extend_from_slice(&indices);
+                }
+                Ok(())
+            }
+            _ => {
+                let decoded = futures::executor::block_on(DecodedSegment::decode_ref(seg, ctx))?;
+                decoded.apply(&mut self.other);
+                Ok(())
+            }
+        }
+    }
+}
+/// A reader of TMF files stored in memory(e.g. a memory-mapped file), which decodes segments directly from the borrowed data, without copying them first.
+/// Meshes can be decoded into caller-provided [`TMFMeshBuffers`], reusing their memory.
+///
+/// Segments are decoded on the calling thread, one after another.
+/// # Example
+/// ```
+/// # use tmf::{TMFMesh,TMFPrecisionInfo,TMFSliceReader,TMFMeshBuffers};
+/// # let meshes = [(TMFMesh::empty(),"a".to_owned()),(TMFMesh::empty(),"b".to_owned())];
+/// # let mut file = Vec::new();
+/// # TMFMesh::write_tmf(&meshes,&mut file,&TMFPrecisionInfo::default()).unwrap();
+/// // `file` can be anything which can be borrowed as a byte slice, for example a memory-mapped file.
+/// let mut reader = TMFSliceReader::new(&file).expect("Could not read TMF header!");
+/// let mut buffers = TMFMeshBuffers::new();
+/// while let Some(name) = reader.read_next_into(&mut buffers).expect("Could not read mesh!"){
+///     // Do something with the mesh(eg. upload `buffers.vertices` to the GPU)
+/// }
+/// ```
+pub struct TMFSliceReader<'a> {
+    src: &'a [u8],
+    ctx: TMFImportContext,
+    mesh_count: u32,
+    meshes_read: u32,
+}
+impl<'a> TMFSliceReader<'a> {
+    /// Creates a new [`TMFSliceReader`] reading from *src*. Reads the file header, but no meshes.
+    /// # Errors
+    /// Returns: an IO error if it occurs, `NotTMFFile` if not tmf file, and `NewerVersionRequired` if a newer importer is required for importing the file.
+    pub fn new(mut src: &'a [u8]) -> Result<Self, TMFImportError> {
+        let (ctx, mesh_count) =
+            runtime_agnostic_block_on!(TMFImportContext::read_header(&mut src))?;
+        Ok(Self {
+            src,
+            ctx,
+            mesh_count,
+            meshes_read: 0,
+        })
+    }
+    /// Returns the total amount of meshes in the file.
+    #[must_use]
+    pub fn mesh_count(&self) -> u32 {
+        self.mesh_count
+    }
+    /// Returns the amount of meshes which were not yet read or skipped.
+    #[must_use]
+    pub fn remaining(&self) -> u32 {
+        self.mesh_count - self.meshes_read
+    }
+    /// Stops any further reads after an error, since the position in the file is no longer known.
+    fn fail<T>(&mut self, err: TMFImportError) -> Result<T, TMFImportError> {
+        self.meshes_read = self.mesh_count;
+        Err(err)
+    }
+    /// Takes *length* bytes from the start of the remaining data.
+    fn take(&mut self, length: usize) -> Result<&'a [u8], TMFImportError> {
+        if length > self.src.len() {
+            return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+        }
+        let (taken, rest) = self.src.split_at(length);
+        self.src = rest;
+        Ok(taken)
+    }
+    fn read_name(&mut self) -> Result<&'a str, TMFImportError> {
+        let length = self.src.read_u16()?;
+        std::str::from_utf8(self.take(length as usize)?)
+            .map_err(|_| std::io::Error::other("Mesh name not valid UTF-8").into())
+    }
+    /// Reads the name and segments of the next mesh, calling *on_segment* with each segment.
+    fn read_mesh<F: FnMut(SegmentRef<'a>, &TMFImportContext) -> Result<(), TMFImportError>>(
+        &mut self,
+        mut on_segment: F,
+    ) -> Result<&'a str, TMFImportError> {
+        let name = self.read_name()?;
+        let segment_count = self.src.read_u16()?;
+        for _ in 0..segment_count {
+            let (seg_type, data_length, compression_type) =
+                self.ctx.read_segment_header(&mut self.src)?;
+            let compression_type = CompressionType::from_u8(compression_type)?;
+            let data = self.take(data_length)?;
+            on_segment(SegmentRef::new(seg_type, compression_type, data), &self.ctx)?;
+        }
+        Ok(name)
+    }
+    /// Decodes the next mesh into *buffers*, replacing their previous contents, and returns its name. Returns `None` if there are no more meshes.
+    /// # Errors
+    /// Returns an error if the mesh is malformed. The contents of *buffers* are then unspecified.
+    pub fn read_next_into(
+        &mut self,
+        buffers: &mut TMFMeshBuffers,
+    ) -> Result<Option<&'a str>, TMFImportError> {
+        if self.remaining() == 0 {
+            return Ok(None);
+        }
+        buffers.clear();
+        let mut omitted = Vec::new();
+        let read = self.read_mesh(|seg, ctx| {
+            if seg.compresion_type() == CompressionType::Ommited
+                && seg.seg_type().is_triangle()
+                && seg.seg_type() != SectionType::SharedTriangleSegment
+                && seg.seg_type() != SectionType::ConnectivityTriangleSegment
+            {
+                omitted.push(seg.seg_type());
+                Ok(())
+            } else {
+                buffers.decode(seg, ctx)
+            }
+        });
+        match read {
+            Ok(name) => {
+                for seg_type in omitted {
+                    buffers.fill_omitted(seg_type);
+                }
+                self.meshes_read += 1;
+                Ok(Some(name))
+            }
+            Err(err) => self.fail(err),
+        }
+    }
+    /// Reads and decodes the next mesh. Returns `None` if there are no more meshes.
+    /// # Errors
+    /// Returns an error if the mesh is malformed.
+    pub fn read_next(&mut self) -> Result<Option<(TMFMesh, &'a str)>, TMFImportError> {
+        let mut buffers = TMFMeshBuffers::new();
+        Ok(self
+            .read_next_into(&mut buffers)?
+            .map(|name| (buffers.into_mesh(), name)))
+    }
+    /// Skips the next mesh without decoding it, and returns its name. Returns `None` if there are no more meshes.
+    /// # Errors
+    /// Returns an error if the mesh is malformed.
+    pub fn skip_next(&mut self) -> Result<Option<&'a str>, TMFImportError> {
+        if self.remaining() == 0 {
+            return Ok(None);
+        }
+        match self.read_mesh(|_, _| Ok(())) {
+            Ok(name) => {
+                self.meshes_read += 1;
+                Ok(Some(name))
+            }
+            Err(err) => self.fail(err),
+        }
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::TMFPrecisionInfo;
+    #[test]
+    #[cfg(feature = "obj_import")]
+    fn read_into_buffers() {
+        let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+        let (tmf_mesh, _) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+        let meshes: Vec<_> = (0..3)
+            .map(|index| (tmf_mesh.clone(), format!("Suzanne{index}")))
+            .collect();
+        let mut out = Vec::new();
+        TMFMesh::write_tmf(&meshes, &mut out, &TMFPrecisionInfo::default()).unwrap();
+        let expected = TMFMesh::read_tmf(&mut &out[..]).unwrap();
+        let mut reader = TMFSliceReader::new(&out).unwrap();
+        assert_eq!(reader.skip_next().unwrap(), Some("Suzanne0"));
+        let mut buffers = TMFMeshBuffers::new();
+        for (mesh, name) in &expected[1..] {
+            assert_eq!(
+                reader.read_next_into(&mut buffers).unwrap(),
+                Some(&name[..])
+            );
+            assert_eq!(Some(&buffers.vertices[..]), mesh.get_vertices());
+            assert_eq!(Some(&buffers.normals[..]), mesh.get_normals());
+            assert_eq!(Some(&buffers.uvs[..]), mesh.get_uvs());
+            assert_eq!(
+                Some(&buffers.vertex_triangles[..]),
+                mesh.get_vertex_triangles()
+            );
+            assert_eq!(
+                Some(&buffers.normal_triangles[..]),
+                mesh.get_normal_triangles()
+            );
+            assert_eq!(Some(&buffers.uv_triangles[..]), mesh.get_uv_triangles());
+        }
+        assert!(reader.read_next_into(&mut buffers).unwrap().is_none());
+        let (mesh, name) = TMFSliceReader::new(&out)
+            .unwrap()
+            .read_next()
+            .unwrap()
+            .unwrap();
+        assert_eq!(name, "Suzanne0");
+        mesh.verify().unwrap();
+        assert_eq!(mesh.get_vertices(), expected[0].0.get_vertices());
+    }
+    #[test]
+    fn truncated_file() {
+        let meshes = [(TMFMesh::empty(), "a".to_owned())];
+        let mut out = Vec::new();
+        TMFMesh::write_tmf(&meshes, &mut out, &TMFPrecisionInfo::default()).unwrap();
+        out.truncate(out.len() - 1);
+        let mut reader = TMFSliceReader::new(&out).unwrap();
+        assert!(reader.read_next().is_err());
+        assert!(reader.read_next().unwrap().is_none());
+    }
+}
@@ -0,0 +1,130 @@
+use crate::tmf_exporter::{write_mesh, write_tmf_header};
+use crate::tmf_index::write_index;
+use crate::{TMFExportError, TMFMesh, TMFPrecisionInfo};
+use std::io::{Seek, SeekFrom, Write};
+/// Offset of the mesh count from the beginning of the file header.
+const MESH_COUNT_OFFSET: u64 = (3 + 4 * std::mem::size_of::<u16>()) as u64;
+/// An incremental writer of TMF files, which accepts meshes one at a time, so they don't all need to be kept in memory.
+/// The mesh count in the file header is patched when the writer is finished with [`Self::finish`]. A writer dropped before being finished leaves a file with a mesh count of 0.
+/// # Example
+/// ```
+/// # use tmf::{TMFMesh,TMFPrecisionInfo,TMFWriter};
+/// # fn load_mesh(_:usize)->TMFMesh{TMFMesh::empty()}
+/// # let output = std::io::Cursor::new(Vec::new());
+/// let precision_info = TMFPrecisionInfo::default();
+/// let mut writer = TMFWriter::new(output).expect("Could not write TMF header!");
+/// for index in 0..16{
+///     // Only one mesh is kept in memory at a time.
+///     let mesh = load_mesh(index);
+///     writer.write_mesh(&mesh, &format!("mesh{index}"), &precision_info).expect("Could not save mesh!");
+/// }
+/// let output = writer.finish().expect("Could not finish TMF file!");
+/// ```
+pub struct TMFWriter<W: Write + Seek> {
+    target: W,
+    header_pos: u64,
+    mesh_count: u32,
+    index: Option<Vec<(String, u64)>>,
+}
+impl<W: Write + Seek> TMFWriter<W> {
+    /// Creates a new [`TMFWriter`] writing to *target*. The file header is written starting at the current position of *target*.
+    /// # Errors
+    /// Returns IO error if occurs.
+    pub fn new(mut target: W) -> Result<Self, TMFExportError> {
+        let header_pos = target.stream_position()?;
+        write_tmf_header(&mut target, 0)?;
+        Ok(Self {
+            target,
+            header_pos,
+            mesh_count: 0,
+            index: None,
+        })
+    }
+    /// Creates a new [`TMFWriter`] writing to *target*, which also writes a mesh index footer when finished. The footer allows [`crate::TMFSeekReader`] to find meshes without reading the whole file, and is ignored by other readers.
+    /// # Errors
+    /// Returns IO error if occurs.
+    pub fn new_indexed(target: W) -> Result<Self, TMFExportError> {
+        let mut res = Self::new(target)?;
+        res.index = Some(Vec::new());
+        Ok(res)
+    }
+    /// Returns the amount of meshes written so far.
+    #[must_use]
+    pub fn mesh_count(&self) -> u32 {
+        self.mesh_count
+    }
+    /// Writes *mesh* named *name*, using precision settings *p_info*.
+    /// # Errors
+    /// Returns IO error if occurs, or if more than `u32::MAX` meshes were written.
+    pub fn write_mesh(
+        &mut self,
+        mesh: &TMFMesh,
+        name: &str,
+        p_info: &TMFPrecisionInfo,
+    ) -> Result<(), TMFExportError> {
+        if self.mesh_count == u32::MAX {
+            return Err(std::io::Error::other("Too many meshes in one TMF file").into());
+        }
+        if let Some(index) = &mut self.index {
+            let offset = self.target.stream_position()? - self.header_pos;
+            index.push((name.to_owned(), offset));
+        }
+        runtime_agnostic_block_on!(write_mesh(mesh, name, &mut self.target, p_info))?;
+        self.mesh_count += 1;
+        Ok(())
+    }
+    /// Writes the mesh index footer(if enabled), patches the mesh count in the file header, and returns the underlying target, positioned at the end of the written data.
+    /// # Errors
+    /// Returns IO error if occurs.
+    pub fn finish(mut self) -> Result<W, TMFExportError> {
+        if let Some(index) = &self.index {
+            write_index(&mut self.target, index, self.header_pos)?;
+        }
+        let end = self.target.stream_position()?;
+        self.target
+            .seek(SeekFrom::Start(self.header_pos + MESH_COUNT_OFFSET))?;
+        self.target.write_all(&self.mesh_count.to_le_bytes())?;
+        self.target.seek(SeekFrom::Start(end))?;
+        self.target.flush()?;
+        Ok(self.target)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::io::Cursor;
+    #[test]
+    #[cfg(feature = "obj_import")]
+    fn write_incremental() {
+        let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+        let (tmf_mesh, _) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+        let prec = TMFPrecisionInfo::default();
+        let names: Vec<_> = (0..3).map(|index| format!("Suzanne{index}")).collect();
+        let mut writer = TMFWriter::new(Cursor::new(Vec::new())).unwrap();
+        for name in &names {
+            writer.write_mesh(&tmf_mesh, name, &prec).unwrap();
+        }
+        assert_eq!(writer.mesh_count(), 3);
+        let out = writer.finish().unwrap().into_inner();
+        let meshes = TMFMesh::read_tmf(&mut &out[..]).unwrap();
+        assert_eq!(meshes.len(), 3);
+        for ((mesh, name), expected) in meshes.iter().zip(names.iter()) {
+            assert_eq!(name, expected);
+            mesh.verify().unwrap();
+            assert_eq!(mesh.get_vertex_triangles(), tmf_mesh.get_vertex_triangles());
+        }
+    }
+    #[test]
+    fn write_after_prefix() {
+        let mut target = Cursor::new(Vec::new());
+        target.write_all(b"prefix").unwrap();
+        let mut writer = TMFWriter::new(target).unwrap();
+        writer
+            .write_mesh(&TMFMesh::empty(), "a", &TMFPrecisionInfo::default())
+            .unwrap();
+        let out = writer.finish().unwrap().into_inner();
+        assert_eq!(&out[..6], b"prefix");
+        let (_, name) = TMFMesh::read_tmf_one(&mut &out[6..]).unwrap();
+        assert_eq!(name, "a");
+    }
+}
@@ -1,110 +1,327 @@
-#[cfg(not(feature = "model_importer"))]
-compile_error!("Feature \"triangulation\" is only useful when model importer feature is enabled, and is useless, dead code otherwise.");
-use crate::utilis::{self, add_vec3, magnitude, normalize, sub_vec3};
-use crate::{FloatType};
-// angle inside triangle in radians
-fn compute_angle(prev: Vector3, curr: Vector3, next: Vector3) -> FloatType {
-    let cp = normalize((curr.0 - prev.0, curr.1 - prev.1, curr.2 - prev.2));
-    let cn = normalize((curr.0 - next.0, curr.1 - next.1, curr.2 - next.2));
-    let dot = utilis::dot(cp, cn);
-    //Vectors are normalised, so there is no point dividing them by magnitude
-    FloatType::acos(dot)
+use crate::utilis::{add_vec3, cross, dot, magnitude, normalize, sub_vec3};
+use crate::{FloatType, Vector3};
+/// A point projected onto the plane of a polygon.
+type Point = (f64, f64);
+/// Returns the normal of the best-fit plane of *polygon*, using Newell's method. Works for concave and non-planar polygons, and points in the direction from which *polygon* winds counter-clockwise.
+fn best_fit_normal(polygon: &[Vector3], center: Vector3) -> Vector3 {
+    let mut normal = (0.0, 0.0, 0.0);
+    for (index, curr) in polygon.iter().enumerate() {
+        let next = sub_vec3(polygon[(index + 1) % polygon.len()], center);
+        let curr = sub_vec3(*curr, center);
+        normal.0 += (curr.1 - next.1) * (curr.2 + next.2);
+        normal.1 += (curr.2 - next.2) * (curr.0 + next.0);
+        normal.2 += (curr.0 - next.0) * (curr.1 + next.1);
+    }
+    normal
+}
+/// Twice the signed area of triangle *a*, *b*, *c*. Positive if the triangle is counter-clockwise.
+fn orient(a: Point, b: Point, c: Point) -> f64 {
+    (b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0)
+}
+/// Twice the signed area of polygon *ring*. Positive if the polygon is counter-clockwise.
+fn ring_area(ring: &[usize], points: &[Point]) -> f64 {
+    (0..ring.len())
+        .map(|index| {
+            let (a, b) = (points[ring[index]], points[ring[(index + 1) % ring.len()]]);
+            a.0 * b.1 - b.0 * a.1
+        })
+        .sum()
 }
-fn eval_ear(
-    tri: (usize, usize, usize),
-    vertices: &[Vector3],
-    indices: &[IndexType],
-) -> FloatType {
-    let angle = compute_angle(vertices[tri.0], vertices[tri.1], vertices[tri.2]);
-    let weight = if is_line_inside_poly(vertices[tri.0], vertices[tri.2], vertices, indices) {
-        angle
+/// Checks if *p* is inside or on the edge of counter-clockwise triangle *a*, *b*, *c*.
+fn inside_triangle(a: Point, b: Point, c: Point, p: Point, epsilon: f64) -> bool {
+    orient(a, b, p) >= -epsilon && orient(b, c, p) >= -epsilon && orient(c, a, p) >= -epsilon
+}
+/// Connects *hole* to *ring* with a bridge of 2 edges, so that the hole becomes a part of the outline. The hole must wind clockwise.
+fn bridge_hole(ring: &mut Vec<usize>, hole: &[usize], points: &[Point], epsilon: f64) {
+    let Some(hole_start) =
+        (0..hole.len()).max_by(|a, b| points[hole[*a]].0.total_cmp(&points[hole[*b]].0))
+    else {
+        return;
+    };
+    let m = points[hole[hole_start]];
+    // Find the closest edge hit by a ray from the rightmost hole point, pointing in +x direction.
+    let mut closest: Option<(f64, usize)> = None;
+    for index in 0..ring.len() {
+        let (a, b) = (points[ring[index]], points[ring[(index + 1) % ring.len()]]);
+        if (a.1 > m.1) == (b.1 > m.1) || a.1 == b.1 {
+            continue;
+        }
+        let x = a.0 + (m.1 - a.1) * (b.0 - a.0) / (b.1 - a.1);
+        if x < m.0 || closest.is_some_and(|(closest, _)| closest <= x) {
+            continue;
+        }
+        // Bridge to the end of the edge further along the ray.
+        let end = if a.0 > b.0 {
+            index
+        } else {
+            (index + 1) % ring.len()
+        };
+        closest = Some((x, end));
+    }
+    let Some((x, mut bridge)) = closest else {
+        // The hole is not inside the outline.
+        return;
+    };
+    // Points inside the triangle between the ray and the bridge would make it cross the outline, so the one closest in angle to the ray is used instead.
+    let (hit, end) = ((x, m.1), points[ring[bridge]]);
+    let (a, b) = if orient(m, hit, end) > 0.0 {
+        (hit, end)
     } else {
-        -angle
+        (end, hit)
     };
-    weight
+    let mut best_tan = f64::INFINITY;
+    for (index, id) in ring.iter().enumerate() {
+        let p = points[*id];
+        if p == end || p.0 <= m.0 || !inside_triangle(m, a, b, p, epsilon) {
+            continue;
+        }
+        let tan = (p.1 - m.1).abs() / (p.0 - m.0);
+        if tan < best_tan {
+            best_tan = tan;
+            bridge = index;
+        }
+    }
+    let mut bridged = Vec::with_capacity(ring.len() + hole.len() + 2);
+    bridged.extend_from_slice(&ring[..=bridge]);
+    bridged.extend(hole[hole_start..].iter().chain(&hole[..=hole_start]));
+    bridged.extend_from_slice(&ring[bridge..]);
+    *ring = bridged;
 }
-fn is_line_inside_poly(
-    a: Vector3,
-    b: Vector3,
-    vertices: &[Vector3],
-    indices: &[IndexType],
-) -> bool {
-    true
+/// Checks if the corner at position *index* of *ring* is an ear: it is convex, and no other point of the ring is inside the triangle it forms with its neighbours.
+fn is_ear(ring: &[usize], index: usize, points: &[Point], epsilon: f64) -> bool {
+    let len = ring.len();
+    let corners = [
+        ring[(index + len - 1) % len],
+        ring[index],
+        ring[(index + 1) % len],
+    ];
+    let [a, b, c] = corners.map(|id| points[id]);
+    if orient(a, b, c) <= epsilon {
+        return false;
+    }
+    !ring.iter().any(|id| {
+        let p = points[*id];
+        // Points bridging holes are duplicated, so they are compared by position.
+        !corners.contains(id) && p != a && p != b && p != c && inside_triangle(a, b, c, p, epsilon)
+    })
 }
-fn calc_ear_val_at_index(
-    indices: &[IndexType],
-    index: usize,
-    vertices: &[Vector3],
-) -> FloatType {
-    let tri = (
-        index.wrapping_sub(1) % indices.len(),
-        index,
-        index.wrapping_add(1) % indices.len(),
-    );
-    let p0 = indices[tri.0] as usize;
-    let p1 = indices[tri.1] as usize;
-    let p2 = indices[tri.2] as usize;
-    let tri = (p0, p1, p2);
-    return eval_ear(tri, vertices, indices);
+/// Triangulates a polygon, given as a loop of its corners *polygon*, with optional *holes* in it. Polygons may be concave, have collinear points and don't have to be planar: they are projected onto their best-fit plane before triangulating.
+/// Returns corners of triangles, as indices into *polygon* followed by all *holes*(index `polygon.len()` is the first point of the first hole). Triangles wind in the same direction as *polygon*, and degenerate triangles(with no area) are skipped.
+/// # Example
+/// ```
+/// # use tmf::triangulate_polygon;
+/// // An L shaped, concave polygon.
+/// let polygon = [(0.0, 0.0, 0.0), (2.0, 0.0, 0.0), (2.0, 1.0, 0.0), (1.0, 1.0, 0.0), (1.0, 2.0, 0.0), (0.0, 2.0, 0.0)];
+/// let triangles = triangulate_polygon(&polygon, &[]);
+/// assert_eq!(triangles.len(), 4 * 3);
+/// ```
+#[must_use]
+pub fn triangulate_polygon(polygon: &[Vector3], holes: &[&[Vector3]]) -> Vec<usize> {
+    if polygon.len() < 3 {
+        return Vec::new();
+    }
+    let center = polygon
+        .iter()
+        .fold((0.0, 0.0, 0.0), |sum, p| add_vec3(sum, *p));
+    let count = polygon.len() as FloatType;
+    let center = (center.0 / count, center.1 / count, center.2 / count);
+    let normal = best_fit_normal(polygon, center);
+    if magnitude(normal) == 0.0 || magnitude(normal).is_nan() {
+        // All points are collinear, so there is no area to triangulate.
+        return Vec::new();
+    }
+    let normal = normalize(normal);
+    // Project onto the plane, with axes chosen so that counter-clockwise winding around the normal stays counter-clockwise.
+    let helper = if normal.0.abs() < 0.5 {
+        (1.0, 0.0, 0.0)
+    } else {
+        (0.0, 1.0, 0.0)
+    };
+    let u = normalize(cross(helper, normal));
+    let v = cross(normal, u);
+    let points: Vec<Point> = polygon
+        .iter()
+        .chain(holes.iter().flat_map(|hole| hole.iter()))
+        .map(|p| {
+            let p = sub_vec3(*p, center);
+            (f64::from(dot(p, u)), f64::from(dot(p, v)))
+        })
+        .collect();
+    let scale = points
+        .iter()
+        .fold(0.0_f64, |scale, p| scale.max(p.0.abs()).max(p.1.abs()));
+    let epsilon = scale * scale * 1e-12;
+    let mut ring: Vec<usize> = (0..polygon.len()).collect();
+    // Holes are bridged starting from the rightmost, so later bridges can't cross earlier ones.
+    let mut hole_rings: Vec<Vec<usize>> = Vec::with_capacity(holes.len());
+    let mut start = polygon.len();
+    for hole in holes {
+        let mut hole_ring: Vec<usize> = (start..(start + hole.len())).collect();
+        start += hole.len();
+        if hole_ring.len() < 3 {
+            continue;
+        }
+        if ring_area(&hole_ring, &points) > 0.0 {
+            hole_ring.reverse();
+        }
+        hole_rings.push(hole_ring);
+    }
+    let max_x = |hole: &Vec<usize>| {
+        hole.iter()
+            .map(|id| points[*id].0)
+            .fold(f64::NEG_INFINITY, f64::max)
+    };
+    hole_rings.sort_by(|a, b| max_x(b).total_cmp(&max_x(a)));
+    for hole in &hole_rings {
+        bridge_hole(&mut ring, hole, &points, epsilon);
+    }
+    let mut triangles = Vec::with_capacity((ring.len() - 2) * 3);
+    let mut search_start = 0;
+    while ring.len() > 3 {
+        let len = ring.len();
+        let ear = (0..len)
+            .map(|offset| (search_start + offset) % len)
+            .find(|index| is_ear(&ring, *index, &points, epsilon));
+        // Without any ears the remaining polygon is degenerate or self-intersecting, so the most convex corner is clipped.
+        let index = ear.unwrap_or_else(|| {
+            (0..len)
+                .max_by(|a, b| {
+                    let area = |index: usize| {
+                        orient(
+                            points[ring[(index + len - 1) % len]],
+                            points[ring[index]],
+                            points[ring[(index + 1) % len]],
+                        )
+                    };
+                    area(*a).total_cmp(&area(*b))
+                })
+                .unwrap_or(0)
+        });
+        let corners = [
+            ring[(index + len - 1) % len],
+            ring[index],
+            ring[(index + 1) % len],
+        ];
+        if orient(points[corners[0]], points[corners[1]], points[corners[2]]) > epsilon {
+            triangles.extend(corners);
+        }
+        ring.remove(index);
+        search_start = index.saturating_sub(1);
+    }
+    if orient(points[ring[0]], points[ring[1]], points[ring[2]]) > epsilon {
+        triangles.extend_from_slice(&ring);
+    }
+    triangles
 }
-use smallvec::SmallVec;
-fn triangulate(
-    mi:&mut ModelImporter,
-    mut vertex_indices:SmallVec<[IndexType; SMALL_VEC_CAP]>,
-    mut normal_indices:SmallVec<[IndexType; SMALL_VEC_CAP]>,
-    mut uv_indices:SmallVec<[IndexType; SMALL_VEC_CAP]>,
-) {
-    debug_assert!(vertex_indices.len() == normal_indices.len()&& vertex_indices.len() == uv_indices.len());
-    let mut ear_vals: Vec<FloatType> = Vec::with_capacity(vertex_indices.len());
-    for index in 0_usize..vertex_indices.len() {
-        ear_vals.push(calc_ear_val_at_index(&vertex_indices, index, &mi.vertices));
-    }
-    while vertex_indices.len() > 3 {
-        let mut best_index = 0;
-        for index in 0_usize..vertex_indices.len() {
-            if ear_vals[index] > ear_vals[best_index] {
-                best_index = index;
-            }
+#[cfg(test)]
+mod test {
+    use super::*;
+    /// Sums areas of triangles in *triangles*, using corner positions in *points*.
+    fn total_area(points: &[Vector3], triangles: &[usize]) -> FloatType {
+        triangles
+            .chunks_exact(3)
+            .map(|tri| {
+                let (a, b, c) = (points[tri[0]], points[tri[1]], points[tri[2]]);
+                magnitude(cross(sub_vec3(b, a), sub_vec3(c, a))) / 2.0
+            })
+            .sum()
+    }
+    /// Checks that all triangles wind the same way as *normal*.
+    fn assert_winding(points: &[Vector3], triangles: &[usize], normal: Vector3) {
+        for tri in triangles.chunks_exact(3) {
+            let (a, b, c) = (points[tri[0]], points[tri[1]], points[tri[2]]);
+            assert!(dot(cross(sub_vec3(b, a), sub_vec3(c, a)), normal) > 0.0);
+        }
+    }
+    #[test]
+    fn concave() {
+        // A "U" shape, whose ears are easily clipped across the gap.
+        let polygon = [
+            (0.0, 0.0, 0.0),
+            (3.0, 0.0, 0.0),
+            (3.0, 3.0, 0.0),
+            (2.0, 3.0, 0.0),
+            (2.0, 1.0, 0.0),
+            (1.0, 1.0, 0.0),
+            (1.0, 3.0, 0.0),
+            (0.0, 3.0, 0.0),
+        ];
+        let triangles = triangulate_polygon(&polygon, &[]);
+        assert_eq!(triangles.len(), 6 * 3);
+        assert!((total_area(&polygon, &triangles) - 7.0).abs() < 0.0001);
+        assert_winding(&polygon, &triangles, (0.0, 0.0, 1.0));
+    }
+    #[test]
+    fn collinear() {
+        // A square with extra points in the middle of 2 edges.
+        let polygon = [
+            (0.0, 0.0, 0.0),
+            (1.0, 0.0, 0.0),
+            (2.0, 0.0, 0.0),
+            (2.0, 2.0, 0.0),
+            (0.0, 2.0, 0.0),
+            (0.0, 1.0, 0.0),
+        ];
+        let triangles = triangulate_polygon(&polygon, &[]);
+        // All points are used, so no T-junctions are created with neighbouring faces.
+        assert_eq!(triangles.len(), 4 * 3);
+        for index in 0..polygon.len() {
+            assert!(triangles.contains(&index));
         }
-        let prev_index = best_index.wrapping_sub(1) % vertex_indices.len();
-        let curr_index = best_index;
-        let next_index = best_index.wrapping_add(1) % vertex_indices.len();
-        
-        mi.push_vertex_index(vertex_indices[prev_index]);
-        mi.push_vertex_index(vertex_indices[curr_index]);
-        mi.push_vertex_index(vertex_indices[next_index]);
-        
-        mi.push_normal_index(normal_indices[prev_index]);
-        mi.push_normal_index(normal_indices[curr_index]);
-        mi.push_normal_index(normal_indices[next_index]);
-        
-        mi.push_uv_index(uv_indices[prev_index]);
-        mi.push_uv_index(uv_indices[curr_index]);
-        mi.push_uv_index(uv_indices[next_index]);
-        
-        vertex_indices.remove(curr_index);
-        normal_indices.remove(curr_index);
-        uv_indices.remove(curr_index);
-        
-        ear_vals.remove(curr_index);
-        
-        let curr_index = curr_index % vertex_indices.len();
-        let prev_index = best_index.wrapping_sub(1) % vertex_indices.len();
-        ear_vals[curr_index] = calc_ear_val_at_index(&vertex_indices, curr_index, &mi.vertices);
-        ear_vals[prev_index] = calc_ear_val_at_index(&vertex_indices, prev_index, &mi.vertices);
-        
-    }
-    mi.push_vertex_index(vertex_indices[0]);
-    mi.push_vertex_index(vertex_indices[1]);
-    mi.push_vertex_index(vertex_indices[2]);
-        
-    mi.push_normal_index(normal_indices[0]);
-    mi.push_normal_index(normal_indices[1]);
-    mi.push_normal_index(normal_indices[2]);
-        
-    mi.push_uv_index(uv_indices[0]);
-    mi.push_uv_index(uv_indices[1]);
-    mi.push_uv_index(uv_indices[2]);
+        assert!((total_area(&polygon, &triangles) - 4.0).abs() < 0.0001);
+        assert_winding(&polygon, &triangles, (0.0, 0.0, 1.0));
+    }
+    #[test]
+    fn hole() {
+        let polygon = [
+            (0.0, 0.0, 0.0),
+            (4.0, 0.0, 0.0),
+            (4.0, 4.0, 0.0),
+            (0.0, 4.0, 0.0),
+        ];
+        // Winds the same way as the outline, so it must be reversed.
+        let hole = [
+            (1.0, 1.0, 0.0),
+            (3.0, 1.0, 0.0),
+            (3.0, 3.0, 0.0),
+            (1.0, 3.0, 0.0),
+        ];
+        let triangles = triangulate_polygon(&polygon, &[&hole]);
+        let points: Vec<Vector3> = polygon.iter().chain(hole.iter()).copied().collect();
+        assert_eq!(triangles.len(), 8 * 3);
+        assert!((total_area(&points, &triangles) - 12.0).abs() < 0.0001);
+        assert_winding(&points, &triangles, (0.0, 0.0, 1.0));
+    }
+    #[test]
+    fn non_planar() {
+        // A concave polygon on a tilted plane, with the middle point pushed slightly out of it.
+        let polygon = [
+            (0.0, 0.0, 0.0),
+            (2.0, 0.0, 2.0),
+            (1.0, 1.0, 1.05),
+            (2.0, 2.0, 2.0),
+            (0.0, 2.0, 0.0),
+        ];
+        let triangles = triangulate_polygon(&polygon, &[]);
+        assert_eq!(triangles.len(), 3 * 3);
+        // The reflex corner must not be the middle of a triangle spanning over it.
+        assert!(!triangles
+            .chunks_exact(3)
+            .any(|tri| tri.contains(&1) && tri.contains(&3)));
+        assert_winding(&polygon, &triangles, (-1.0, 0.0, 1.0));
+    }
+    #[test]
+    fn degenerate() {
+        let line = [
+            (0.0, 0.0, 0.0),
+            (1.0, 0.0, 0.0),
+            (2.0, 0.0, 0.0),
+            (3.0, 0.0, 0.0),
+        ];
+        assert!(triangulate_polygon(&line, &[]).is_empty());
+        assert!(triangulate_polygon(&line[..2], &[]).is_empty());
+        assert!(triangulate_polygon(&line[..3], &[]).is_empty());
+        let triangle = [(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)];
+        assert_eq!(triangulate_polygon(&triangle, &[]), [0, 1, 2]);
+    }
 }
-
-
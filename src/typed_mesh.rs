@@ -0,0 +1,536 @@
+use crate::custom_data::CustomDataSegment;
+use crate::read_extension::ReadExt;
+use crate::tmf::{
+    read_shared_triangles, CompressionType, DecodedSegment, EncodedSegment, SectionType, SegmentRef,
+};
+use crate::tmf_importer::{read_compressed_triangles, read_string, TMFImportContext};
+use crate::{
+    CustomData, FloatType, GroupInfo, IndexType, MaterialInfo, MaterialLibrary,
+    SmoothingGroupRange, TMFImportError, TMFMesh,
+};
+use std::io::Read;
+mod sealed {
+    use std::ops::{Add, Div, Mul, Neg, Sub};
+    pub trait Sealed {}
+    impl Sealed for f32 {}
+    impl Sealed for f64 {}
+    impl Sealed for u16 {}
+    impl Sealed for u32 {}
+    impl Sealed for u64 {}
+    /// Operations needed to decode floats of this type.
+    pub trait Float:
+        Sized
+        + Add<Output = Self>
+        + Sub<Output = Self>
+        + Mul<Output = Self>
+        + Div<Output = Self>
+        + Neg<Output = Self>
+    {
+        fn from_f64(value: f64) -> Self;
+        fn from_u64(value: u64) -> Self;
+        fn to_f64(self) -> f64;
+        fn sqrt(self) -> Self;
+        fn sin_cos(self) -> (Self, Self);
+    }
+    macro_rules! impl_float {
+        ($float:ty) => {
+            impl Float for $float {
+                #[allow(clippy::cast_possible_truncation, clippy::unnecessary_cast)]
+                fn from_f64(value: f64) -> Self {
+                    value as $float
+                }
+                #[allow(clippy::cast_precision_loss)]
+                fn from_u64(value: u64) -> Self {
+                    value as $float
+                }
+                #[allow(clippy::unnecessary_cast)]
+                fn to_f64(self) -> f64 {
+                    self as f64
+                }
+                fn sqrt(self) -> Self {
+                    <$float>::sqrt(self)
+                }
+                fn sin_cos(self) -> (Self, Self) {
+                    <$float>::sin_cos(self)
+                }
+            }
+        };
+    }
+    impl_float!(f32);
+    impl_float!(f64);
+}
+/// Floating-point type a [`TMFMeshOf`] can store its data in: [`f32`] or [`f64`].
+pub trait MeshFloat:
+    sealed::Sealed + sealed::Float + Copy + Default + PartialEq + std::fmt::Debug
+{
+    /// Converts *value* to this type.
+    fn from_float(value: FloatType) -> Self;
+}
+impl MeshFloat for f32 {
+    #[allow(clippy::unnecessary_cast)]
+    fn from_float(value: FloatType) -> Self {
+        value as f32
+    }
+}
+impl MeshFloat for f64 {
+    #[allow(clippy::unnecessary_cast)]
+    fn from_float(value: FloatType) -> Self {
+        value as f64
+    }
+}
+/// Index type a [`TMFMeshOf`] can store its triangles in: [`u16`], [`u32`] or [`u64`].
+pub trait MeshIndex:
+    sealed::Sealed + Copy + PartialEq + std::fmt::Debug + TryFrom<u64> + Into<u64>
+{
+    /// Size of this type in bits.
+    const BITS: u32;
+}
+/// Converts a decoded *index* to *I*, failing if it does not fit.
+pub(crate) fn index_from_u64<I: MeshIndex>(index: u64) -> Result<I, TMFImportError> {
+    I::try_from(index).map_err(|_| TMFImportError::IndexWidthExceeded(I::BITS))
+}
+impl MeshIndex for u16 {
+    const BITS: u32 = u16::BITS;
+}
+impl MeshIndex for u32 {
+    const BITS: u32 = u32::BITS;
+}
+impl MeshIndex for u64 {
+    const BITS: u32 = u64::BITS;
+}
+/// 2d vector of float type *F*.
+pub type Vector2Of<F> = (F, F);
+/// 3d vector of float type *F*.
+pub type Vector3Of<F> = (F, F, F);
+/// Tangent(and its handedness) of float type *F*.
+pub type TangentOf<F> = (Vector3Of<F>, F);
+/// RGBA color of float type *F*.
+pub type ColorOf<F> = (F, F, F, F);
+/// A mesh storing its data in float type *F* and index type *I*, chosen when reading instead of by cargo features. Created by [`TMFMesh::read_tmf_as`] or [`TMFMeshOf::from_mesh`].
+///
+/// [`TMFMesh::read_tmf_as`] decodes data directly into *F* and *I*, so it can read files with more indices than [`IndexType`] supports, or keep more precision than [`FloatType`] has.
+/// Custom data keeps using [`FloatType`] and [`IndexType`].
+/// # Example
+/// ```
+/// # use tmf::{TMFMesh,TMFMeshOf};
+/// # let mut file = std::fs::File::open("testing/susan.tmf").unwrap();
+/// let meshes = TMFMesh::read_tmf_as::<f32, u16, _>(&mut file).expect("Could not read meshes!");
+/// let (mesh, name) = &meshes[0];
+/// let triangles: &[u16] = mesh.get_vertex_triangles().unwrap();
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TMFMeshOf<F: MeshFloat, I: MeshIndex> {
+    vertices: Option<Box<[Vector3Of<F>]>>,
+    normals: Option<Box<[Vector3Of<F>]>>,
+    uvs: Option<Box<[Vector2Of<F>]>>,
+    tangents: Option<Box<[TangentOf<F>]>>,
+    colors: Option<Box<[ColorOf<F>]>>,
+    vertex_triangles: Option<Box<[I]>>,
+    normal_triangles: Option<Box<[I]>>,
+    uv_triangles: Option<Box<[I]>>,
+    tangent_triangles: Option<Box<[I]>>,
+    color_triangles: Option<Box<[I]>>,
+    materials: Option<MaterialInfo>,
+    groups: Option<GroupInfo>,
+    smoothing_groups: Option<Box<[SmoothingGroupRange]>>,
+    material_library: Option<MaterialLibrary>,
+    custom_data: Vec<CustomDataSegment>,
+}
+fn convert_triangles<I: MeshIndex>(
+    triangles: Option<&[IndexType]>,
+) -> Result<Option<Box<[I]>>, TMFImportError> {
+    triangles
+        .map(|triangles| {
+            triangles
+                .iter()
+                .map(|index| index_from_u64(u64::from(*index)))
+                .collect()
+        })
+        .transpose()
+}
+/// Appends indices `0..len` to *triangles*, recreating an omitted triangle segment.
+fn fill_identity<I: MeshIndex>(triangles: &mut Vec<I>, len: usize) -> Result<(), TMFImportError> {
+    triangles.reserve(len);
+    for index in 0..len as u64 {
+        triangles.push(index_from_u64(index)?);
+    }
+    Ok(())
+}
+/// Moves *data* into a boxed slice, or returns `None` if it is empty.
+fn non_empty<T>(data: Vec<T>) -> Option<Box<[T]>> {
+    (!data.is_empty()).then(|| data.into())
+}
+/// Arrays of a mesh, which segments are decoded into.
+struct MeshDecoder<F: MeshFloat, I: MeshIndex> {
+    vertices: Vec<Vector3Of<F>>,
+    normals: Vec<Vector3Of<F>>,
+    uvs: Vec<Vector2Of<F>>,
+    tangents: Vec<TangentOf<F>>,
+    colors: Vec<ColorOf<F>>,
+    vertex_triangles: Vec<I>,
+    normal_triangles: Vec<I>,
+    uv_triangles: Vec<I>,
+    tangent_triangles: Vec<I>,
+    color_triangles: Vec<I>,
+    /// Triangle segments which were omitted, and need to be recreated after all other segments are decoded.
+    omitted: Vec<SectionType>,
+    /// Materials, groups, material library and custom data of the mesh.
+    other: TMFMesh,
+}
+impl<F: MeshFloat, I: MeshIndex> MeshDecoder<F, I> {
+    fn new() -> Self {
+        Self {
+            vertices: Vec::new(),
+            normals: Vec::new(),
+            uvs: Vec::new(),
+            tangents: Vec::new(),
+            colors: Vec::new(),
+            vertex_triangles: Vec::new(),
+            normal_triangles: Vec::new(),
+            uv_triangles: Vec::new(),
+            tangent_triangles: Vec::new(),
+            color_triangles: Vec::new(),
+            omitted: Vec::new(),
+            other: TMFMesh::empty(),
+        }
+    }
+    /// Decodes *seg*, appending its data to the arrays.
+    fn decode(
+        &mut self,
+        seg: SegmentRef<'_>,
+        ctx: &TMFImportContext,
+    ) -> Result<(), TMFImportError> {
+        let mut data = seg.data();
+        let compression_type = seg.compresion_type();
+        let triangles = match seg.seg_type() {
+            SectionType::VertexSegment => {
+                return crate::vertices::read_tmf_vertices_into(&mut data, &mut self.vertices)
+            }
+            SectionType::NormalSegment => {
+                return crate::normals::read_normal_array_into(&mut data, &mut self.normals)
+                    .map(|_| ())
+            }
+            SectionType::UvSegment => return crate::uv::read_uvs_into(&mut data, &mut self.uvs),
+            SectionType::TangentSegment => {
+                return crate::tangents::read_tangents_into(&mut data, &mut self.tangents)
+            }
+            SectionType::ColorSegment => {
+                return crate::color::read_colors_into(&mut data, &mut self.colors)
+            }
+            SectionType::VertexTriangleSegment => &mut self.vertex_triangles,
+            SectionType::NormalTriangleSegment => &mut self.normal_triangles,
+            SectionType::UvTriangleSegment => &mut self.uv_triangles,
+            SectionType::TangentTriangleSegment => &mut self.tangent_triangles,
+            SectionType::ColorTriangleSegment => &mut self.color_triangles,
+            SectionType::SharedTriangleSegment | SectionType::ConnectivityTriangleSegment => {
+                let mut indices = Vec::new();
+                let kind = read_shared_triangles(seg, &mut indices, ctx)?;
+                if kind.get_vertex() {
+                    self.vertex_triangles.extend_from_slice(&indices);
+                }
+                if kind.get_normal() {
+                    self.normal_triangles.extend_from_slice(&indices);
+                }
+                if kind.get_uv() {
+                    self.uv_triangles.extend_from_slice(&indices);
+                }
+                return Ok(());
+            }
+            _ => {
+                let decoded = futures::executor::block_on(DecodedSegment::decode_ref(seg, ctx))?;
+                decoded.apply(&mut self.other);
+                return Ok(());
+            }
+        };
+        if compression_type == CompressionType::Ommited {
+            self.omitted.push(seg.seg_type());
+            Ok(())
+        } else {
+            read_compressed_triangles(data, triangles, ctx, compression_type)
+        }
+    }
+    /// Recreates omitted triangle segments, and creates the mesh from the decoded arrays.
+    fn finish(mut self) -> Result<TMFMeshOf<F, I>, TMFImportError> {
+        for seg_type in std::mem::take(&mut self.omitted) {
+            let (triangles, len) = match seg_type {
+                SectionType::VertexTriangleSegment => {
+                    (&mut self.vertex_triangles, self.vertices.len())
+                }
+                SectionType::NormalTriangleSegment => {
+                    (&mut self.normal_triangles, self.normals.len())
+                }
+                SectionType::UvTriangleSegment => (&mut self.uv_triangles, self.uvs.len()),
+                SectionType::TangentTriangleSegment => {
+                    (&mut self.tangent_triangles, self.tangents.len())
+                }
+                _ => (&mut self.color_triangles, self.colors.len()),
+            };
+            fill_identity(triangles, len)?;
+        }
+        Ok(TMFMeshOf {
+            vertices: non_empty(self.vertices),
+            normals: non_empty(self.normals),
+            uvs: non_empty(self.uvs),
+            tangents: non_empty(self.tangents),
+            colors: non_empty(self.colors),
+            vertex_triangles: non_empty(self.vertex_triangles),
+            normal_triangles: non_empty(self.normal_triangles),
+            uv_triangles: non_empty(self.uv_triangles),
+            tangent_triangles: non_empty(self.tangent_triangles),
+            color_triangles: non_empty(self.color_triangles),
+            materials: self.other.materials,
+            groups: self.other.groups,
+            smoothing_groups: self.other.smoothing_groups.map(Into::into),
+            material_library: self.other.material_library,
+            custom_data: self.other.custom_data,
+        })
+    }
+}
+/// Reads all meshes from *src*, decoding their data directly into float type *F* and index type *I*.
+#[allow(clippy::type_complexity)]
+pub(crate) fn read_tmf_as<F: MeshFloat, I: MeshIndex, R: Read>(
+    src: &mut R,
+) -> Result<Vec<(TMFMeshOf<F, I>, String)>, TMFImportError> {
+    let (ctx, mesh_count) = runtime_agnostic_block_on!(TMFImportContext::read_header(src))?;
+    let mut meshes = Vec::with_capacity((u16::MAX as usize).min(mesh_count as usize));
+    for _ in 0..mesh_count {
+        let name = read_string(src)?;
+        let segment_count = src.read_u16()?;
+        let mut decoder = MeshDecoder::new();
+        for _ in 0..segment_count {
+            let encoded = EncodedSegment::read(&ctx, src)?;
+            decoder.decode(encoded.as_ref(), &ctx)?;
+        }
+        meshes.push((decoder.finish()?, name));
+    }
+    Ok(meshes)
+}
+impl<F: MeshFloat, I: MeshIndex> TMFMeshOf<F, I> {
+    /// Converts *mesh* to use float type *F* and index type *I*.
+    /// # Errors
+    /// Returns [`TMFImportError::IndexWidthExceeded`] if any index of *mesh* does not fit in *I*.
+    pub fn from_mesh(mesh: &TMFMesh) -> Result<Self, TMFImportError> {
+        let f = F::from_float;
+        Ok(Self {
+            vertices: mesh
+                .get_vertices()
+                .map(|vertices| vertices.iter().map(|v| (f(v.0), f(v.1), f(v.2))).collect()),
+            normals: mesh
+                .get_normals()
+                .map(|normals| normals.iter().map(|n| (f(n.0), f(n.1), f(n.2))).collect()),
+            uvs: mesh
+                .get_uvs()
+                .map(|uvs| uvs.iter().map(|uv| (f(uv.0), f(uv.1))).collect()),
+            tangents: mesh.get_tangents().map(|tangents| {
+                tangents
+                    .iter()
+                    .map(|(t, h)| ((f(t.0), f(t.1), f(t.2)), f(*h)))
+                    .collect()
+            }),
+            colors: mesh.get_colors().map(|colors| {
+                colors
+                    .iter()
+                    .map(|c| (f(c.0), f(c.1), f(c.2), f(c.3)))
+                    .collect()
+            }),
+            vertex_triangles: convert_triangles(mesh.get_vertex_triangles())?,
+            normal_triangles: convert_triangles(mesh.get_normal_triangles())?,
+            uv_triangles: convert_triangles(mesh.get_uv_triangles())?,
+            tangent_triangles: convert_triangles(mesh.get_tangent_triangles())?,
+            color_triangles: convert_triangles(mesh.get_color_triangles())?,
+            materials: mesh.get_materials().cloned(),
+            groups: mesh.get_groups().cloned(),
+            smoothing_groups: mesh.get_smoothing_groups().map(Into::into),
+            material_library: mesh.get_material_library().cloned(),
+            custom_data: mesh.custom_data.clone(),
+        })
+    }
+    /// Gets the vertex array of this mesh.
+    #[must_use]
+    pub fn get_vertices(&self) -> Option<&[Vector3Of<F>]> {
+        self.vertices.as_deref()
+    }
+    /// Gets the normal array of this mesh.
+    #[must_use]
+    pub fn get_normals(&self) -> Option<&[Vector3Of<F>]> {
+        self.normals.as_deref()
+    }
+    /// Gets the uv array of this mesh.
+    #[must_use]
+    pub fn get_uvs(&self) -> Option<&[Vector2Of<F>]> {
+        self.uvs.as_deref()
+    }
+    /// Gets the tangent array of this mesh.
+    #[must_use]
+    pub fn get_tangents(&self) -> Option<&[TangentOf<F>]> {
+        self.tangents.as_deref()
+    }
+    /// Gets the vertex color array of this mesh.
+    #[must_use]
+    pub fn get_colors(&self) -> Option<&[ColorOf<F>]> {
+        self.colors.as_deref()
+    }
+    /// Gets the vertex triangle index array of this mesh.
+    #[must_use]
+    pub fn get_vertex_triangles(&self) -> Option<&[I]> {
+        self.vertex_triangles.as_deref()
+    }
+    /// Gets the normal triangle index array of this mesh.
+    #[must_use]
+    pub fn get_normal_triangles(&self) -> Option<&[I]> {
+        self.normal_triangles.as_deref()
+    }
+    /// Gets the uv triangle index array of this mesh.
+    #[must_use]
+    pub fn get_uv_triangles(&self) -> Option<&[I]> {
+        self.uv_triangles.as_deref()
+    }
+    /// Gets the tangent triangle index array of this mesh.
+    #[must_use]
+    pub fn get_tangent_triangles(&self) -> Option<&[I]> {
+        self.tangent_triangles.as_deref()
+    }
+    /// Gets the color triangle index array of this mesh.
+    #[must_use]
+    pub fn get_color_triangles(&self) -> Option<&[I]> {
+        self.color_triangles.as_deref()
+    }
+    /// Gets the material info of this mesh.
+    #[must_use]
+    pub fn get_materials(&self) -> Option<&MaterialInfo> {
+        self.materials.as_ref()
+    }
+    /// Gets the group info of this mesh.
+    #[must_use]
+    pub fn get_groups(&self) -> Option<&GroupInfo> {
+        self.groups.as_ref()
+    }
+    /// Gets the smoothing group ranges of this mesh.
+    #[must_use]
+    pub fn get_smoothing_groups(&self) -> Option<&[SmoothingGroupRange]> {
+        self.smoothing_groups.as_deref()
+    }
+    /// Gets the material library of this mesh.
+    #[must_use]
+    pub fn get_material_library(&self) -> Option<&MaterialLibrary> {
+        self.material_library.as_ref()
+    }
+    /// Gets a custom data array with name *name*.
+    /// Returns `None`, if data not present, or name too long(over 255 bytes).
+    #[must_use]
+    pub fn lookup_custom_data(&self, name: &str) -> Option<&CustomData> {
+        CustomDataSegment::lookup(&self.custom_data, name)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn index_width() {
+        let mut mesh = TMFMesh::empty();
+        mesh.set_vertices([(0.0, 0.0, 0.0), (1.0, 0.5, 0.25)]);
+        mesh.set_vertex_triangles([0, 1, 1, 0, 1, u16::MAX as IndexType]);
+        let small = TMFMeshOf::<f64, u16>::from_mesh(&mesh).unwrap();
+        assert_eq!(
+            small.get_vertex_triangles(),
+            Some(&[0_u16, 1, 1, 0, 1, u16::MAX][..])
+        );
+        assert_eq!(small.get_vertices().unwrap()[1], (1.0, 0.5, 0.25));
+        #[cfg(not(feature = "short_indices"))]
+        {
+            mesh.set_vertex_triangles([0, 1, 1, 0, 1, u16::MAX as IndexType + 1]);
+            assert!(matches!(
+                TMFMeshOf::<f32, u16>::from_mesh(&mesh),
+                Err(TMFImportError::IndexWidthExceeded(16))
+            ));
+            let wide = TMFMeshOf::<f32, u64>::from_mesh(&mesh).unwrap();
+            assert_eq!(wide.get_vertex_triangles().unwrap()[5], 1 << 16);
+        }
+    }
+    #[test]
+    fn decode_wide_indices() {
+        use crate::unaligned_rw::{UnalignedRWMode, UnalignedWriter};
+        // Triangle indices 2^40, 2^40 + 1, 2^40 + 1, which don't fit in `IndexType` of any default build.
+        let min = 1_u64 << 40;
+        let mut data = vec![1];
+        data.extend_from_slice(&3_u64.to_le_bytes());
+        data.extend_from_slice(&min.to_le_bytes());
+        {
+            let mut writer = UnalignedWriter::new(&mut data);
+            for index in [0, 1, 1] {
+                writer
+                    .write_unaligned(UnalignedRWMode::precision_bits(1), index)
+                    .unwrap();
+            }
+            writer.flush().unwrap();
+        }
+        let seg = SegmentRef::new(
+            SectionType::VertexTriangleSegment,
+            CompressionType::None,
+            &data,
+        );
+        let ctx = TMFImportContext::default();
+        let mut wide = MeshDecoder::<f64, u64>::new();
+        wide.decode(seg, &ctx).unwrap();
+        assert_eq!(wide.vertex_triangles, [min, min + 1, min + 1]);
+        let mut narrow = MeshDecoder::<f32, u32>::new();
+        assert!(matches!(
+            narrow.decode(seg, &ctx),
+            Err(TMFImportError::IndexWidthExceeded(32))
+        ));
+    }
+    #[test]
+    fn read_susan_as() {
+        let file = std::fs::read("testing/susan.tmf").unwrap();
+        let meshes = TMFMesh::read_tmf(&mut &file[..]).unwrap();
+        let typed = TMFMesh::read_tmf_as::<FloatType, IndexType, _>(&mut &file[..]).unwrap();
+        assert_eq!(typed.len(), meshes.len());
+        for ((mesh, name), (typed, typed_name)) in meshes.iter().zip(&typed) {
+            assert_eq!(name, typed_name);
+            assert_eq!(&TMFMeshOf::from_mesh(mesh).unwrap(), typed);
+        }
+        let wide = TMFMesh::read_tmf_as::<f64, u64, _>(&mut &file[..]).unwrap();
+        let (mesh, _) = &meshes[0];
+        let (wide, _) = &wide[0];
+        for (vertex, wide_vertex) in mesh
+            .get_vertices()
+            .unwrap()
+            .iter()
+            .zip(wide.get_vertices().unwrap())
+        {
+            assert!((f64::from(vertex.0) - wide_vertex.0).abs() < 0.001);
+        }
+        assert_eq!(
+            wide.get_vertex_triangles().unwrap().len(),
+            mesh.get_vertex_triangles().unwrap().len()
+        );
+    }
+    #[test]
+    fn read_custom_data_and_library_as() {
+        use crate::{MaterialDefinition, TMFPrecisionInfo};
+        let mut mesh = TMFMesh::empty();
+        mesh.set_vertices([(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 1.0, 0.0)]);
+        mesh.set_vertex_triangles([0, 1, 2]);
+        let intensity: &[FloatType] = &[0.25, 0.5, 1.0];
+        mesh.add_custom_data(intensity.into(), "intensity").unwrap();
+        let mut library = MaterialLibrary::new();
+        library.add_material(MaterialDefinition::new("wood"));
+        mesh.set_material_library(library);
+        let mut out = Vec::new();
+        mesh.write_tmf_one(&mut out, &TMFPrecisionInfo::default(), "mesh")
+            .unwrap();
+        let (read, _) = TMFMesh::read_tmf_one(&mut &out[..]).unwrap();
+        let typed = TMFMesh::read_tmf_as::<f64, u16, _>(&mut &out[..])
+            .unwrap()
+            .remove(0)
+            .0;
+        assert_eq!(typed.get_material_library(), read.get_material_library());
+        assert!(typed.get_material_library().is_some());
+        assert_eq!(
+            typed.lookup_custom_data("intensity"),
+            read.lookup_custom_data("intensity")
+        );
+        assert!(typed.lookup_custom_data("intensity").is_some());
+        assert_eq!(typed, TMFMeshOf::from_mesh(&read).unwrap());
+    }
+}
@@ -17,8 +17,8 @@ fn unfiy_data_common<const DATA_COUNT: usize>(
     let mut new_indices = Vec::with_capacity(indices.len());
     for index in 0..len {
         let mut curr_combo: ComboType<DATA_COUNT> = [0; DATA_COUNT];
-        for sub_index in 0..DATA_COUNT {
-            curr_combo[sub_index] = indices[sub_index][index];
+        for (combo_index, index_arr) in curr_combo.iter_mut().zip(indices) {
+            *combo_index = index_arr[index];
         }
         let curr_id = *combos.entry(curr_combo).or_insert_with(|| {
             let id = combo_id;
@@ -103,6 +103,44 @@ pub(crate) fn merge_data_4<A: Copy, B: Copy, C: Copy, D: Copy>(
         .collect();
     (indices, a, b, c, d)
 }
+pub(crate) fn merge_data_5<A: Copy, B: Copy, C: Copy, D: Copy, E: Copy>(
+    indices: &[&[IndexType]; 5],
+    a: &[A],
+    b: &[B],
+    c: &[C],
+    d: &[D],
+    e: &[E],
+) -> (
+    Box<[IndexType]>,
+    Box<[A]>,
+    Box<[B]>,
+    Box<[C]>,
+    Box<[D]>,
+    Box<[E]>,
+) {
+    let (indices, index_map) = unfiy_data_common::<5>(indices);
+    let a: Box<[_]> = index_map[0]
+        .iter()
+        .map(|index| a[*index as usize])
+        .collect();
+    let b: Box<[_]> = index_map[1]
+        .iter()
+        .map(|index| b[*index as usize])
+        .collect();
+    let c: Box<[_]> = index_map[2]
+        .iter()
+        .map(|index| c[*index as usize])
+        .collect();
+    let d: Box<[_]> = index_map[3]
+        .iter()
+        .map(|index| d[*index as usize])
+        .collect();
+    let e: Box<[_]> = index_map[4]
+        .iter()
+        .map(|index| e[*index as usize])
+        .collect();
+    (indices, a, b, c, d, e)
+}
 type OBoxArr<A> = Option<Box<[A]>>;
 fn is_merge_needed(indices: &[Option<&[IndexType]>]) -> bool {
     // filter keeps only `Some` values
@@ -137,13 +175,13 @@ pub(crate) fn smart_merge_data_3<A: Copy, B: Copy, C: Copy>(
     if !is_merge_needed(&indices) {
         return (None, None, None, None);
     }
-    if !a.is_some_and(|data| !data.is_empty()) || indices[0].is_none() {
+    if a.map_or(true, |data| data.is_empty()) || indices[0].is_none() {
         let (b, c, indices) = smart_merge_data_2(b, c, [indices[1], indices[2]]);
         (None, b, c, indices)
-    } else if !b.is_some_and(|data| !data.is_empty()) || indices[1].is_none() {
+    } else if b.map_or(true, |data| data.is_empty()) || indices[1].is_none() {
         let (a, c, indices) = smart_merge_data_2(a, c, [indices[0], indices[2]]);
         (a, None, c, indices)
-    } else if !c.is_some_and(|data| !data.is_empty()) || indices[2].is_none() {
+    } else if c.map_or(true, |data| data.is_empty()) || indices[2].is_none() {
         let (a, b, indices) = smart_merge_data_2(a, b, [indices[0], indices[1]]);
         (a, b, None, indices)
     } else if let Some((((a, indices_a), (b, indices_b)), (c, indices_c))) = a
@@ -173,16 +211,16 @@ pub(crate) fn smart_merge_data_4<A: Copy, B: Copy, C: Copy, D: Copy>(
     if !is_merge_needed(&indices) {
         return (None, None, None, None, None);
     }
-    if !a.is_some_and(|data| !data.is_empty()) || indices[0].is_none() {
+    if a.map_or(true, |data| data.is_empty()) || indices[0].is_none() {
         let (b, c, d, indices) = smart_merge_data_3(b, c, d, [indices[1], indices[2], indices[3]]);
         (None, b, c, d, indices)
-    } else if !b.is_some_and(|data| !data.is_empty()) || indices[1].is_none() {
+    } else if b.map_or(true, |data| data.is_empty()) || indices[1].is_none() {
         let (a, c, d, indices) = smart_merge_data_3(a, c, d, [indices[0], indices[2], indices[3]]);
         (a, None, c, d, indices)
-    } else if !c.is_some_and(|data| !data.is_empty()) || indices[2].is_none() {
+    } else if c.map_or(true, |data| data.is_empty()) || indices[2].is_none() {
         let (a, b, d, indices) = smart_merge_data_3(a, b, d, [indices[0], indices[1], indices[3]]);
         (a, b, None, d, indices)
-    } else if !d.is_some_and(|data| !data.is_empty()) || indices[3].is_none() {
+    } else if d.map_or(true, |data| data.is_empty()) || indices[3].is_none() {
         let (a, b, c, indices) = smart_merge_data_3(a, b, c, [indices[0], indices[1], indices[2]]);
         (a, b, c, None, indices)
     } else if let Some((((a, indices_a), (b, indices_b)), ((c, indices_c), (d, indices_d)))) = a
@@ -199,6 +237,70 @@ pub(crate) fn smart_merge_data_4<A: Copy, B: Copy, C: Copy, D: Copy>(
         (None, None, None, None, None)
     }
 }
+pub(crate) fn smart_merge_data_5<A: Copy, B: Copy, C: Copy, D: Copy, E: Copy>(
+    a: Option<&[A]>,
+    b: Option<&[B]>,
+    c: Option<&[C]>,
+    d: Option<&[D]>,
+    e: Option<&[E]>,
+    indices: [Option<&[IndexType]>; 5],
+) -> (
+    OBoxArr<A>,
+    OBoxArr<B>,
+    OBoxArr<C>,
+    OBoxArr<D>,
+    OBoxArr<E>,
+    OBoxArr<IndexType>,
+) {
+    if !is_merge_needed(&indices) {
+        return (None, None, None, None, None, None);
+    }
+    if a.map_or(true, |data| data.is_empty()) || indices[0].is_none() {
+        let (b, c, d, e, indices) =
+            smart_merge_data_4(b, c, d, e, [indices[1], indices[2], indices[3], indices[4]]);
+        (None, b, c, d, e, indices)
+    } else if b.map_or(true, |data| data.is_empty()) || indices[1].is_none() {
+        let (a, c, d, e, indices) =
+            smart_merge_data_4(a, c, d, e, [indices[0], indices[2], indices[3], indices[4]]);
+        (a, None, c, d, e, indices)
+    } else if c.map_or(true, |data| data.is_empty()) || indices[2].is_none() {
+        let (a, b, d, e, indices) =
+            smart_merge_data_4(a, b, d, e, [indices[0], indices[1], indices[3], indices[4]]);
+        (a, b, None, d, e, indices)
+    } else if d.map_or(true, |data| data.is_empty()) || indices[3].is_none() {
+        let (a, b, c, e, indices) =
+            smart_merge_data_4(a, b, c, e, [indices[0], indices[1], indices[2], indices[4]]);
+        (a, b, c, None, e, indices)
+    } else if e.map_or(true, |data| data.is_empty()) || indices[4].is_none() {
+        let (a, b, c, d, indices) =
+            smart_merge_data_4(a, b, c, d, [indices[0], indices[1], indices[2], indices[3]]);
+        (a, b, c, d, None, indices)
+    } else if let (
+        Some((a, indices_a)),
+        Some((b, indices_b)),
+        Some((c, indices_c)),
+        Some((d, indices_d)),
+        Some((e, indices_e)),
+    ) = (
+        a.zip(indices[0]),
+        b.zip(indices[1]),
+        c.zip(indices[2]),
+        d.zip(indices[3]),
+        e.zip(indices[4]),
+    ) {
+        let (indices, a, b, c, d, e) = merge_data_5(
+            &[indices_a, indices_b, indices_c, indices_d, indices_e],
+            a,
+            b,
+            c,
+            d,
+            e,
+        );
+        (Some(a), Some(b), Some(c), Some(d), Some(e), Some(indices))
+    } else {
+        (None, None, None, None, None, None)
+    }
+}
 #[test]
 #[cfg(feature = "obj_import")]
 fn read_susan_obj() {
@@ -212,7 +314,7 @@ fn read_susan_obj() {
     tmf_mesh.unify_index_data();
     tmf_mesh.verify().unwrap();
     let mut out = std::fs::File::create("target/test_res/susan_unified.obj").unwrap();
-    tmf_mesh.write_obj_one(&mut out, &"SUSAN").unwrap();
+    tmf_mesh.write_obj_one(&mut out, "SUSAN").unwrap();
     assert_eq!(
         tmf_mesh.get_vertex_triangles().unwrap(),
         tmf_mesh.get_normal_triangles().unwrap()
@@ -222,9 +324,9 @@ fn read_susan_obj() {
         .write_tmf_one(&mut out, &TMFPrecisionInfo::default(), "SUSAN")
         .unwrap();
     let mut out = std::fs::File::open("target/test_res/susan_unified.tmf").unwrap();
-    let (r_mesh, name) = TMFMesh::read_tmf_one(&mut out).unwrap();
+    let (r_mesh, _) = TMFMesh::read_tmf_one(&mut out).unwrap();
     let mut out = std::fs::File::create("target/test_res/suan_unified_ftmf.obj").unwrap();
-    r_mesh.write_obj_one(&mut out, &"SUSAN").unwrap();
+    r_mesh.write_obj_one(&mut out, "SUSAN").unwrap();
 }
 #[test]
 #[cfg(feature = "obj_import")]
@@ -251,7 +353,7 @@ fn hand_optimized() {
         .write_tmf_one(&mut out, &tmf_prec, "SUSAN")
         .unwrap();
     let mut out = std::fs::File::open("target/test_res/susan_ho.tmf").unwrap();
-    let (r_mesh, name) = TMFMesh::read_tmf_one(&mut out).unwrap();
+    let (r_mesh, _) = TMFMesh::read_tmf_one(&mut out).unwrap();
     let mut out = std::fs::File::create("target/test_res/suan_ho_ftmf.obj").unwrap();
-    r_mesh.write_obj_one(&mut out, &"SUSAN").unwrap();
+    r_mesh.write_obj_one(&mut out, "SUSAN").unwrap();
 }
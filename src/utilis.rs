@@ -107,30 +107,6 @@ pub(crate) fn optimize_triangle_indices<T: Sized + Copy + std::fmt::Debug>(
         .collect();
     (new_indices, data_mapped.into())
 }
-fn calc_deltas(data: &[IndexType]) {
-    let mut deltas = Vec::with_capacity(data.len());
-    for index in 0..(data.len() - 1) {
-        deltas.push((data[index] as isize - data[index + 1] as isize).unsigned_abs());
-    }
-    let avg = (deltas.iter().sum::<usize>()) / data.len();
-    let mut delta_over_avg = 0;
-    deltas.iter().for_each(|delta| {
-        if *delta > avg {
-            delta_over_avg += 1
-        }
-    });
-    todo!("avg delta:{avg:?} delta_over_avg:{delta_over_avg}");
-}
-/*
-#[test]
-fn test_delta_encode(){
-    use crate::{TMFMesh, TMFPrecisionInfo};
-    crate::init_test_env();
-    let mut file = std::fs::File::open("testing/susan.obj").unwrap();
-    let (mut tmf_mesh, name) = TMFMesh::read_from_obj_one(&mut file).unwrap();
-    tmf_mesh.optimize();
-    delta_encode(tmf_mesh.get_vertex_triangles().unwrap());
-}*/
 #[test]
 fn test_opt_tris() {
     let test_data = ["A", "B", "C", "D", "E"];
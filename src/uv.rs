@@ -1,4 +1,5 @@
 use crate::read_extension::ReadExt;
+use crate::typed_mesh::{MeshFloat, Vector2Of};
 use crate::unaligned_rw::{UnalignedRWMode, UnalignedReader, UnalignedWriter};
 use crate::TMFImportError;
 use crate::MAX_SEG_SIZE;
@@ -51,28 +52,38 @@ pub fn save_uvs<W: Write>(
     Ok(())
 }
 pub fn read_uvs<R: Read>(reader: &mut R) -> Result<Box<[Vector2]>, TMFImportError> {
+    let mut uvs = Vec::new();
+    read_uvs_into(reader, &mut uvs)?;
+    Ok(uvs.into())
+}
+/// Reads uvs, appending them to *uvs*.
+pub(crate) fn read_uvs_into<F: MeshFloat, R: Read>(
+    reader: &mut R,
+    uvs: &mut Vec<Vector2Of<F>>,
+) -> Result<(), TMFImportError> {
     let precision = reader.read_u8()?;
     let count = reader.read_u64()?;
     if count > MAX_SEG_SIZE as u64 {
         return Err(TMFImportError::SegmentTooLong);
     }
     if precision == 0 {
-        return Ok(vec![(0.0, 0.0); count as usize].into());
+        uvs.resize(uvs.len() + count as usize, (F::default(), F::default()));
+        return Ok(());
     }
     if precision >= u64::BITS as u8 {
         return Err(TMFImportError::InvalidPrecision(precision));
     }
-    let mut uvs = Vec::with_capacity(count as usize);
-    let divisor = ((1_u64 << precision) - 1) as FloatType;
+    uvs.reserve(count as usize);
+    let divisor = F::from_u64((1_u64 << precision) - 1);
     let precision = UnalignedRWMode::precision_bits(precision);
     let mut reader = UnalignedReader::new(reader);
     for _ in 0..count {
         let (x, y) = reader.read2_unaligned(precision)?;
-        let x = (x as FloatType) / divisor;
-        let y = (y as FloatType) / divisor;
+        let x = F::from_u64(x) / divisor;
+        let y = F::from_u64(y) / divisor;
         uvs.push((x, y));
     }
-    Ok(uvs.into())
+    Ok(())
 }
 #[cfg(test)]
 mod test {
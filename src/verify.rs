@@ -19,6 +19,20 @@ pub enum TMFIntegrityStatus {
     UVArrayMissing,
     /// An *index* is larger than length of array *length*
     IndexOutsideUVArray(IndexType, IndexType),
+    /// A triangle range uses material *index*, but there are only *length* materials.
+    MaterialOutsideMaterialArray(IndexType, IndexType),
+    /// A material triangle range *start*..*end* is reversed or ends after the last triangle(*triangle count*).
+    MaterialRangeOutsideTriangles(IndexType, IndexType, IndexType),
+    /// Color array is not present despite being required(eg. Color triangle array is present);
+    ColorArrayMissing,
+    /// An *index* is larger than length of array *length*
+    IndexOutsideColorArray(IndexType, IndexType),
+    /// A triangle range belongs to group *index*, but there are only *length* groups.
+    GroupOutsideGroupArray(IndexType, IndexType),
+    /// A group triangle range *start*..*end* is reversed or ends after the last triangle(*triangle count*).
+    GroupRangeOutsideTriangles(IndexType, IndexType, IndexType),
+    /// A smoothing group triangle range *start*..*end* is reversed or ends after the last triangle(*triangle count*).
+    SmoothingGroupRangeOutsideTriangles(IndexType, IndexType, IndexType),
     /// More than one error
     CompositeError(Box<[Self]>),
 }
@@ -49,6 +63,31 @@ impl std::fmt::Display for TMFIntegrityStatus {
             }
             Self::UVOutsideRange(x, y) => write!(f, "UVOutsideRange{{x:{x},y:{y}}}"),
             Self::UVArrayMissing => write!(f, "UVArrayMissing"),
+            Self::ColorArrayMissing => write!(f, "ColorArrayMissing"),
+            Self::IndexOutsideColorArray(index, length) => {
+                write!(f, "IndexOutsideColorArray{{index:{index},length{length}}}")
+            }
+            Self::MaterialOutsideMaterialArray(index, length) => {
+                write!(
+                    f,
+                    "MaterialOutsideMaterialArray{{index:{index},length{length}}}"
+                )
+            }
+            Self::MaterialRangeOutsideTriangles(start, end, count) => write!(
+                f,
+                "MaterialRangeOutsideTriangles{{start:{start},end:{end},triangle_count:{count}}}"
+            ),
+            Self::GroupOutsideGroupArray(index, length) => {
+                write!(f, "GroupOutsideGroupArray{{index:{index},length{length}}}")
+            }
+            Self::GroupRangeOutsideTriangles(start, end, count) => write!(
+                f,
+                "GroupRangeOutsideTriangles{{start:{start},end:{end},triangle_count:{count}}}"
+            ),
+            Self::SmoothingGroupRangeOutsideTriangles(start, end, count) => write!(
+                f,
+                "SmoothingGroupRangeOutsideTriangles{{start:{start},end:{end},triangle_count:{count}}}"
+            ),
             Self::CompositeError(errors) => write_composite_error(f, errors),
         }
     }
@@ -147,6 +186,92 @@ fn verify_normals(mesh: &TMFMesh) -> Result<(), TMFIntegrityStatus> {
         }
     }
 }
+fn verify_colors(mesh: &TMFMesh) -> Result<(), TMFIntegrityStatus> {
+    match mesh.get_colors() {
+        None => {
+            if mesh.get_color_triangles().is_some() {
+                Err(TMFIntegrityStatus::ColorArrayMissing)
+            } else {
+                Ok(())
+            }
+        }
+        Some(colors) => match mesh.get_color_triangles() {
+            Some(triangles) => match indices_inside_array(triangles, colors.len() as IndexType) {
+                Some(index) => Err(TMFIntegrityStatus::IndexOutsideColorArray(
+                    index,
+                    colors.len() as IndexType,
+                )),
+                None => Ok(()),
+            },
+            None => Ok(()),
+        },
+    }
+}
+fn verify_materials(mesh: &TMFMesh) -> Result<(), TMFIntegrityStatus> {
+    let Some(materials) = mesh.get_materials() else {
+        return Ok(());
+    };
+    let material_count = materials.materials().len() as IndexType;
+    let triangle_count =
+        (mesh.get_vertex_triangles().map_or(0, <[IndexType]>::len) / 3) as IndexType;
+    for (material, range) in materials.material_groups() {
+        if *material >= material_count {
+            return Err(TMFIntegrityStatus::MaterialOutsideMaterialArray(
+                *material,
+                material_count,
+            ));
+        }
+        if range.start > range.end || range.end > triangle_count {
+            return Err(TMFIntegrityStatus::MaterialRangeOutsideTriangles(
+                range.start,
+                range.end,
+                triangle_count,
+            ));
+        }
+    }
+    Ok(())
+}
+fn verify_groups(mesh: &TMFMesh) -> Result<(), TMFIntegrityStatus> {
+    let Some(groups) = mesh.get_groups() else {
+        return Ok(());
+    };
+    let group_count = groups.groups().len() as IndexType;
+    let triangle_count =
+        (mesh.get_vertex_triangles().map_or(0, <[IndexType]>::len) / 3) as IndexType;
+    for (group, range) in groups.group_ranges() {
+        if *group >= group_count {
+            return Err(TMFIntegrityStatus::GroupOutsideGroupArray(
+                *group,
+                group_count,
+            ));
+        }
+        if range.start > range.end || range.end > triangle_count {
+            return Err(TMFIntegrityStatus::GroupRangeOutsideTriangles(
+                range.start,
+                range.end,
+                triangle_count,
+            ));
+        }
+    }
+    Ok(())
+}
+fn verify_smoothing_groups(mesh: &TMFMesh) -> Result<(), TMFIntegrityStatus> {
+    let Some(smoothing_groups) = mesh.get_smoothing_groups() else {
+        return Ok(());
+    };
+    let triangle_count =
+        (mesh.get_vertex_triangles().map_or(0, <[IndexType]>::len) / 3) as IndexType;
+    for (_, range) in smoothing_groups {
+        if range.start > range.end || range.end > triangle_count {
+            return Err(TMFIntegrityStatus::SmoothingGroupRangeOutsideTriangles(
+                range.start,
+                range.end,
+                triangle_count,
+            ));
+        }
+    }
+    Ok(())
+}
 pub(crate) fn verify_tmf_mesh(mesh: &TMFMesh) -> Result<(), TMFIntegrityStatus> {
     let mut errors = Vec::new();
     // Verify vertices
@@ -164,6 +289,26 @@ pub(crate) fn verify_tmf_mesh(mesh: &TMFMesh) -> Result<(), TMFIntegrityStatus>
     if let Err(err) = uvs_status {
         errors.push(err)
     };
+    //Verify colors
+    let colors_status = verify_colors(mesh);
+    if let Err(err) = colors_status {
+        errors.push(err)
+    };
+    //Verify materials
+    let materials_status = verify_materials(mesh);
+    if let Err(err) = materials_status {
+        errors.push(err)
+    };
+    //Verify groups
+    let groups_status = verify_groups(mesh);
+    if let Err(err) = groups_status {
+        errors.push(err)
+    };
+    //Verify smoothing groups
+    let smoothing_status = verify_smoothing_groups(mesh);
+    if let Err(err) = smoothing_status {
+        errors.push(err)
+    };
     let len = errors.len();
     match len {
         0 => Ok(()),
@@ -0,0 +1,476 @@
+// GPU buffers always store f32, while `FloatType` is f64 with `double_precision`, so casts are needed only in some configurations.
+#![allow(clippy::unnecessary_cast)]
+use crate::{Color, FloatType, IndexType, TMFMesh, Tangent, Vector2, Vector3};
+use std::collections::HashMap;
+/// Kind of data stored in a vertex attribute.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum VertexAttribute {
+    /// Position of the vertex, 3 components.
+    Position,
+    /// Normal of the vertex, 3 components.
+    Normal,
+    /// Uv coordinates of the vertex, 2 components.
+    Uv,
+    /// Tangent of the vertex, followed by its handedness, 4 components.
+    Tangent,
+    /// RGBA color of the vertex, 4 components.
+    Color,
+}
+impl VertexAttribute {
+    /// Number of components of this attribute.
+    #[must_use]
+    pub fn component_count(self) -> usize {
+        match self {
+            Self::Position | Self::Normal => 3,
+            Self::Uv => 2,
+            Self::Tangent | Self::Color => 4,
+        }
+    }
+}
+/// Format each component of a vertex attribute is stored in. All formats are little endian.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AttributeFormat {
+    /// 32 bit float.
+    F32,
+    /// 16 bit(half precision) float.
+    F16,
+    /// 8 bit unsigned integer, mapping <0,1> to <0,255>. Values outside <0,1> are clamped.
+    Unorm8,
+    /// 8 bit signed integer, mapping <-1,1> to <-127,127>. Values outside <-1,1> are clamped.
+    Snorm8,
+    /// 16 bit unsigned integer, mapping <0,1> to <0,65535>. Values outside <0,1> are clamped.
+    Unorm16,
+    /// 16 bit signed integer, mapping <-1,1> to <-32767,32767>. Values outside <-1,1> are clamped.
+    Snorm16,
+}
+impl AttributeFormat {
+    /// Size of one component in bytes.
+    #[must_use]
+    pub fn component_size(self) -> usize {
+        match self {
+            Self::F32 => 4,
+            Self::F16 | Self::Unorm16 | Self::Snorm16 => 2,
+            Self::Unorm8 | Self::Snorm8 => 1,
+        }
+    }
+    fn write(self, value: FloatType, target: &mut Vec<u8>) {
+        let value = value as f32;
+        match self {
+            Self::F32 => target.extend_from_slice(&value.to_le_bytes()),
+            Self::F16 => target.extend_from_slice(&f32_to_f16_bits(value).to_le_bytes()),
+            Self::Unorm8 => target.push((value.clamp(0.0, 1.0) * 255.0).round() as u8),
+            Self::Snorm8 => target
+                .extend_from_slice(&((value.clamp(-1.0, 1.0) * 127.0).round() as i8).to_le_bytes()),
+            Self::Unorm16 => target.extend_from_slice(
+                &((value.clamp(0.0, 1.0) * 65535.0).round() as u16).to_le_bytes(),
+            ),
+            Self::Snorm16 => target.extend_from_slice(
+                &((value.clamp(-1.0, 1.0) * 32767.0).round() as i16).to_le_bytes(),
+            ),
+        }
+    }
+}
+/// Converts *value* to bits of a half precision float, rounding to nearest even.
+fn f32_to_f16_bits(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xFF) as i32;
+    let mantissa = bits & 0x7F_FF_FF;
+    // Infinity or NaN
+    if exponent == 0xFF {
+        return sign | 0x7C00 | if mantissa == 0 { 0 } else { 0x200 };
+    }
+    let exponent = exponent - 127 + 15;
+    // Too large, becomes infinity.
+    if exponent >= 0x1F {
+        return sign | 0x7C00;
+    }
+    // Too small for a normal half, becomes subnormal or zero.
+    let (half, rest, halfway) = if exponent <= 0 {
+        if exponent < -10 {
+            return sign;
+        }
+        let mantissa = mantissa | 0x80_00_00;
+        let shift = (14 - exponent) as u32;
+        (
+            mantissa >> shift,
+            mantissa & ((1 << shift) - 1),
+            1 << (shift - 1),
+        )
+    } else {
+        (
+            ((exponent as u32) << 10) | (mantissa >> 13),
+            mantissa & 0x1FFF,
+            0x1000,
+        )
+    };
+    // Carry out of the mantissa correctly increments the exponent.
+    let half = if rest > halfway || (rest == halfway && half & 1 == 1) {
+        half + 1
+    } else {
+        half
+    };
+    sign | half as u16
+}
+/// Format of an index buffer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IndexFormat {
+    /// 16 bit indices, supporting up to 65536 vertices.
+    U16,
+    /// 32 bit indices.
+    U32,
+}
+/// Describes the layout of an interleaved vertex buffer: attributes, in order they are stored in each vertex, and their formats.
+/// Each attribute starts at an offset aligned to 4 bytes, so attributes which are not a multiple of 4 bytes long(e.g. a normal stored as 3 [`AttributeFormat::Snorm8`]) are padded.
+/// # Example
+/// ```
+/// # use tmf::{VertexLayout,VertexAttribute,AttributeFormat};
+/// let layout = VertexLayout::new()
+///     .with_attribute(VertexAttribute::Position, AttributeFormat::F32)
+///     .with_attribute(VertexAttribute::Normal, AttributeFormat::Snorm8)
+///     .with_attribute(VertexAttribute::Uv, AttributeFormat::F16);
+/// assert_eq!(layout.offset(VertexAttribute::Normal), Some(12));
+/// assert_eq!(layout.offset(VertexAttribute::Uv), Some(16));
+/// assert_eq!(layout.stride(), 20);
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VertexLayout {
+    attributes: Vec<(VertexAttribute, AttributeFormat)>,
+    index_format: Option<IndexFormat>,
+}
+impl VertexLayout {
+    /// Creates an empty layout.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Adds *attribute* stored in *format* at the end of each vertex. If *attribute* is already present, only its format is changed.
+    #[must_use]
+    pub fn with_attribute(mut self, attribute: VertexAttribute, format: AttributeFormat) -> Self {
+        match self
+            .attributes
+            .iter_mut()
+            .find(|(curr, _)| *curr == attribute)
+        {
+            Some((_, curr_format)) => *curr_format = format,
+            None => self.attributes.push((attribute, format)),
+        }
+        self
+    }
+    /// Makes indices be stored in *format*. By default, the smallest format able to index all vertices is used.
+    #[must_use]
+    pub fn with_index_format(mut self, format: IndexFormat) -> Self {
+        self.index_format = Some(format);
+        self
+    }
+    /// Returns attributes of each vertex and their formats, in order they are stored in.
+    #[must_use]
+    pub fn attributes(&self) -> &[(VertexAttribute, AttributeFormat)] {
+        &self.attributes
+    }
+    fn attribute_size(attribute: VertexAttribute, format: AttributeFormat) -> usize {
+        (attribute.component_count() * format.component_size()).next_multiple_of(4)
+    }
+    /// Returns the offset of *attribute* in each vertex, in bytes, or [`None`] if *attribute* is not a part of this layout.
+    #[must_use]
+    pub fn offset(&self, attribute: VertexAttribute) -> Option<usize> {
+        let mut offset = 0;
+        for (curr, format) in &self.attributes {
+            if *curr == attribute {
+                return Some(offset);
+            }
+            offset += Self::attribute_size(*curr, *format);
+        }
+        None
+    }
+    /// Returns the size of each vertex, in bytes.
+    #[must_use]
+    pub fn stride(&self) -> usize {
+        self.attributes
+            .iter()
+            .map(|(attribute, format)| Self::attribute_size(*attribute, *format))
+            .sum()
+    }
+}
+/// Indices of an [`InterleavedBuffers`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum IndexBuffer {
+    /// 16 bit indices.
+    U16(Box<[u16]>),
+    /// 32 bit indices.
+    U32(Box<[u32]>),
+}
+impl IndexBuffer {
+    /// Returns the format of the indices.
+    #[must_use]
+    pub fn format(&self) -> IndexFormat {
+        match self {
+            Self::U16(_) => IndexFormat::U16,
+            Self::U32(_) => IndexFormat::U32,
+        }
+    }
+    /// Returns the number of indices.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match self {
+            Self::U16(indices) => indices.len(),
+            Self::U32(indices) => indices.len(),
+        }
+    }
+    /// Checks if there are no indices.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Returns the indices as little endian bytes, ready for upload.
+    #[must_use]
+    pub fn to_le_bytes(&self) -> Box<[u8]> {
+        match self {
+            Self::U16(indices) => indices
+                .iter()
+                .flat_map(|index| index.to_le_bytes())
+                .collect(),
+            Self::U32(indices) => indices
+                .iter()
+                .flat_map(|index| index.to_le_bytes())
+                .collect(),
+        }
+    }
+}
+/// Interleaved vertex buffer and index buffer of a mesh, created by [`TMFMesh::get_interleaved_buffers`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InterleavedBuffers {
+    /// Vertex data, laid out as described by the [`VertexLayout`] used to create it.
+    pub vertices: Box<[u8]>,
+    /// Size of each vertex, in bytes.
+    pub stride: usize,
+    /// Indices of vertices creating triangles.
+    pub indices: IndexBuffer,
+}
+impl InterleavedBuffers {
+    /// Returns the number of vertices.
+    #[must_use]
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len().checked_div(self.stride).unwrap_or(0)
+    }
+}
+/// Data of a vertex attribute, and triangles indexing it.
+enum AttributeSource<'a> {
+    Vector3(&'a [Vector3]),
+    Vector2(&'a [Vector2]),
+    Tangent(&'a [Tangent]),
+    Color(&'a [Color]),
+}
+impl AttributeSource<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Self::Vector3(data) => data.len(),
+            Self::Vector2(data) => data.len(),
+            Self::Tangent(data) => data.len(),
+            Self::Color(data) => data.len(),
+        }
+    }
+    fn components(&self, index: usize) -> [FloatType; 4] {
+        match self {
+            Self::Vector3(data) => {
+                let (x, y, z) = data[index];
+                [x, y, z, 0.0]
+            }
+            Self::Vector2(data) => {
+                let (x, y) = data[index];
+                [x, y, 0.0, 0.0]
+            }
+            Self::Tangent(data) => {
+                let ((x, y, z), w) = data[index];
+                [x, y, z, w]
+            }
+            Self::Color(data) => {
+                let (r, g, b, a) = data[index];
+                [r, g, b, a]
+            }
+        }
+    }
+}
+fn attribute_source(
+    mesh: &TMFMesh,
+    attribute: VertexAttribute,
+) -> Option<(AttributeSource<'_>, &[IndexType])> {
+    Some(match attribute {
+        VertexAttribute::Position => (
+            AttributeSource::Vector3(mesh.get_vertices()?),
+            mesh.get_vertex_triangles()?,
+        ),
+        VertexAttribute::Normal => (
+            AttributeSource::Vector3(mesh.get_normals()?),
+            mesh.get_normal_triangles()?,
+        ),
+        VertexAttribute::Uv => (
+            AttributeSource::Vector2(mesh.get_uvs()?),
+            mesh.get_uv_triangles()?,
+        ),
+        VertexAttribute::Tangent => (
+            AttributeSource::Tangent(mesh.get_tangents()?),
+            mesh.get_tangent_triangles()?,
+        ),
+        VertexAttribute::Color => (
+            AttributeSource::Color(mesh.get_colors()?),
+            mesh.get_color_triangles()?,
+        ),
+    })
+}
+/// Creates interleaved vertex and index buffers of *mesh*. Each unique combination of attribute indices becomes one vertex.
+pub(crate) fn interleave(mesh: &TMFMesh, layout: &VertexLayout) -> Option<InterleavedBuffers> {
+    const MAX_ATTRIBUTES: usize = 5;
+    let mut sources = Vec::with_capacity(layout.attributes().len());
+    for (attribute, format) in layout.attributes() {
+        let (source, triangles) = attribute_source(mesh, *attribute)?;
+        sources.push((source, triangles, *attribute, *format));
+    }
+    let index_count = sources.first()?.1.len();
+    if sources
+        .iter()
+        .any(|(_, triangles, _, _)| triangles.len() != index_count)
+    {
+        return None;
+    }
+    let stride = layout.stride();
+    let mut combos: HashMap<[IndexType; MAX_ATTRIBUTES], u32> = HashMap::new();
+    let mut vertices = Vec::new();
+    let mut indices = Vec::with_capacity(index_count);
+    for index in 0..index_count {
+        let mut combo = [0; MAX_ATTRIBUTES];
+        for (combo_index, (source, triangles, _, _)) in combo.iter_mut().zip(&sources) {
+            *combo_index = triangles[index];
+            if triangles[index] as usize >= source.len() {
+                return None;
+            }
+        }
+        let next_id = combos.len();
+        let id = *combos.entry(combo).or_insert_with(|| {
+            for ((source, _, attribute, format), attribute_index) in sources.iter().zip(combo) {
+                let start = vertices.len();
+                let components = source.components(attribute_index as usize);
+                for component in &components[..attribute.component_count()] {
+                    format.write(*component, &mut vertices);
+                }
+                // Padding to the next attribute.
+                vertices.resize(start + VertexLayout::attribute_size(*attribute, *format), 0);
+            }
+            next_id as u32
+        });
+        indices.push(id);
+    }
+    debug_assert_eq!(vertices.len(), combos.len() * stride);
+    let index_format = match layout.index_format {
+        Some(format) => format,
+        None if combos.len() <= u16::MAX as usize + 1 => IndexFormat::U16,
+        None => IndexFormat::U32,
+    };
+    let indices = match index_format {
+        IndexFormat::U16 => IndexBuffer::U16(
+            indices
+                .into_iter()
+                .map(u16::try_from)
+                .collect::<Result<_, _>>()
+                .ok()?,
+        ),
+        IndexFormat::U32 => IndexBuffer::U32(indices.into()),
+    };
+    Some(InterleavedBuffers {
+        vertices: vertices.into(),
+        stride,
+        indices,
+    })
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn f16_conversion() {
+        assert_eq!(f32_to_f16_bits(0.0), 0x0000);
+        assert_eq!(f32_to_f16_bits(-0.0), 0x8000);
+        assert_eq!(f32_to_f16_bits(1.0), 0x3C00);
+        assert_eq!(f32_to_f16_bits(0.5), 0x3800);
+        assert_eq!(f32_to_f16_bits(-2.0), 0xC000);
+        assert_eq!(f32_to_f16_bits(65504.0), 0x7BFF);
+        assert_eq!(f32_to_f16_bits(1e6), 0x7C00);
+        assert_eq!(f32_to_f16_bits(f32::NEG_INFINITY), 0xFC00);
+        assert_eq!(f32_to_f16_bits(f32::NAN) & 0x7C00, 0x7C00);
+        // Smallest subnormal
+        assert_eq!(f32_to_f16_bits(2.0_f32.powi(-24)), 0x0001);
+        assert_eq!(f32_to_f16_bits(2.0_f32.powi(-26)), 0x0000);
+        // 1 + 2^-11 is halfway between 1 and the next half, and rounds to even.
+        assert_eq!(f32_to_f16_bits(1.0 + 2.0_f32.powi(-11)), 0x3C00);
+        assert_eq!(f32_to_f16_bits(1.0 + 3.0 * 2.0_f32.powi(-11)), 0x3C02);
+    }
+    #[test]
+    fn interleave_quad() {
+        let mut mesh = TMFMesh::empty();
+        mesh.set_vertices([
+            (0.0, 0.0, 0.0),
+            (1.0, 0.0, 0.0),
+            (1.0, 1.0, 0.0),
+            (0.0, 1.0, 0.0),
+        ]);
+        mesh.set_vertex_triangles([0, 1, 2, 0, 2, 3]);
+        mesh.set_normals([(0.0, 0.0, 1.0)]);
+        mesh.set_normal_triangles([0, 0, 0, 0, 0, 0]);
+        mesh.set_uvs([(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        mesh.set_uv_triangles([0, 1, 2, 0, 2, 3]);
+        let layout = VertexLayout::new()
+            .with_attribute(VertexAttribute::Position, AttributeFormat::F32)
+            .with_attribute(VertexAttribute::Normal, AttributeFormat::Snorm8)
+            .with_attribute(VertexAttribute::Uv, AttributeFormat::Unorm16);
+        let buffers = interleave(&mesh, &layout).unwrap();
+        assert_eq!(buffers.stride, 20);
+        assert_eq!(buffers.vertex_count(), 4);
+        assert_eq!(buffers.indices, IndexBuffer::U16([0, 1, 2, 0, 2, 3].into()));
+        // Third vertex: position (1,1,0), normal (0,0,1) and uv (1,1).
+        let vertex = &buffers.vertices[2 * 20..3 * 20];
+        assert_eq!(&vertex[0..4], &1.0_f32.to_le_bytes());
+        assert_eq!(&vertex[4..8], &1.0_f32.to_le_bytes());
+        assert_eq!(&vertex[8..12], &0.0_f32.to_le_bytes());
+        assert_eq!(&vertex[12..16], &[0, 0, 127, 0]);
+        assert_eq!(&vertex[16..20], &[0xFF, 0xFF, 0xFF, 0xFF]);
+        let layout = layout.with_index_format(IndexFormat::U32);
+        let buffers = interleave(&mesh, &layout).unwrap();
+        assert_eq!(buffers.indices.format(), IndexFormat::U32);
+        assert_eq!(buffers.indices.to_le_bytes().len(), 6 * 4);
+        // Missing data
+        let layout = layout.with_attribute(VertexAttribute::Tangent, AttributeFormat::F32);
+        assert!(interleave(&mesh, &layout).is_none());
+    }
+    #[test]
+    #[cfg(feature = "obj_import")]
+    fn interleave_susan() {
+        let mut file = std::fs::File::open("testing/susan.obj").unwrap();
+        let (mut mesh, _) = TMFMesh::read_from_obj_one(&mut file).unwrap();
+        let layout = VertexLayout::new()
+            .with_attribute(VertexAttribute::Position, AttributeFormat::F32)
+            .with_attribute(VertexAttribute::Normal, AttributeFormat::F32)
+            .with_attribute(VertexAttribute::Uv, AttributeFormat::F32);
+        let buffers = interleave(&mesh, &layout).unwrap();
+        let IndexBuffer::U16(indices) = &buffers.indices else {
+            panic!("Susan should use 16 bit indices");
+        };
+        let vertex_buffer = mesh.get_vertex_buffer().unwrap();
+        let uv_buffer = mesh.get_uv_buffer().unwrap();
+        for ((index, vertex), uv) in indices
+            .iter()
+            .zip(vertex_buffer.iter())
+            .zip(uv_buffer.iter())
+        {
+            let vertex_data = &buffers.vertices[*index as usize * buffers.stride..];
+            let read = |offset: usize| {
+                f32::from_le_bytes(vertex_data[offset..offset + 4].try_into().unwrap())
+            };
+            // Attributes are saved as f32, so compare with data rounded the same way.
+            let (x, y, z) = *vertex;
+            assert_eq!((read(0), read(4), read(8)), (x as f32, y as f32, z as f32));
+            assert_eq!((read(24), read(28)), (uv.0 as f32, uv.1 as f32));
+        }
+        // Vertices are shared the same way as after unifying index data.
+        mesh.unify_index_data();
+        assert_eq!(buffers.vertex_count(), mesh.get_vertices().unwrap().len());
+    }
+}
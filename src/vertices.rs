@@ -1,4 +1,5 @@
 use crate::read_extension::ReadExt;
+use crate::typed_mesh::{MeshFloat, Vector3Of};
 use crate::unaligned_rw::{UnalignedRWMode, UnalignedReader, UnalignedWriter};
 use crate::TMFImportError;
 use crate::MAX_SEG_SIZE;
@@ -103,14 +104,23 @@ pub fn save_tmf_vertices<W: Write>(
     Ok(())
 }
 pub fn read_tmf_vertices<R: Read>(reader: &mut R) -> Result<Box<[Vector3]>, TMFImportError> {
+    let mut vertices = Vec::new();
+    read_tmf_vertices_into(reader, &mut vertices)?;
+    Ok(vertices.into())
+}
+/// Reads vertices, appending them to *vertices*.
+pub(crate) fn read_tmf_vertices_into<F: MeshFloat, R: Read>(
+    reader: &mut R,
+    vertices: &mut Vec<Vector3Of<F>>,
+) -> Result<(), TMFImportError> {
     let vertex_count = reader.read_u64()? as usize;
     // Read data bounding box
-    let min_x = reader.read_f64()? as FloatType;
-    let max_x = reader.read_f64()? as FloatType;
-    let min_y = reader.read_f64()? as FloatType;
-    let max_y = reader.read_f64()? as FloatType;
-    let min_z = reader.read_f64()? as FloatType;
-    let max_z = reader.read_f64()? as FloatType;
+    let min_x = F::from_f64(reader.read_f64()?);
+    let max_x = F::from_f64(reader.read_f64()?);
+    let min_y = F::from_f64(reader.read_f64()?);
+    let max_y = F::from_f64(reader.read_f64()?);
+    let min_z = F::from_f64(reader.read_f64()?);
+    let max_z = F::from_f64(reader.read_f64()?);
     // Read precision
     let prec_x = reader.read_u8()?;
     if prec_x >= u64::BITS as u8 {
@@ -125,9 +135,9 @@ pub fn read_tmf_vertices<R: Read>(reader: &mut R) -> Result<Box<[Vector3]>, TMFI
         return Err(TMFImportError::InvalidPrecision(prec_z));
     }
     // Calculate float read divisor
-    let div_x = ((1_u64 << prec_x) - 1) as FloatType;
-    let div_y = ((1_u64 << prec_y) - 1) as FloatType;
-    let div_z = ((1_u64 << prec_z) - 1) as FloatType;
+    let div_x = F::from_u64((1_u64 << prec_x) - 1);
+    let div_y = F::from_u64((1_u64 << prec_y) - 1);
+    let div_z = F::from_u64((1_u64 << prec_z) - 1);
     // Create unaligned rw modes
     let prec_x = UnalignedRWMode::precision_bits(prec_x);
     let prec_y = UnalignedRWMode::precision_bits(prec_y);
@@ -140,22 +150,22 @@ pub fn read_tmf_vertices<R: Read>(reader: &mut R) -> Result<Box<[Vector3]>, TMFI
     if vertex_count > MAX_SEG_SIZE {
         return Err(TMFImportError::SegmentTooLong);
     }
-    let mut vertices = Vec::with_capacity(vertex_count);
+    vertices.reserve(vertex_count);
     let mut reader = UnalignedReader::new(reader);
     for _ in 0..vertex_count {
         //let (x,y) = reader.read_pair_unaligned(prec_x,prec_y)?;
         let x = reader.read_unaligned(prec_x)?;
         let y = reader.read_unaligned(prec_y)?;
         let z = reader.read_unaligned(prec_z)?;
-        let x = (x as FloatType) / div_x;
-        let y = (y as FloatType) / div_y;
-        let z = (z as FloatType) / div_z;
+        let x = F::from_u64(x) / div_x;
+        let y = F::from_u64(y) / div_y;
+        let z = F::from_u64(z) / div_z;
         let x = x * sx + min_x;
         let y = y * sy + min_y;
         let z = z * sz + min_z;
         vertices.push((x, y, z));
     }
-    Ok(vertices.into())
+    Ok(())
 }
 //Those issues wont happen.
 #[allow(clippy::cast_possible_truncation)]
@@ -193,29 +203,6 @@ pub fn save_triangles<W: Write>(
     }
     writer.flush()
 }
-pub(crate) fn read_triangles<R: Read>(
-    reader: &mut R,
-    ctx: &crate::tmf_importer::TMFImportContext,
-) -> Result<Box<[IndexType]>, TMFImportError> {
-    let precision = reader.read_u8()?;
-    let length = reader.read_u64()?;
-    let min = ctx.read_traingle_min(reader)?;
-    if length > MAX_SEG_SIZE as u64 {
-        return Err(TMFImportError::SegmentTooLong);
-    }
-    let mut res = Vec::with_capacity(length as usize);
-    let precision = UnalignedRWMode::precision_bits(precision);
-    let mut reader = UnalignedReader::new(reader);
-    for _ in 0..(length / 2) {
-        let (i1, i2) = reader.read2_unaligned(precision)?;
-        res.push((i1 + min) as IndexType);
-        res.push((i2 + min) as IndexType);
-    }
-    for _i in 0..length % 2 {
-        res.push((reader.read_unaligned(precision)? + min) as IndexType);
-    }
-    Ok(res.into())
-}
 #[cfg(test)]
 mod testing {
     fn dst(a: Vector3, b: Vector3) -> FloatType {